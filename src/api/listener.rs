@@ -14,8 +14,8 @@
 
 use crate::api::router::{build_foreign_api_router, build_owner_api_router};
 use crate::broker::{
-	Controller, GrinboxPublisher, GrinboxSubscriber, KeybasePublisher, KeybaseSubscriber,
-	Publisher, Subscriber,
+	Controller, GrinboxIdentity, GrinboxPublisher, GrinboxSubscriber, KeybasePublisher,
+	KeybaseSubscriber, Publisher, Subscriber,
 };
 use crate::common::hasher::derive_address_key;
 use crate::common::{Arc, Keychain, Mutex, MutexGuard};
@@ -33,6 +33,15 @@ pub trait Listener: Sync + Send + 'static {
 	fn interface(&self) -> ListenerInterface;
 	fn address(&self) -> String;
 	fn publish(&self, slate: &VersionedSlate, to: &String) -> Result<(), Error>;
+	/// Publish from the address with this index, if the listener has several
+	fn publish_from(
+		&self,
+		slate: &VersionedSlate,
+		to: &String,
+		_index: Option<u32>,
+	) -> Result<(), Error> {
+		self.publish(slate, to)
+	}
 	fn stop(self: Box<Self>) -> Result<(), Error>;
 }
 
@@ -76,6 +85,16 @@ impl Listener for GrinboxListener {
 		self.publisher.post_slate(slate, &address)
 	}
 
+	fn publish_from(
+		&self,
+		slate: &VersionedSlate,
+		to: &String,
+		index: Option<u32>,
+	) -> Result<(), Error> {
+		let address = GrinboxAddress::from_str(to)?;
+		self.publisher.post_slate_from(slate, &address, index)
+	}
+
 	fn stop(self: Box<Self>) -> Result<(), Error> {
 		let s = *self;
 		s.subscriber.stop();
@@ -176,19 +195,23 @@ where
 	C: NodeClient,
 	K: Keychain,
 {
-	let index = c.config.grinbox_address_index();
+	let domain = c.config.grinbox_domain.clone();
+	let port = c.config.grinbox_port;
+	let indices = c.grinbox_indices()?;
 	let keychain = c.backend()?.keychain();
-	let sec_key = derive_address_key(keychain, index)?;
-	let pub_key = PublicKey::from_secret_key(keychain.secp(), &sec_key)?;
-
-	let address = GrinboxAddress::new(
-		pub_key,
-		Some(c.config.grinbox_domain.clone()),
-		c.config.grinbox_port,
-	);
+	let mut identities = Vec::with_capacity(indices.len());
+	for index in indices {
+		let secret_key = derive_address_key(keychain, index)?;
+		let public_key = PublicKey::from_secret_key(keychain.secp(), &secret_key)?;
+		identities.push(GrinboxIdentity {
+			index,
+			address: GrinboxAddress::new(public_key, Some(domain.clone()), port),
+			secret_key,
+		});
+	}
+	let address = identities[0].address.clone();
 
-	let publisher =
-		GrinboxPublisher::new(&address, &sec_key, c.config.grinbox_protocol_unsecure())?;
+	let publisher = GrinboxPublisher::new(identities, c.config.grinbox_protocol_unsecure())?;
 
 	let subscriber = GrinboxSubscriber::new(&publisher)?;
 
@@ -196,7 +219,7 @@ where
 	let mut csubscriber = subscriber.clone();
 	let cpublisher = publisher.clone();
 	let handle = spawn(move || {
-		let controller = Controller::new(&caddress.stripped(), container, cpublisher)
+		let controller = Controller::new(&caddress.stripped(), "grinbox", container, cpublisher)
 			.expect("could not start grinbox controller!");
 		csubscriber
 			.start(controller)
@@ -221,13 +244,14 @@ where
 	C: NodeClient,
 	K: Keychain,
 {
-	let subscriber = KeybaseSubscriber::new()?;
-	let publisher = KeybasePublisher::new(c.config.default_keybase_ttl.clone())?;
+	let binary = c.config.keybase_binary();
+	let subscriber = KeybaseSubscriber::new(&binary)?;
+	let publisher = KeybasePublisher::new(&binary, c.config.default_keybase_ttl.clone())?;
 
 	let mut csubscriber = subscriber.clone();
 	let cpublisher = publisher.clone();
 	let handle = spawn(move || {
-		let controller = Controller::new("keybase", container, cpublisher)
+		let controller = Controller::new("keybase", "keybase", container, cpublisher)
 			.expect("could not start keybase controller!");
 		csubscriber
 			.start(controller)
@@ -24,11 +24,15 @@ use gotham::pipeline::single::single_pipeline;
 use gotham::router::builder::*;
 use gotham::router::Router;
 use gotham::state::{FromState, State};
+use hyper::header::{HeaderValue, CACHE_CONTROL};
 use hyper::{Body, Chunk, HeaderMap, Method, Response, StatusCode, Uri, Version};
 use log::trace;
 use mime::Mime;
 use serde_json::{json, Value};
+use std::io;
 use std::panic::RefUnwindSafe;
+use std::time::{Duration, Instant};
+use tokio::timer::Interval;
 
 use super::rpc::{ForeignRpc, OwnerRpc};
 use crate::api::auth::BasicAuthMiddleware;
@@ -38,6 +42,9 @@ use crate::wallet::api::{Foreign, Owner};
 use crate::wallet::types::{Arc, Mutex, NodeClient, WalletBackend};
 use crate::wallet::Container;
 
+/// Interval of the comments that keep idle event streams open
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(30);
+
 pub struct ForeignApiMiddleware<W, C, K>
 where
 	W: WalletBackend<C, K>,
@@ -251,9 +258,42 @@ where
 		route
 			.request(vec![Method::POST], "/v2/owner")
 			.to(owner_api_handler::<W, C, K>);
+		route
+			.get("/v2/owner/events")
+			.to(owner_events_handler::<W, C, K>);
 	})
 }
 
+/// Server-Sent Events stream of the wallet events, open until the client disconnects
+fn owner_events_handler<W, C, K>(state: State) -> (State, Response<Body>)
+where
+	W: WalletBackend<C, K>,
+	C: NodeClient,
+	K: Keychain,
+{
+	let api = Owner::<W, C, K>::borrow_from(&state);
+	let events = api.subscribe_events().map(|e| {
+		let data = serde_json::to_string(&e).unwrap_or_default();
+		Chunk::from(format!("event: {}\ndata: {}\n\n", e.event, data))
+	});
+	let keepalive = Interval::new(Instant::now() + KEEPALIVE_INTERVAL, KEEPALIVE_INTERVAL)
+		.map(|_| Chunk::from(": keepalive\n\n"))
+		.map_err(|_| ());
+	let stream = events
+		.select(keepalive)
+		.map_err(|_| io::Error::new(io::ErrorKind::Other, "event stream closed"));
+
+	let mut res = create_response(
+		&state,
+		StatusCode::OK,
+		mime::TEXT_EVENT_STREAM,
+		Body::wrap_stream(stream),
+	);
+	res.headers_mut()
+		.insert(CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+	(state, res)
+}
+
 fn owner_api_handler<W, C, K>(mut state: State) -> Box<HandlerFuture>
 where
 	W: WalletBackend<C, K>,
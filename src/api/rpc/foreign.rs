@@ -17,7 +17,9 @@
 use crate::common::Keychain;
 use crate::wallet::api::types::VersionInfo;
 use crate::wallet::api::Foreign;
-use crate::wallet::types::{BlockFees, CbData, NodeClient, Slate, VersionedSlate, WalletBackend};
+use crate::wallet::types::{
+	BlockFees, CbData, NodeClient, Slate, VersionedSlate, WalletBackend, WalletEventType,
+};
 use crate::wallet::ErrorKind;
 use easy_jsonrpc_mw;
 
@@ -74,9 +76,20 @@ where
 			dest_acct_name.as_ref().map(String::as_str),
 			Some("http".to_owned()),
 			message,
+			None,
 		)
 		.map_err(|e| ErrorKind::GenericError(e.to_string()))?;
 
+		// The signed slate goes back in the response
+		let address = Some("http".to_owned());
+		Foreign::notify(
+			self,
+			WalletEventType::SlateReceived,
+			&slate,
+			address.clone(),
+		);
+		Foreign::notify(self, WalletEventType::ResponseSent, &slate, address);
+
 		Ok(VersionedSlate::into_version(slate, version))
 	}
 
@@ -18,7 +18,8 @@ use crate::common::Keychain;
 use crate::wallet::api::Owner;
 use crate::wallet::types::{
 	AcctPathMapping, Identifier, InitTxArgs, NodeClient, NodeHeightResult, OutputCommitMapping,
-	Slate, Transaction, TxLogEntry, WalletBackend, WalletInfo,
+	PaymentRequest, ScanProgress, ScanStart, SignedMessage, Slate, Transaction, TxLogEntry,
+	WalletBackend, WalletInfo,
 };
 use crate::wallet::ErrorKind;
 use easy_jsonrpc_mw;
@@ -59,9 +60,34 @@ pub trait OwnerRpc {
 	fn cancel_tx(&self, tx_id: Option<u32>, tx_slate_id: Option<Uuid>) -> Result<(), ErrorKind>;
 	fn get_stored_tx(&self, slate_id: &Uuid) -> Result<Option<Transaction>, ErrorKind>;
 	fn verify_slate_messages(&self, slate: &Slate) -> Result<(), ErrorKind>;
-	fn restore(&self) -> Result<(), ErrorKind>;
-	fn check_repair(&self, delete_unconfirmed: bool) -> Result<(), ErrorKind>;
+	fn restore(&self, start: Option<ScanStart>) -> Result<(), ErrorKind>;
+	fn check_repair(
+		&self,
+		delete_unconfirmed: bool,
+		start: Option<ScanStart>,
+	) -> Result<(), ErrorKind>;
+	fn scan_progress(&self) -> Result<ScanProgress, ErrorKind>;
+	fn cancel_scan(&self) -> Result<bool, ErrorKind>;
 	fn node_height(&self) -> Result<NodeHeightResult, ErrorKind>;
+	fn create_payment_request(
+		&self,
+		amount: u64,
+		memo: Option<String>,
+		expiry_minutes: Option<u64>,
+	) -> Result<PaymentRequest, ErrorKind>;
+	fn retrieve_payment_requests(&self) -> Result<Vec<PaymentRequest>, ErrorKind>;
+	fn cancel_payment_request(&self, address_index: u32) -> Result<(), ErrorKind>;
+	fn sign_message(
+		&self,
+		message: String,
+		address_index: Option<u32>,
+	) -> Result<SignedMessage, ErrorKind>;
+	fn verify_message(
+		&self,
+		address: String,
+		message: String,
+		signature: String,
+	) -> Result<bool, ErrorKind>;
 }
 
 impl<W, C, K> OwnerRpc for Owner<W, C, K>
@@ -158,16 +184,69 @@ where
 			.map_err(|e| ErrorKind::GenericError(e.to_string()))
 	}
 
-	fn restore(&self) -> Result<(), ErrorKind> {
-		Owner::restore(self).map_err(|e| ErrorKind::GenericError(e.to_string()))
+	fn restore(&self, start: Option<ScanStart>) -> Result<(), ErrorKind> {
+		Owner::restore(self, start.unwrap_or(ScanStart::Resume))
+			.map_err(|e| ErrorKind::GenericError(e.to_string()))
 	}
 
-	fn check_repair(&self, delete_unconfirmed: bool) -> Result<(), ErrorKind> {
-		Owner::check_repair(self, delete_unconfirmed)
+	fn check_repair(
+		&self,
+		delete_unconfirmed: bool,
+		start: Option<ScanStart>,
+	) -> Result<(), ErrorKind> {
+		Owner::check_repair(self, delete_unconfirmed, start.unwrap_or(ScanStart::Resume))
 			.map_err(|e| ErrorKind::GenericError(e.to_string()))
 	}
 
+	fn scan_progress(&self) -> Result<ScanProgress, ErrorKind> {
+		Ok(Owner::scan_progress(self))
+	}
+
+	fn cancel_scan(&self) -> Result<bool, ErrorKind> {
+		Ok(Owner::cancel_scan(self))
+	}
+
 	fn node_height(&self) -> Result<NodeHeightResult, ErrorKind> {
 		Owner::node_height(self).map_err(|e| ErrorKind::GenericError(e.to_string()))
 	}
+
+	fn create_payment_request(
+		&self,
+		amount: u64,
+		memo: Option<String>,
+		expiry_minutes: Option<u64>,
+	) -> Result<PaymentRequest, ErrorKind> {
+		Owner::create_payment_request(self, amount, memo, expiry_minutes)
+			.map_err(|e| ErrorKind::GenericError(e.to_string()))
+	}
+
+	fn retrieve_payment_requests(&self) -> Result<Vec<PaymentRequest>, ErrorKind> {
+		Owner::update_payment_requests(self)
+			.and_then(|_| Owner::payment_requests(self))
+			.map_err(|e| ErrorKind::GenericError(e.to_string()))
+	}
+
+	fn cancel_payment_request(&self, address_index: u32) -> Result<(), ErrorKind> {
+		Owner::cancel_payment_request(self, address_index)
+			.map_err(|e| ErrorKind::GenericError(e.to_string()))
+	}
+
+	fn sign_message(
+		&self,
+		message: String,
+		address_index: Option<u32>,
+	) -> Result<SignedMessage, ErrorKind> {
+		Owner::sign_message(self, &message, address_index)
+			.map_err(|e| ErrorKind::GenericError(e.to_string()))
+	}
+
+	fn verify_message(
+		&self,
+		address: String,
+		message: String,
+		signature: String,
+	) -> Result<bool, ErrorKind> {
+		Owner::verify_message(self, &address, &message, &signature)
+			.map_err(|e| ErrorKind::GenericError(e.to_string()))
+	}
 }
@@ -17,63 +17,84 @@ use super::types::{CloseReason, Controller, Publisher, Subscriber, SubscriptionH
 use crate::cli_message;
 use crate::common::crypto::{sign_challenge, Hex, SecretKey};
 use crate::common::message::EncryptedMessage;
+use crate::common::socks;
 use crate::common::{Arc, ErrorKind, Keychain, Mutex, Result};
 use crate::contacts::{Address, GrinboxAddress, DEFAULT_GRINBOX_PORT};
 use crate::wallet::types::{NodeClient, TxProof, VersionedSlate, WalletBackend};
 use colored::Colorize;
 use log::error;
-use ws::util::Token;
+use native_tls::{TlsConnector, TlsStream};
+use url::Url;
+use ws::util::{TcpStream, Token};
 use ws::{
 	connect, CloseCode, Error as WsError, ErrorKind as WsErrorKind, Handler, Handshake, Message,
-	Result as WsResult, Sender,
+	Request, Result as WsResult, Sender,
 };
 
 const KEEPALIVE_TOKEN: Token = Token(1);
 const KEEPALIVE_INTERVAL_MS: u64 = 30_000;
 
+/// Grinbox address the wallet listens on, with the index it was derived at
+#[derive(Clone)]
+pub struct GrinboxIdentity {
+	pub index: u32,
+	pub address: GrinboxAddress,
+	pub secret_key: SecretKey,
+}
+
 #[derive(Clone)]
 pub struct GrinboxPublisher {
-	address: GrinboxAddress,
+	/// Addresses to listen on, slates are sent from the first one
+	identities: Vec<GrinboxIdentity>,
 	broker: GrinboxBroker,
-	secret_key: SecretKey,
 }
 
 impl GrinboxPublisher {
-	pub fn new(
-		address: &GrinboxAddress,
-		secret_key: &SecretKey,
-		protocol_unsecure: bool,
-	) -> Result<Self> {
+	pub fn new(identities: Vec<GrinboxIdentity>, protocol_unsecure: bool) -> Result<Self> {
+		if identities.is_empty() {
+			return Err(
+				ErrorKind::GenericError("No grinbox address to listen on".to_string()).into(),
+			);
+		}
 		Ok(Self {
-			address: address.clone(),
+			identities,
 			broker: GrinboxBroker::new(protocol_unsecure)?,
-			secret_key: secret_key.clone(),
 		})
 	}
 }
 
 impl Publisher for GrinboxPublisher {
 	fn post_slate(&self, slate: &VersionedSlate, to: &dyn Address) -> Result<()> {
+		self.post_slate_from(slate, to, None)
+	}
+
+	fn post_slate_from(
+		&self,
+		slate: &VersionedSlate,
+		to: &dyn Address,
+		index: Option<u32>,
+	) -> Result<()> {
 		let to = GrinboxAddress::from_str(&to.to_string())?;
+		let from = index
+			.and_then(|i| self.identities.iter().find(|id| id.index == i))
+			.unwrap_or(&self.identities[0]);
 		self.broker
-			.post_slate(slate, &to, &self.address, &self.secret_key)?;
+			.post_slate(slate, &to, &from.address, &from.secret_key)?;
 		Ok(())
 	}
 }
 
 #[derive(Clone)]
 pub struct GrinboxSubscriber {
-	address: GrinboxAddress,
+	identities: Vec<GrinboxIdentity>,
 	broker: GrinboxBroker,
-	secret_key: SecretKey,
 }
 
 impl GrinboxSubscriber {
 	pub fn new(publisher: &GrinboxPublisher) -> Result<Self> {
 		Ok(Self {
-			address: publisher.address.clone(),
+			identities: publisher.identities.clone(),
 			broker: publisher.broker.clone(),
-			secret_key: publisher.secret_key.clone(),
 		})
 	}
 }
@@ -86,8 +107,7 @@ impl Subscriber for GrinboxSubscriber {
 		K: Keychain,
 		P: Publisher,
 	{
-		self.broker
-			.subscribe(&self.address, &self.secret_key, handler)?;
+		self.broker.subscribe(&self.identities, handler)?;
 		Ok(())
 	}
 
@@ -167,8 +187,7 @@ impl GrinboxBroker {
 
 	fn subscribe<W, C, K, P>(
 		&mut self,
-		address: &GrinboxAddress,
-		secret_key: &SecretKey,
+		identities: &Vec<GrinboxIdentity>,
 		handler: Controller<W, C, K, P>,
 	) -> Result<()>
 	where
@@ -178,45 +197,51 @@ impl GrinboxBroker {
 		P: Publisher,
 	{
 		let handler = Arc::new(Mutex::new(handler));
-		let url = {
-			let cloned_address = address.clone();
-			match self.protocol_unsecure {
-				true => format!(
-					"ws://{}:{}",
-					cloned_address.domain,
-					cloned_address.port.unwrap_or(DEFAULT_GRINBOX_PORT)
-				),
-				false => format!(
-					"wss://{}:{}",
-					cloned_address.domain,
-					cloned_address.port.unwrap_or(DEFAULT_GRINBOX_PORT)
-				),
-			}
+		let cloned_address = identities[0].address.clone();
+		let domain = cloned_address.domain;
+		let port = cloned_address.port.unwrap_or(DEFAULT_GRINBOX_PORT);
+		let scheme = match self.protocol_unsecure {
+			true => "ws",
+			false => "wss",
 		};
-		let cloned_address = address.clone();
+		let url = Url::parse(&format!("{}://{}:{}", scheme, domain, port))
+			.map_err(|_| ErrorKind::GrinboxWebsocketAbnormalTermination)?;
 		let cloned_inner = self.inner.clone();
 		let cloned_handler = handler.clone();
 		let connection_meta_data = Arc::new(Mutex::new(ConnectionMetadata::new()));
 		loop {
-			let cloned_address = cloned_address.clone();
 			let cloned_handler = cloned_handler.clone();
 			let cloned_cloned_inner = cloned_inner.clone();
 			let cloned_connection_meta_data = connection_meta_data.clone();
-			let result = connect(url.clone(), |sender| {
-				{
-					let mut guard = cloned_cloned_inner.lock();
-					*guard = Some(sender.clone());
-				}
+			// Through a proxy the websocket dials the proxy, the connection to
+			// the relay is requested before the TLS handshake
+			let proxy = socks::proxy();
+			let connect_url = match &proxy {
+				Some(_) if self.protocol_unsecure => Err(WsError::new(
+					WsErrorKind::Protocol,
+					"Unsecure grinbox connections can't go through a proxy",
+				)),
+				Some(proxy) => Ok(format!("wss://{}", proxy)),
+				None => Ok(url.to_string()),
+			};
+			let result = connect_url.and_then(|connect_url| {
+				connect(connect_url, |sender| {
+					{
+						let mut guard = cloned_cloned_inner.lock();
+						*guard = Some(sender.clone());
+					}
 
-				let client = GrinboxClient {
-					sender,
-					handler: cloned_handler.clone(),
-					challenge: None,
-					address: cloned_address.clone(),
-					secret_key: secret_key.clone(),
-					connection_meta_data: cloned_connection_meta_data.clone(),
-				};
-				client
+					let client = GrinboxClient {
+						sender,
+						url: url.clone(),
+						proxied: proxy.is_some(),
+						handler: cloned_handler.clone(),
+						challenge: None,
+						identities: identities.clone(),
+						connection_meta_data: cloned_connection_meta_data.clone(),
+					};
+					client
+				})
 			});
 
 			let is_stopped = cloned_inner.lock().is_none();
@@ -267,10 +292,14 @@ where
 	P: Publisher,
 {
 	sender: Sender,
+	/// Address of the grinbox relay, which differs from the one connected
+	/// to when going through a proxy
+	url: Url,
+	/// Whether the websocket is connected to the proxy
+	proxied: bool,
 	handler: Arc<Mutex<Controller<W, C, K, P>>>,
 	challenge: Option<String>,
-	address: GrinboxAddress,
-	secret_key: SecretKey,
+	identities: Vec<GrinboxIdentity>,
 	connection_meta_data: Arc<Mutex<ConnectionMetadata>>,
 }
 
@@ -282,16 +311,25 @@ where
 	P: Publisher,
 {
 	fn subscribe(&self, challenge: &str) -> Result<()> {
-		let signature = sign_challenge(&challenge, &self.secret_key)?.to_hex();
-		let request = ProtocolRequest::Subscribe {
-			address: self.address.public_key.to_string(),
-			signature,
-		};
-		self.send(&request)
-			.expect("could not send subscribe request!");
+		for identity in &self.identities {
+			let signature = sign_challenge(&challenge, &identity.secret_key)?.to_hex();
+			let request = ProtocolRequest::Subscribe {
+				address: identity.address.public_key.to_string(),
+				signature,
+			};
+			self.send(&request)?;
+		}
 		Ok(())
 	}
 
+	/// Our address the encrypted slate is addressed to
+	fn destination(&self, message: &str) -> Option<&GrinboxIdentity> {
+		let message: EncryptedMessage = serde_json::from_str(message).ok()?;
+		self.identities
+			.iter()
+			.find(|id| id.address.public_key == message.destination.public_key)
+	}
+
 	fn send(&self, request: &ProtocolRequest) -> Result<()> {
 		let request = serde_json::to_string(&request).unwrap();
 		self.sender.send(request)?;
@@ -306,6 +344,35 @@ where
 	K: Keychain,
 	P: Publisher,
 {
+	fn build_request(&mut self, _url: &Url) -> WsResult<Request> {
+		Request::from_url(&self.url)
+	}
+
+	fn upgrade_ssl_client(
+		&mut self,
+		mut sock: TcpStream,
+		_url: &Url,
+	) -> WsResult<TlsStream<TcpStream>> {
+		let domain = self.url.domain().ok_or(WsError::new(
+			WsErrorKind::Protocol,
+			format!("Unable to parse domain from {}", self.url),
+		))?;
+		if self.proxied {
+			let port = self
+				.url
+				.port_or_known_default()
+				.unwrap_or(DEFAULT_GRINBOX_PORT);
+			socks::handshake(&mut socks::Blocking::new(&mut sock), domain, port)?;
+		}
+		let connector = TlsConnector::new().map_err(|e| {
+			WsError::new(
+				WsErrorKind::Internal,
+				format!("Failed to upgrade client to SSL: {}", e),
+			)
+		})?;
+		connector.connect(domain, sock).map_err(WsError::from)
+	}
+
 	fn on_open(&mut self, _shake: Handshake) -> WsResult<()> {
 		let mut guard = self.connection_meta_data.lock();
 
@@ -358,13 +425,23 @@ where
 				challenge,
 				signature,
 			} => {
+				let identity = match self.destination(&str) {
+					Some(id) => id.clone(),
+					None => {
+						cli_message!(
+							"{} Received a slate for an address we don't listen on",
+							"ERROR:".bright_red()
+						);
+						return Ok(());
+					}
+				};
 				let (slate, mut tx_proof) = match TxProof::from_response(
 					from,
 					str,
 					challenge,
 					signature,
-					&self.secret_key,
-					Some(&self.address),
+					&identity.secret_key,
+					Some(&identity.address),
 				) {
 					Ok(x) => x,
 					Err(e) => {
@@ -374,9 +451,12 @@ where
 				};
 
 				let address = tx_proof.address.clone();
-				self.handler
-					.lock()
-					.on_slate(&address, &slate, Some(&mut tx_proof));
+				self.handler.lock().on_slate(
+					&address,
+					&slate,
+					Some(&mut tx_proof),
+					Some(identity.index),
+				);
 			}
 			ProtocolResponse::Error {
 				kind: _,
@@ -16,42 +16,123 @@ use super::types::{CloseReason, Controller, Publisher, Subscriber, SubscriptionH
 use crate::common::{Arc, ErrorKind, Keychain, Mutex, Result};
 use crate::contacts::{Address, KeybaseAddress};
 use crate::wallet::types::{NodeClient, VersionedSlate, WalletBackend};
+use log::{debug, error};
 use serde::Serialize;
 use serde_json::{json, Value};
-use std::borrow::Borrow;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufReader};
 use std::iter::FromIterator;
-use std::process::{Command, Stdio};
+use std::process::{Child, ChildStdout, Command, Stdio};
 use std::time::Duration;
 
 pub const TOPIC_SLATE_NEW: &str = "grin_slate_new";
 pub const TOPIC_WALLET713_SLATES: &str = "wallet713_grin_slate";
 const TOPIC_SLATE_SIGNED: &str = "grin_slate_signed";
-const SLEEP_DURATION: Duration = Duration::from_millis(5000);
 
 #[derive(Clone)]
 pub struct KeybasePublisher {
+	broker: KeybaseBroker,
 	ttl: Option<String>,
 }
 
 impl KeybasePublisher {
-	pub fn new(ttl: Option<String>) -> Result<Self> {
-		let _broker = KeybaseBroker::new()?;
-		Ok(Self { ttl })
+	pub fn new(binary: &str, ttl: Option<String>) -> Result<Self> {
+		Ok(Self {
+			broker: KeybaseBroker::new(binary)?,
+			ttl,
+		})
 	}
 }
 
 #[derive(Clone)]
 pub struct KeybaseSubscriber {
+	broker: KeybaseBroker,
 	stop_signal: Arc<Mutex<bool>>,
+	/// The running `keybase chat api-listen` process
+	listener: Arc<Mutex<Option<Child>>>,
 }
 
 impl KeybaseSubscriber {
-	pub fn new() -> Result<Self> {
+	pub fn new(binary: &str) -> Result<Self> {
 		Ok(Self {
+			broker: KeybaseBroker::new(binary)?,
 			stop_signal: Arc::new(Mutex::new(true)),
+			listener: Arc::new(Mutex::new(None)),
 		})
 	}
+
+	/// Start `api-listen` and collect what arrived while it wasn't running
+	fn connect(
+		&self,
+		topics: &HashSet<&str>,
+	) -> Result<(String, ChildStdout, Vec<KeybaseMessage>)> {
+		let username = self.broker.username()?;
+		let mut child = self.broker.listen()?;
+		let stdout = match child.stdout.take() {
+			Some(s) => s,
+			None => {
+				let _ = child.kill();
+				return Err(
+					ErrorKind::KeybaseApiError("api-listen has no output".to_string()).into(),
+				);
+			}
+		};
+		*self.listener.lock() = Some(child);
+		if *self.stop_signal.lock() {
+			// Stopped while starting, end the stream right away
+			self.kill_listener();
+		}
+		match self.broker.get_unread(topics) {
+			Ok(unread) => Ok((username, stdout, unread)),
+			Err(e) => {
+				self.kill_listener();
+				Err(e)
+			}
+		}
+	}
+
+	/// Read messages from `api-listen` until the process exits
+	fn stream<H: SubscriptionHandler>(
+		&self,
+		handler: &H,
+		stdout: ChildStdout,
+		username: &str,
+		topics: &HashSet<&str>,
+		seen: &mut SeenMessages,
+	) -> Result<()> {
+		for line in BufReader::new(stdout).lines() {
+			let line = line?;
+			let value: Value = match serde_json::from_str(&line) {
+				Ok(v) => v,
+				Err(_) => {
+					debug!("Ignoring keybase output: {}", line);
+					continue;
+				}
+			};
+			if value["type"] != "chat" {
+				continue;
+			}
+			if let Some(message) = parse_message(&value["msg"], topics) {
+				if message.sender == username {
+					continue;
+				}
+				let marked = self.broker.mark_read(&message);
+				handle_message(handler, &message, seen);
+				match marked {
+					Ok(_) => seen.mark_read(&message),
+					Err(e) => debug!("Unable to mark keybase message as read: {}", e),
+				}
+			}
+		}
+		Ok(())
+	}
+
+	fn kill_listener(&self) {
+		if let Some(mut child) = self.listener.lock().take() {
+			let _ = child.kill();
+			let _ = child.wait();
+		}
+	}
 }
 
 impl Publisher for KeybasePublisher {
@@ -69,7 +150,7 @@ impl Publisher for KeybasePublisher {
 			None => TOPIC_WALLET713_SLATES,
 		};
 
-		KeybaseBroker::send(&slate, &to.stripped(), topic, ttl)?;
+		self.broker.send(&slate, &to.stripped(), topic, ttl)?;
 
 		Ok(())
 	}
@@ -88,52 +169,57 @@ impl Subscriber for KeybaseSubscriber {
 			*guard = false;
 		}
 
+		let topics = HashSet::from_iter(vec![
+			TOPIC_WALLET713_SLATES,
+			TOPIC_SLATE_NEW,
+			TOPIC_SLATE_SIGNED,
+		]);
+		let mut seen = SeenMessages::default();
 		let mut subscribed = false;
 		let mut dropped = false;
+		let mut retries = 0;
 		let result: Result<()> = loop {
 			if *self.stop_signal.lock() {
 				break Ok(());
 			};
-			let result = KeybaseBroker::get_unread(HashSet::from_iter(vec![
-				TOPIC_WALLET713_SLATES,
-				TOPIC_SLATE_NEW,
-				TOPIC_SLATE_SIGNED,
-			]));
-			if let Ok(unread) = result {
-				if !subscribed {
-					subscribed = true;
-					handler.on_open();
-				}
-				if dropped {
-					dropped = false;
-					handler.on_reestablished();
-				}
-				for (sender, topic, msg) in &unread {
-					let reply_topic = match topic.as_ref() {
-						TOPIC_SLATE_NEW => TOPIC_SLATE_SIGNED.to_string(),
-						_ => TOPIC_WALLET713_SLATES.to_string(),
-					};
-
-					let slate: VersionedSlate =
-						serde_json::from_str(&msg).map_err(|_| ErrorKind::ParseSlate)?;
-
-					let address = KeybaseAddress {
-						username: sender.to_string(),
-						topic: Some(reply_topic),
-					};
-					handler.on_slate(address.borrow(), &slate, None);
+			match self.connect(&topics) {
+				Ok((username, stdout, unread)) => {
+					retries = 0;
+					if !subscribed {
+						subscribed = true;
+						handler.on_open();
+					}
+					if dropped {
+						dropped = false;
+						handler.on_reestablished();
+					}
+					// Reading them marked them as read
+					for message in &unread {
+						handle_message(&handler, message, &mut seen);
+						seen.mark_read(message);
+					}
+					if let Err(e) = self.stream(&handler, stdout, &username, &topics, &mut seen) {
+						error!("Keybase listener failed: {}", e);
+					}
+					self.kill_listener();
 				}
-			} else {
-				if !dropped {
-					dropped = true;
-					if subscribed {
-						handler.on_dropped();
-					} else {
-						break Err(ErrorKind::KeybaseNotFound.into());
+				Err(e) => {
+					if !subscribed {
+						break Err(e);
 					}
+					debug!("Unable to restart keybase listener: {}", e);
 				}
 			}
-			std::thread::sleep(SLEEP_DURATION);
+			if *self.stop_signal.lock() {
+				break Ok(());
+			}
+			if !dropped {
+				dropped = true;
+				handler.on_dropped();
+			}
+			let secs = std::cmp::min(32, 2u64.pow(retries.min(5)));
+			std::thread::sleep(Duration::from_secs(secs));
+			retries += 1;
 		};
 		match result {
 			Err(e) => handler.on_close(CloseReason::Abnormal(e)),
@@ -143,8 +229,11 @@ impl Subscriber for KeybaseSubscriber {
 	}
 
 	fn stop(&self) {
-		let mut guard = self.stop_signal.lock();
-		*guard = true;
+		{
+			let mut guard = self.stop_signal.lock();
+			*guard = true;
+		}
+		self.kill_listener();
 	}
 
 	fn is_running(&self) -> bool {
@@ -153,35 +242,166 @@ impl Subscriber for KeybaseSubscriber {
 	}
 }
 
-struct KeybaseBroker {}
+/// Text message received on one of the slate topics
+struct KeybaseMessage {
+	id: u64,
+	channel: String,
+	sender: String,
+	topic: String,
+	body: String,
+}
+
+/// Extract a slate message from the `msg` object of `read` and `api-listen` output
+fn parse_message(msg: &Value, topics: &HashSet<&str>) -> Option<KeybaseMessage> {
+	if msg["content"]["type"] != "text" || msg["channel"]["topic_type"] != "dev" {
+		return None;
+	}
+	let topic = msg["channel"]["topic_name"].as_str()?;
+	if !topics.contains(topic) {
+		return None;
+	}
+	let body = msg["content"]["text"]["body"].as_str().unwrap_or("");
+	let sender = msg["sender"]["username"].as_str().unwrap_or("");
+	if body.is_empty() || sender.is_empty() {
+		return None;
+	}
+	Some(KeybaseMessage {
+		id: msg["id"].as_u64()?,
+		channel: msg["channel"]["name"].as_str()?.to_string(),
+		sender: sender.to_string(),
+		topic: topic.to_string(),
+		body: body.to_string(),
+	})
+}
+
+/// Messages handled per channel. A message can arrive both as unread and
+/// from api-listen while we reconnect. Ids up to the last one marked read
+/// count as handled, so only the ones above it are kept
+#[derive(Default)]
+struct SeenMessages {
+	channels: HashMap<String, (u64, HashSet<u64>)>,
+}
+
+impl SeenMessages {
+	/// Record a message, false if it was handled before
+	fn insert(&mut self, message: &KeybaseMessage) -> bool {
+		let (read, ids) = self.channels.entry(message.channel.clone()).or_default();
+		message.id > *read && ids.insert(message.id)
+	}
+
+	/// Keybase marked the messages up to this one as read
+	fn mark_read(&mut self, message: &KeybaseMessage) {
+		let (read, ids) = self.channels.entry(message.channel.clone()).or_default();
+		if message.id > *read {
+			*read = message.id;
+			ids.retain(|id| *id > message.id);
+		}
+	}
+}
+
+fn handle_message<H: SubscriptionHandler>(
+	handler: &H,
+	message: &KeybaseMessage,
+	seen: &mut SeenMessages,
+) {
+	if !seen.insert(message) {
+		return;
+	}
+
+	let slate: VersionedSlate = match serde_json::from_str(&message.body) {
+		Ok(s) => s,
+		Err(_) => {
+			error!(
+				"{} from keybase user {}",
+				ErrorKind::ParseSlate,
+				message.sender
+			);
+			return;
+		}
+	};
+
+	let reply_topic = match message.topic.as_ref() {
+		TOPIC_SLATE_NEW => TOPIC_SLATE_SIGNED.to_string(),
+		_ => TOPIC_WALLET713_SLATES.to_string(),
+	};
+	let address = KeybaseAddress {
+		username: message.sender.clone(),
+		topic: Some(reply_topic),
+	};
+	handler.on_slate(&address, &slate, None, None);
+}
+
+#[derive(Clone)]
+struct KeybaseBroker {
+	/// Path of the keybase executable
+	binary: String,
+}
 
 impl KeybaseBroker {
-	pub fn new() -> Result<Self> {
+	pub fn new(binary: &str) -> Result<Self> {
 		let mut proc = if cfg!(target_os = "windows") {
 			Command::new("where")
 		} else {
 			Command::new("which")
 		};
 
-		let status = proc.arg("keybase").stdout(Stdio::null()).status()?;
+		let status = proc.arg(binary).stdout(Stdio::null()).status()?;
 
 		if status.success() {
-			Ok(Self {})
+			Ok(Self {
+				binary: binary.to_string(),
+			})
 		} else {
 			Err(ErrorKind::KeybaseNotFound)?
 		}
 	}
 
-	pub fn api_send(payload: &str) -> Result<Value> {
-		let mut proc = Command::new("keybase");
-		proc.args(&["chat", "api", "-m", &payload]);
-		let output = proc.output().expect("No output").stdout;
-		let response = std::str::from_utf8(&output)?;
-		let response: Value = serde_json::from_str(response)?;
+	pub fn api_send(&self, payload: &str) -> Result<Value> {
+		let output = Command::new(&self.binary)
+			.args(&["chat", "api", "-m", &payload])
+			.stdin(Stdio::null())
+			.output()?;
+		if !output.status.success() {
+			let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+			return Err(ErrorKind::KeybaseApiError(stderr).into());
+		}
+		let response: Value = serde_json::from_slice(&output.stdout)?;
+		if let Some(e) = response["error"]["message"].as_str() {
+			return Err(ErrorKind::KeybaseApiError(e.to_string()).into());
+		}
 		Ok(response)
 	}
 
-	pub fn read_from_channel(channel: &str, topic: &str) -> Result<Vec<(String, String, String)>> {
+	/// Username of the logged in keybase user
+	pub fn username(&self) -> Result<String> {
+		let output = Command::new(&self.binary)
+			.arg("whoami")
+			.stdin(Stdio::null())
+			.output()?;
+		let username = String::from_utf8_lossy(&output.stdout).trim().to_string();
+		if !output.status.success() || username.is_empty() {
+			return Err(ErrorKind::KeybaseApiError("not logged in".to_string()).into());
+		}
+		Ok(username)
+	}
+
+	/// Spawn a process that prints every new chat message as a line of JSON
+	pub fn listen(&self) -> Result<Child> {
+		let child = Command::new(&self.binary)
+			.args(&["chat", "api-listen", "--dev"])
+			.stdin(Stdio::null())
+			.stdout(Stdio::piped())
+			.stderr(Stdio::null())
+			.spawn()?;
+		Ok(child)
+	}
+
+	fn read_from_channel(
+		&self,
+		channel: &str,
+		topic: &str,
+		topics: &HashSet<&str>,
+	) -> Result<Vec<KeybaseMessage>> {
 		let payload = json!({
 			"method": "read",
 			"params": {
@@ -197,24 +417,22 @@ impl KeybaseBroker {
 			}
 		});
 		let payload = serde_json::to_string(&payload)?;
-		let response = KeybaseBroker::api_send(&payload)?;
-		let mut unread: Vec<(String, String, String)> = Vec::new();
-		let messages = response["result"]["messages"].as_array();
-		if let Some(messages) = messages {
+		let response = self.api_send(&payload)?;
+		let mut unread = Vec::new();
+		if let Some(messages) = response["result"]["messages"].as_array() {
 			for msg in messages.iter() {
-				if (msg["msg"]["content"]["type"] == "text") && (msg["msg"]["unread"] == true) {
-					let message = msg["msg"]["content"]["text"]["body"].as_str().unwrap_or("");
-					let sender: &str = msg["msg"]["sender"]["username"].as_str().unwrap_or("");
-					if !message.is_empty() && !sender.is_empty() {
-						unread.push((sender.to_string(), topic.to_string(), message.to_string()));
-					}
+				if msg["msg"]["unread"] != true {
+					continue;
+				}
+				if let Some(message) = parse_message(&msg["msg"], topics) {
+					unread.push(message);
 				}
 			}
 		}
 		Ok(unread)
 	}
 
-	pub fn get_unread(topics: HashSet<&str>) -> Result<Vec<(String, String, String)>> {
+	fn get_unread(&self, topics: &HashSet<&str>) -> Result<Vec<KeybaseMessage>> {
 		let payload = json!({
 			"method": "list",
 			"params": {
@@ -224,29 +442,47 @@ impl KeybaseBroker {
 			}
 		});
 		let payload = serde_json::to_string(&payload)?;
-		let response = KeybaseBroker::api_send(&payload)?;
+		let response = self.api_send(&payload)?;
 
 		let mut channels = HashSet::new();
-		let messages = response["result"]["conversations"].as_array();
-		if let Some(messages) = messages {
-			for msg in messages.iter() {
-				let topic = msg["channel"]["topic_name"].as_str().unwrap();
-				if (msg["unread"] == true) && topics.contains(topic) {
-					let channel = msg["channel"]["name"].as_str().unwrap();
-					channels.insert((channel.to_string(), topic));
+		if let Some(conversations) = response["result"]["conversations"].as_array() {
+			for conversation in conversations.iter() {
+				let topic = conversation["channel"]["topic_name"].as_str().unwrap_or("");
+				let channel = conversation["channel"]["name"].as_str().unwrap_or("");
+				if conversation["unread"] == true && topics.contains(topic) && !channel.is_empty() {
+					channels.insert((channel.to_string(), topic.to_string()));
 				}
 			}
 		}
 
-		let mut unread: Vec<(String, String, String)> = Vec::new();
+		let mut unread = Vec::new();
 		for (channel, topic) in channels.iter() {
-			let mut messages = KeybaseBroker::read_from_channel(channel, topic)?;
+			let mut messages = self.read_from_channel(channel, topic, topics)?;
 			unread.append(&mut messages);
 		}
 		Ok(unread)
 	}
 
+	fn mark_read(&self, message: &KeybaseMessage) -> Result<()> {
+		let payload = json!({
+			"method": "mark",
+			"params": {
+				"options": {
+					"channel": {
+						"name": message.channel,
+						"topic_type": "dev",
+						"topic_name": message.topic
+					},
+					"message_id": message.id
+				}
+			}
+		});
+		self.api_send(&serde_json::to_string(&payload)?)?;
+		Ok(())
+	}
+
 	pub fn send<T: Serialize>(
+		&self,
 		message: &T,
 		channel: &str,
 		topic: &str,
@@ -273,10 +509,179 @@ impl KeybaseBroker {
 		}
 
 		let payload = serde_json::to_string(&payload)?;
-		let response = KeybaseBroker::api_send(&payload)?;
+		let response = self.api_send(&payload)?;
 		match response["result"]["message"].as_str() {
 			Some("message sent") => Ok(()),
 			_ => Err(ErrorKind::KeybaseMessageSendError)?,
 		}
 	}
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+	use super::*;
+	use crate::wallet::types::{Slate, SlateVersion, TxProof};
+	use std::env;
+	use std::fs;
+	use std::os::unix::fs::PermissionsExt;
+	use std::path::PathBuf;
+	use uuid::Uuid;
+
+	const CHANNEL: &str = "alice,bob";
+
+	/// Records the slates it receives
+	#[derive(Default)]
+	struct Recorder {
+		slates: Mutex<Vec<(String, Uuid)>>,
+	}
+
+	impl SubscriptionHandler for Recorder {
+		fn on_open(&self) {}
+
+		fn on_slate(
+			&self,
+			from: &dyn Address,
+			slate: &VersionedSlate,
+			_proof: Option<&mut TxProof>,
+			_address_index: Option<u32>,
+		) {
+			let slate: Slate = slate.clone().into();
+			self.slates.lock().push((from.stripped(), slate.id));
+		}
+
+		fn on_close(&self, _result: CloseReason) {}
+
+		fn on_dropped(&self) {}
+
+		fn on_reestablished(&self) {}
+	}
+
+	/// Keybase executable in a temporary directory, logged in as alice
+	struct FakeKeybase {
+		dir: PathBuf,
+	}
+
+	impl FakeKeybase {
+		/// `unread` is returned by `read`, `listen` is printed by `api-listen`
+		fn new(unread: Vec<Value>, listen: Vec<Value>) -> Self {
+			let dir = env::temp_dir().join(format!("wallet713-test-{}", Uuid::new_v4()));
+			fs::create_dir_all(&dir).unwrap();
+
+			let list = json!({
+				"result": {
+					"conversations": [{
+						"channel": {
+							"name": CHANNEL,
+							"topic_type": "dev",
+							"topic_name": TOPIC_WALLET713_SLATES
+						},
+						"unread": !unread.is_empty()
+					}]
+				}
+			});
+			let read = json!({
+				"result": {
+					"messages": unread.into_iter().map(|m| json!({ "msg": m })).collect::<Vec<_>>()
+				}
+			});
+			let listen: Vec<String> = listen
+				.into_iter()
+				.map(|m| json!({ "type": "chat", "msg": m }).to_string())
+				.collect();
+
+			let script = format!(
+				"#!/bin/sh\n\
+				 case \"$1 $2\" in\n\
+				 \"whoami \") echo alice ;;\n\
+				 \"chat api-listen\") cat <<'EOF'\n{}\nEOF\n;;\n\
+				 \"chat api\") case \"$4\" in\n\
+				 *'\"method\":\"list\"'*) cat <<'EOF'\n{}\nEOF\n;;\n\
+				 *'\"method\":\"read\"'*) cat <<'EOF'\n{}\nEOF\n;;\n\
+				 *) echo '{{\"result\":{{}}}}' ;;\n\
+				 esac ;;\n\
+				 esac\n",
+				listen.join("\n"),
+				list,
+				read
+			);
+			let path = dir.join("keybase");
+			fs::write(&path, script).unwrap();
+			fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+			Self { dir }
+		}
+
+		fn binary(&self) -> String {
+			self.dir.join("keybase").to_string_lossy().to_string()
+		}
+	}
+
+	impl Drop for FakeKeybase {
+		fn drop(&mut self) {
+			let _ = fs::remove_dir_all(&self.dir);
+		}
+	}
+
+	fn message(id: u64, sender: &str, slate: &Slate) -> Value {
+		let slate = VersionedSlate::into_version(slate.clone(), SlateVersion::V3);
+		json!({
+			"id": id,
+			"channel": {
+				"name": CHANNEL,
+				"topic_type": "dev",
+				"topic_name": TOPIC_WALLET713_SLATES
+			},
+			"sender": { "username": sender },
+			"content": {
+				"type": "text",
+				"text": { "body": serde_json::to_string(&slate).unwrap() }
+			},
+			"unread": true
+		})
+	}
+
+	#[test]
+	fn listen_handles_each_slate_once() {
+		let first = Slate::blank(2);
+		let second = Slate::blank(2);
+		let keybase = FakeKeybase::new(
+			vec![message(1, "bob", &first)],
+			vec![
+				message(1, "bob", &first),
+				message(2, "bob", &second),
+				message(3, "alice", &second),
+			],
+		);
+
+		let subscriber = KeybaseSubscriber::new(&keybase.binary()).unwrap();
+		*subscriber.stop_signal.lock() = false;
+		let topics = HashSet::from_iter(vec![TOPIC_WALLET713_SLATES]);
+		let (username, stdout, unread) = subscriber.connect(&topics).unwrap();
+		assert_eq!(username, "alice");
+		assert_eq!(unread.len(), 1);
+
+		let recorder = Recorder::default();
+		let mut seen = SeenMessages::default();
+		for message in &unread {
+			handle_message(&recorder, message, &mut seen);
+			seen.mark_read(message);
+		}
+		subscriber
+			.stream(&recorder, stdout, &username, &topics, &mut seen)
+			.unwrap();
+		subscriber.kill_listener();
+
+		assert_eq!(
+			*recorder.slates.lock(),
+			vec![("bob".to_owned(), first.id), ("bob".to_owned(), second.id)]
+		);
+		// Only ids above the last read one are remembered
+		assert_eq!(seen.channels[CHANNEL], (2, HashSet::new()));
+	}
+
+	#[test]
+	fn missing_keybase_fails() {
+		let keybase = FakeKeybase::new(vec![], vec![]);
+		let binary = keybase.dir.join("missing").to_string_lossy().to_string();
+		assert!(KeybaseSubscriber::new(&binary).is_err());
+	}
+}
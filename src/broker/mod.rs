@@ -15,8 +15,9 @@
 mod grinbox;
 mod keybase;
 mod protocol;
+pub mod relay;
 mod types;
 
-pub use self::grinbox::{GrinboxPublisher, GrinboxSubscriber};
+pub use self::grinbox::{GrinboxIdentity, GrinboxPublisher, GrinboxSubscriber};
 pub use self::keybase::{KeybasePublisher, KeybaseSubscriber, TOPIC_SLATE_NEW};
 pub use self::types::{CloseReason, Controller, Publisher, Subscriber, SubscriptionHandler};
@@ -23,6 +23,9 @@ pub enum ProtocolError {
 	InvalidSignature,
 	InvalidChallenge,
 	TooManySubscriptions,
+	TooManyRequests,
+	QueueFull,
+	SlateTooLarge,
 }
 
 impl Display for ProtocolError {
@@ -33,6 +36,9 @@ impl Display for ProtocolError {
 			ProtocolError::InvalidSignature => write!(f, "{}", "invalid signature!"),
 			ProtocolError::InvalidChallenge => write!(f, "{}", "invalid challenge!"),
 			ProtocolError::TooManySubscriptions => write!(f, "{}", "too many subscriptions!"),
+			ProtocolError::TooManyRequests => write!(f, "{}", "too many requests!"),
+			ProtocolError::QueueFull => write!(f, "{}", "recipient queue is full!"),
+			ProtocolError::SlateTooLarge => write!(f, "{}", "slate is too large!"),
 		}
 	}
 }
@@ -0,0 +1,385 @@
+// Copyright 2019 The vault713 Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Server side of the grinbox protocol, for running a private relay
+
+use super::protocol::{ProtocolError, ProtocolRequest, ProtocolResponse};
+use crate::cli_message;
+use crate::common::config::RelayConfig;
+use crate::common::crypto::{verify_signature, Hex, Signature};
+use crate::common::{Arc, Mutex, Result};
+use crate::contacts::{Address, GrinboxAddress};
+use colored::Colorize;
+use log::{debug, error};
+use rand::distributions::Alphanumeric;
+use rand::{thread_rng, Rng};
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+use ws::{Builder, CloseCode, Handler, Handshake, Message, Result as WsResult, Sender, Settings};
+
+const CHALLENGE_LENGTH: usize = 32;
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Slate waiting for its recipient to subscribe
+struct QueuedSlate {
+	message: String,
+	queued_at: Instant,
+}
+
+/// State shared by all connections to the relay
+struct RelayState {
+	/// Connections subscribed to each public key
+	subscribers: HashMap<String, Vec<Sender>>,
+	/// Slates for public keys nobody is subscribed to
+	queues: HashMap<String, VecDeque<QueuedSlate>>,
+	/// Times each public key posted a slate in the last minute
+	posts: HashMap<String, Vec<Instant>>,
+	/// Times each remote IP posted a slate in the last minute
+	ip_posts: HashMap<IpAddr, Vec<Instant>>,
+	last_pruned: Instant,
+}
+
+impl RelayState {
+	fn new() -> Self {
+		Self {
+			subscribers: HashMap::new(),
+			queues: HashMap::new(),
+			posts: HashMap::new(),
+			ip_posts: HashMap::new(),
+			last_pruned: Instant::now(),
+		}
+	}
+
+	/// Drop expired slates and rate limit windows, and the entries left empty
+	fn prune(&mut self, ttl: Duration) {
+		for queue in self.queues.values_mut() {
+			queue.retain(|s| s.queued_at.elapsed() < ttl);
+		}
+		self.queues.retain(|_, q| !q.is_empty());
+		for posts in self.posts.values_mut() {
+			posts.retain(|t| t.elapsed() < RATE_WINDOW);
+		}
+		self.posts.retain(|_, p| !p.is_empty());
+		for posts in self.ip_posts.values_mut() {
+			posts.retain(|t| t.elapsed() < RATE_WINDOW);
+		}
+		self.ip_posts.retain(|_, p| !p.is_empty());
+		self.last_pruned = Instant::now();
+	}
+
+	fn queued_slates(&self) -> usize {
+		self.queues.values().map(|q| q.len()).sum()
+	}
+}
+
+/// Run a grinbox relay until the process is stopped
+pub fn run(config: RelayConfig) -> Result<()> {
+	let address = config.listen_address();
+	let state = Arc::new(Mutex::new(RelayState::new()));
+	cli_message!("Grinbox relay listening on {}", address.bright_green());
+	// Requests are a bit larger than the slate they carry, anything bigger is
+	// refused before it is buffered
+	let settings = Settings {
+		max_fragment_size: 2 * config.max_slate_size(),
+		fragments_grow: false,
+		..Settings::default()
+	};
+	Builder::new()
+		.with_settings(settings)
+		.build(|sender| RelayConnection {
+			sender,
+			state: state.clone(),
+			config: config.clone(),
+			challenge: thread_rng()
+				.sample_iter(&Alphanumeric)
+				.take(CHALLENGE_LENGTH)
+				.collect(),
+			subscriptions: Vec::new(),
+			ip: None,
+			posts: Vec::new(),
+		})?
+		.listen(address.as_str())?;
+	Ok(())
+}
+
+struct RelayConnection {
+	sender: Sender,
+	state: Arc<Mutex<RelayState>>,
+	config: RelayConfig,
+	challenge: String,
+	/// Public keys this connection is subscribed to
+	subscriptions: Vec<String>,
+	/// Remote IP of the connection
+	ip: Option<IpAddr>,
+	/// Times this connection posted a slate in the last minute
+	posts: Vec<Instant>,
+}
+
+impl RelayConnection {
+	fn subscribe(&mut self, address: &str, signature: &str) -> Result<ProtocolResponse> {
+		let public_key = match verify(address, &self.challenge, signature) {
+			Some(k) => k,
+			None => return Ok(error(ProtocolError::InvalidSignature)),
+		};
+		if self.subscriptions.contains(&public_key) {
+			return Ok(ProtocolResponse::Ok);
+		}
+
+		let mut state = self.state.lock();
+		let subscribers = state
+			.subscribers
+			.entry(public_key.clone())
+			.or_insert_with(Vec::new);
+		if subscribers.len() >= self.config.max_subscriptions() {
+			return Ok(error(ProtocolError::TooManySubscriptions));
+		}
+		subscribers.push(self.sender.clone());
+		self.subscriptions.push(public_key.clone());
+
+		// Hand over what arrived while the address was offline
+		let ttl = Duration::from_secs(self.config.queue_ttl_secs());
+		if let Some(queue) = state.queues.remove(&public_key) {
+			for slate in queue {
+				if slate.queued_at.elapsed() < ttl {
+					self.sender.send(slate.message)?;
+				}
+			}
+		}
+		Ok(ProtocolResponse::Ok)
+	}
+
+	fn unsubscribe(&mut self, address: &str) -> ProtocolResponse {
+		let public_key = match GrinboxAddress::from_str(address) {
+			Ok(a) => a.public_key,
+			Err(_) => return error(ProtocolError::InvalidRequest),
+		};
+		self.subscriptions.retain(|k| *k != public_key);
+		remove_subscriber(&mut self.state.lock(), &public_key, &self.sender);
+		ProtocolResponse::Ok
+	}
+
+	fn post_slate(
+		&mut self,
+		from: String,
+		to: &str,
+		str: String,
+		signature: String,
+	) -> Result<ProtocolResponse> {
+		// Senders sign the encrypted slate only, recipients verify it with an empty challenge
+		let sender_key = match verify(&from, &str, &signature) {
+			Some(k) => k,
+			None => return Ok(error(ProtocolError::InvalidSignature)),
+		};
+		let recipient_key = match GrinboxAddress::from_str(to) {
+			Ok(a) => a.public_key,
+			Err(_) => return Ok(error(ProtocolError::InvalidRequest)),
+		};
+
+		if str.len() > self.config.max_slate_size() {
+			return Ok(error(ProtocolError::SlateTooLarge));
+		}
+
+		// Keys are free, so posts are limited per connection and IP as well
+		let max_posts = self.config.max_posts_per_minute();
+		if !within_rate(&mut self.posts, max_posts) {
+			return Ok(error(ProtocolError::TooManyRequests));
+		}
+		let ttl = Duration::from_secs(self.config.queue_ttl_secs());
+		let mut state = self.state.lock();
+		if state.last_pruned.elapsed() >= RATE_WINDOW {
+			state.prune(ttl);
+		}
+		if let Some(ip) = self.ip {
+			let max_ip_posts = self.config.max_ip_posts_per_minute();
+			let ip_posts = state.ip_posts.entry(ip).or_insert_with(Vec::new);
+			if max_ip_posts > 0 && !within_rate(ip_posts, max_ip_posts) {
+				return Ok(error(ProtocolError::TooManyRequests));
+			}
+		}
+		let posts = state.posts.entry(sender_key).or_insert_with(Vec::new);
+		if !within_rate(posts, max_posts) {
+			return Ok(error(ProtocolError::TooManyRequests));
+		}
+
+		let message = serde_json::to_string(&ProtocolResponse::Slate {
+			from,
+			str,
+			signature,
+			challenge: String::new(),
+		})?;
+
+		let mut delivered = false;
+		if let Some(subscribers) = state.subscribers.get(&recipient_key) {
+			for subscriber in subscribers {
+				delivered |= subscriber.send(message.clone()).is_ok();
+			}
+		}
+		if !delivered {
+			if state.queued_slates() >= self.config.max_total_queued_slates() {
+				state.prune(ttl);
+				if state.queued_slates() >= self.config.max_total_queued_slates() {
+					return Ok(error(ProtocolError::QueueFull));
+				}
+			}
+			let queue = state
+				.queues
+				.entry(recipient_key)
+				.or_insert_with(VecDeque::new);
+			queue.retain(|s| s.queued_at.elapsed() < ttl);
+			if queue.len() >= self.config.max_queued_slates() {
+				return Ok(error(ProtocolError::QueueFull));
+			}
+			queue.push_back(QueuedSlate {
+				message,
+				queued_at: Instant::now(),
+			});
+		}
+		Ok(ProtocolResponse::Ok)
+	}
+
+	fn respond(&self, response: &ProtocolResponse) -> WsResult<()> {
+		debug!("{}", response);
+		self.sender.send(serde_json::to_string(response).unwrap())
+	}
+}
+
+impl Handler for RelayConnection {
+	fn on_open(&mut self, shake: Handshake) -> WsResult<()> {
+		self.ip = shake.peer_addr.map(|a| a.ip());
+		self.respond(&ProtocolResponse::Challenge {
+			str: self.challenge.clone(),
+		})
+	}
+
+	fn on_message(&mut self, msg: Message) -> WsResult<()> {
+		let request = match serde_json::from_str::<ProtocolRequest>(&msg.to_string()) {
+			Ok(r) => r,
+			Err(_) => return self.respond(&error(ProtocolError::InvalidRequest)),
+		};
+		debug!("{}", request);
+
+		let result = match request {
+			ProtocolRequest::Challenge => Ok(ProtocolResponse::Challenge {
+				str: self.challenge.clone(),
+			}),
+			ProtocolRequest::Subscribe { address, signature } => {
+				self.subscribe(&address, &signature)
+			}
+			ProtocolRequest::Unsubscribe { address } => Ok(self.unsubscribe(&address)),
+			ProtocolRequest::PostSlate {
+				from,
+				to,
+				str,
+				signature,
+			} => self.post_slate(from, &to, str, signature),
+		};
+
+		match result {
+			Ok(response) => self.respond(&response),
+			Err(e) => {
+				error!("Relay request failed: {}", e);
+				self.respond(&error(ProtocolError::UnknownError))
+			}
+		}
+	}
+
+	fn on_close(&mut self, _code: CloseCode, _reason: &str) {
+		let mut state = self.state.lock();
+		for public_key in self.subscriptions.drain(..) {
+			remove_subscriber(&mut state, &public_key, &self.sender);
+		}
+	}
+}
+
+/// Verify a signature made with the key of a grinbox address, returning the key
+fn verify(address: &str, challenge: &str, signature: &str) -> Option<String> {
+	let address = GrinboxAddress::from_str(address).ok()?;
+	let public_key = address.public_key().ok()?;
+	let signature = Signature::from_hex(signature).ok()?;
+	verify_signature(challenge, &signature, &public_key).ok()?;
+	Some(address.public_key)
+}
+
+/// Record a post in a rate limit window, unless the limit is already reached
+fn within_rate(posts: &mut Vec<Instant>, limit: usize) -> bool {
+	posts.retain(|t| t.elapsed() < RATE_WINDOW);
+	if posts.len() >= limit {
+		return false;
+	}
+	posts.push(Instant::now());
+	true
+}
+
+fn remove_subscriber(state: &mut RelayState, public_key: &str, sender: &Sender) {
+	if let Some(subscribers) = state.subscribers.get_mut(public_key) {
+		subscribers.retain(|s| s.connection_id() != sender.connection_id());
+		if subscribers.is_empty() {
+			state.subscribers.remove(public_key);
+		}
+	}
+}
+
+fn error(kind: ProtocolError) -> ProtocolResponse {
+	ProtocolResponse::Error {
+		description: kind.to_string(),
+		kind,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn rate_limit_window() {
+		let mut posts = Vec::new();
+		assert!(within_rate(&mut posts, 2));
+		assert!(within_rate(&mut posts, 2));
+		assert!(!within_rate(&mut posts, 2));
+		assert_eq!(posts.len(), 2);
+
+		// Posts older than the window no longer count
+		posts[0] = Instant::now() - RATE_WINDOW;
+		assert!(within_rate(&mut posts, 2));
+	}
+
+	#[test]
+	fn prune_drops_empty_entries() {
+		let mut state = RelayState::new();
+		let old = Instant::now() - Duration::from_secs(120);
+		let mut queue = VecDeque::new();
+		queue.push_back(QueuedSlate {
+			message: String::from("expired"),
+			queued_at: old,
+		});
+		state.queues.insert(String::from("a"), queue);
+		let mut queue = VecDeque::new();
+		queue.push_back(QueuedSlate {
+			message: String::from("fresh"),
+			queued_at: Instant::now(),
+		});
+		state.queues.insert(String::from("b"), queue);
+		state.posts.insert(String::from("a"), vec![old]);
+		state
+			.ip_posts
+			.insert("127.0.0.1".parse().unwrap(), vec![old]);
+
+		state.prune(Duration::from_secs(60));
+		assert_eq!(state.queued_slates(), 1);
+		assert!(state.queues.contains_key("b"));
+		assert!(state.posts.is_empty());
+		assert!(state.ip_posts.is_empty());
+	}
+}
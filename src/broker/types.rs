@@ -14,11 +14,16 @@
 
 use crate::cli_message;
 use crate::common::{Arc, Error, Keychain, Mutex};
-use crate::contacts::{Address, AddressType, GrinboxAddress};
-use crate::wallet::api::{Foreign, Owner};
-use crate::wallet::types::{NodeClient, Slate, TxProof, VersionedSlate, WalletBackend};
-use crate::wallet::Container;
+use crate::contacts::{Address, AddressType, GrinboxAddress, KeybaseAddress};
+use crate::wallet::api::{policy, Foreign, Owner};
+use crate::wallet::types::{
+	IncomingSlate, NodeClient, Slate, TxLogEntryType, TxProof, VersionedSlate, WalletBackend,
+	WalletEvent, WalletEventType,
+};
+use crate::wallet::{Container, ErrorKind};
 use colored::Colorize;
+use grin_core::core::amount_to_hr_string;
+use log::debug;
 use std::marker::Send;
 
 pub enum CloseReason {
@@ -28,6 +33,16 @@ pub enum CloseReason {
 
 pub trait Publisher: Send {
 	fn post_slate(&self, slate: &VersionedSlate, to: &dyn Address) -> Result<(), Error>;
+
+	/// Post from the address with this index, for transports listening on several addresses
+	fn post_slate_from(
+		&self,
+		slate: &VersionedSlate,
+		to: &dyn Address,
+		_index: Option<u32>,
+	) -> Result<(), Error> {
+		self.post_slate(slate, to)
+	}
 }
 
 pub trait Subscriber {
@@ -43,7 +58,13 @@ pub trait Subscriber {
 
 pub trait SubscriptionHandler: Send {
 	fn on_open(&self);
-	fn on_slate(&self, from: &dyn Address, slate: &VersionedSlate, proof: Option<&mut TxProof>);
+	fn on_slate(
+		&self,
+		from: &dyn Address,
+		slate: &VersionedSlate,
+		proof: Option<&mut TxProof>,
+		address_index: Option<u32>,
+	);
 	fn on_close(&self, result: CloseReason);
 	fn on_dropped(&self);
 	fn on_reestablished(&self);
@@ -57,6 +78,9 @@ where
 	P: Publisher,
 {
 	name: String,
+	/// Send method of the outbox entries this controller delivers
+	method: String,
+	container: Arc<Mutex<Container<W, C, K>>>,
 	owner: Owner<W, C, K>,
 	foreign: Foreign<W, C, K>,
 	publisher: P,
@@ -71,11 +95,14 @@ where
 {
 	pub fn new(
 		name: &str,
+		method: &str,
 		container: Arc<Mutex<Container<W, C, K>>>,
 		publisher: P,
 	) -> Result<Self, Error> {
 		Ok(Self {
 			name: name.to_string(),
+			method: method.to_string(),
+			container: container.clone(),
 			owner: Owner::new(container.clone()),
 			foreign: Foreign::new(container),
 			publisher,
@@ -87,19 +114,124 @@ where
 		address: Option<String>,
 		slate: &mut Slate,
 		tx_proof: Option<&mut TxProof>,
+		address_index: Option<u32>,
 	) -> Result<bool, Error> {
 		if slate.num_participants > slate.participant_data.len() {
+			self.owner.check_ttl(slate, slate.ttl_cutoff_height)?;
 			if slate.tx.inputs().len() == 0 {
 				// TODO: invoicing
 			} else {
-				*slate = self.foreign.receive_tx(slate, None, address, None)?;
+				*slate = self
+					.foreign
+					.receive_tx(slate, None, address, None, address_index)?;
+				if let Some(index) = address_index {
+					if let Err(e) = self.owner.match_payment_request(index, slate) {
+						cli_message!("{}: {}", "ERROR".bright_red(), e);
+					}
+				}
 			}
 			Ok(false)
 		} else {
+			// Older slate versions don't carry the TTL, use the one we recorded
+			let tx = self.owner.retrieve_tx(None, Some(slate.id))?;
+			self.owner.check_ttl(slate, tx.ttl_cutoff_height)?;
 			self.owner.finalize_tx(slate, tx_proof)?;
 			Ok(true)
 		}
 	}
+
+	/// Deliver the slates that were queued while this listener was down
+	fn flush_outbox(&self) {
+		let result = self.owner.deliver_outbox(
+			|e| e.method == self.method,
+			|e| match e.method.as_str() {
+				"keybase" => {
+					let to = KeybaseAddress::from_str(&e.dest)?;
+					self.publisher.post_slate(&e.slate, &to)
+				}
+				_ => {
+					let to = GrinboxAddress::from_str(&e.dest)?;
+					self.publisher
+						.post_slate_from(&e.slate, &to, e.address_index)
+				}
+			},
+		);
+		if let Err(e) = result {
+			debug!("Unable to deliver outbox of {}: {}", self.name, e);
+		}
+	}
+
+	/// The receiver sent our slate back unsigned, cancel the transaction
+	fn process_rejection(&self, from: &dyn Address, slate: &Slate) -> Result<(), Error> {
+		let tx = self.owner.retrieve_tx(None, Some(slate.id))?;
+		// Only the recipient can reject
+		if tx.tx_type != TxLogEntryType::TxSent || tx.address != Some(from.stripped()) {
+			return Err(ErrorKind::GenericError(format!(
+				"Ignored rejection of slate {} from {}",
+				slate.id,
+				from.stripped()
+			))
+			.into());
+		}
+		self.owner.cancel_tx(Some(tx.id), None)?;
+		cli_message!(
+			"{} slate {} was rejected by {} ({}), the transaction was cancelled",
+			"WARNING:".bright_yellow(),
+			slate.id.to_string().bright_green(),
+			from.stripped().bright_green(),
+			slate.rejection.clone().unwrap_or_default()
+		);
+		Ok(())
+	}
+
+	/// Apply the receive policy before a new slate is queued or signed
+	fn check_policy(&self, from: &dyn Address, slate: &Slate) -> Result<(), Error> {
+		let mut guard = self.container.lock();
+		let c = &mut *guard;
+		let policy = c.config.receive_policy();
+		policy::check_sender(&policy, &c.address_book, slate, Some(from))?;
+		policy::check_slate(&policy, slate)?;
+		policy::check_rate(&policy, &mut c.received_slates, slate, from)
+	}
+
+	/// Post an event about a slate exchanged with `address` to the event streams and webhooks
+	fn notify(&self, event: WalletEventType, slate: &Slate, address: &dyn Address) {
+		let mut event = WalletEvent::new(event);
+		event.slate_id = Some(slate.id);
+		event.amount = Some(slate.amount);
+		event.address = Some(address.stripped());
+		self.owner.notify(event);
+	}
+
+	/// Post an event about the connection of this listener to the event streams and webhooks
+	fn notify_listener(&self, event: WalletEventType) {
+		let mut event = WalletEvent::new(event);
+		event.address = Some(self.name.clone());
+		self.owner.notify(event);
+	}
+
+	/// Hold a new slate until it is accepted or rejected with the `incoming` command
+	fn queue_incoming(
+		&self,
+		from: &dyn Address,
+		slate: &VersionedSlate,
+		address_index: Option<u32>,
+	) -> Result<(), Error> {
+		let s: Slate = slate.into();
+		let mut entry =
+			IncomingSlate::new(s.id, self.method.clone(), from.to_string(), slate.clone());
+		entry.address_index = address_index;
+		self.owner.queue_incoming(&entry)?;
+		cli_message!(
+			"Slate {} for {} grin from {} is waiting for approval, run `incoming accept {}` or `incoming reject {}`",
+			s.id.to_string().bright_green(),
+			amount_to_hr_string(s.amount, false).bright_green(),
+			from.stripped().bright_green(),
+			s.id,
+			s.id
+		);
+		Ok(())
+	}
 }
 
 impl<W, C, K, P> SubscriptionHandler for Controller<W, C, K, P>
@@ -111,11 +243,23 @@ where
 {
 	fn on_open(&self) {
 		//        cli_message!("Listener for {} started", self.name.bright_green());
+		self.flush_outbox();
 	}
 
-	fn on_slate(&self, from: &dyn Address, slate: &VersionedSlate, tx_proof: Option<&mut TxProof>) {
-		let version = slate.version();
-		let mut slate: Slate = slate.clone().into();
+	fn on_slate(
+		&self,
+		from: &dyn Address,
+		vslate: &VersionedSlate,
+		tx_proof: Option<&mut TxProof>,
+		address_index: Option<u32>,
+	) {
+		let version = vslate.version();
+		let mut slate: Slate = vslate.clone().into();
+		// Reply in the same version and use it for our next slate to this peer
+		self.container
+			.lock()
+			.peer_slate_versions
+			.insert(from.stripped(), version.clone());
 
 		/*if slate.num_participants > slate.participant_data.len() {
 			cli_message!(
@@ -137,15 +281,38 @@ where
 			GrinboxAddress::from_str(&from.to_string()).expect("invalid grinbox address");
 		}
 
+		if slate.rejection.is_some() {
+			if let Err(e) = self.process_rejection(from, &slate) {
+				cli_message!("{}", e);
+			}
+			return;
+		}
+
+		let is_new = slate.num_participants > slate.participant_data.len();
+		if is_new {
+			if let Err(e) = self.check_policy(from, &slate) {
+				cli_message!("{} {}", "WARNING:".bright_yellow(), e);
+				return;
+			}
+			self.notify(WalletEventType::SlateReceived, &slate, from);
+		}
+
+		if is_new && self.container.lock().config.incoming_approval() {
+			if let Err(e) = self.queue_incoming(from, &vslate, address_index) {
+				cli_message!("{}", e);
+			}
+			return;
+		}
+
 		let result = self
-			.process_incoming_slate(Some(from.to_string()), &mut slate, tx_proof)
+			.process_incoming_slate(Some(from.to_string()), &mut slate, tx_proof, address_index)
 			.and_then(|is_finalized| {
 				if !is_finalized {
 					let id = slate.id.clone();
-					let slate = VersionedSlate::into_version(slate, version);
+					let vslate = VersionedSlate::into_version(slate.clone(), version);
 
 					self.publisher
-						.post_slate(&slate, from)
+						.post_slate_from(&vslate, from, address_index)
 						.map_err(|e| {
 							cli_message!("{}: {}", "ERROR".bright_red(), e);
 							e
@@ -156,13 +323,8 @@ where
 						id.to_string().bright_green(),
 						from.stripped().bright_green()
 					);
+					self.notify(WalletEventType::ResponseSent, &slate, from);
 				}
-				/*else {
-					cli_message!(
-						"Slate [{}] finalized successfully",
-						slate.id.to_string().bright_green()
-					);
-				}*/
 				Ok(())
 			});
 
@@ -178,19 +340,23 @@ where
 				//println!("Listener for {} stopped", self.name.bright_green())
 			}
 			CloseReason::Abnormal(_) => {
-				cli_message!("Listener {} stopped unexpectedly", self.name.bright_green())
+				cli_message!("Listener {} stopped unexpectedly", self.name.bright_green());
+				self.notify_listener(WalletEventType::ListenerDisconnected);
 			}
 		}
 	}
 
 	fn on_dropped(&self) {
-		cli_message!("Listener {} lost connection. it will keep trying to restore connection in the background.", self.name.bright_green())
+		cli_message!("Listener {} lost connection. it will keep trying to restore connection in the background.", self.name.bright_green());
+		self.notify_listener(WalletEventType::ListenerDisconnected);
 	}
 
 	fn on_reestablished(&self) {
 		cli_message!(
 			"Listener {} reestablished connection.",
 			self.name.bright_green()
-		)
+		);
+		self.notify_listener(WalletEventType::ListenerReestablished);
+		self.flush_outbox();
 	}
 }
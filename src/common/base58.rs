@@ -79,78 +79,33 @@ impl ToBase58 for [u8] {
 
 impl FromBase58 for str {
 	fn from_base58(&self) -> Result<Vec<u8>> {
-		let mut bin = [0u8; 132];
-		let mut out = [0u32; (132 + 3) / 4];
-		let bytesleft = (bin.len() % 4) as u8;
-		let zeromask = match bytesleft {
-			0 => 0u32,
-			_ => 0xffffffff << (bytesleft * 8),
-		};
-
-		let zcount = self.chars().take_while(|x| *x == '1').count();
-		let mut i = zcount;
-		let b58: Vec<u8> = self.bytes().collect();
-
-		while i < self.len() {
-			if (b58[i] & 0x80) != 0 {
-				// High-bit set on invalid digit
-				Err(ErrorKind::InvalidBase58Character(b58[i] as char, i))?;
-			}
-
-			if B58_DIGITS_MAP[b58[i] as usize] == -1 {
-				// // Invalid base58 digit
-				Err(ErrorKind::InvalidBase58Character(b58[i] as char, i))?;
-			}
+		let zcount = self.bytes().take_while(|x| *x == b'1').count();
+		let size = (self.len() - zcount) * 733 / 1000 + 1;
+		let mut buffer = vec![0u8; size];
 
-			let mut c = B58_DIGITS_MAP[b58[i] as usize] as u64;
-			let mut j = out.len();
-			while j != 0 {
-				j -= 1;
-				let t = out[j] as u64 * 58 + c;
-				c = (t & 0x3f00000000) >> 32;
-				out[j] = (t & 0xffffffff) as u32;
+		for (i, c) in self.bytes().enumerate().skip(zcount) {
+			if (c & 0x80) != 0 || B58_DIGITS_MAP[c as usize] == -1 {
+				// Invalid base58 digit
+				Err(ErrorKind::InvalidBase58Character(c as char, i))?;
 			}
 
-			if c != 0 {
-				// Output number too big (carry to the next int32)
-				Err(ErrorKind::InvalidBase58Length)?;
+			let mut carry = B58_DIGITS_MAP[c as usize] as u32;
+			for byte in buffer.iter_mut().rev() {
+				carry += 58 * *byte as u32;
+				*byte = (carry % 256) as u8;
+				carry /= 256;
 			}
 
-			if (out[0] & zeromask) != 0 {
-				// Output number too big (last int32 filled too far)
+			if carry != 0 {
+				// Output number too big
 				Err(ErrorKind::InvalidBase58Length)?;
 			}
-
-			i += 1;
-		}
-
-		let mut i = 1;
-		let mut j = 0;
-
-		bin[0] = match bytesleft {
-			3 => ((out[0] & 0xff0000) >> 16) as u8,
-			2 => ((out[0] & 0xff00) >> 8) as u8,
-			1 => {
-				j = 1;
-				(out[0] & 0xff) as u8
-			}
-			_ => {
-				i = 0;
-				bin[0]
-			}
-		};
-
-		while j < out.len() {
-			bin[i] = ((out[j] >> 0x18) & 0xff) as u8;
-			bin[i + 1] = ((out[j] >> 0x10) & 0xff) as u8;
-			bin[i + 2] = ((out[j] >> 8) & 0xff) as u8;
-			bin[i + 3] = ((out[j] >> 0) & 0xff) as u8;
-			i += 4;
-			j += 1;
 		}
 
-		let leading_zeros = bin.iter().take_while(|x| **x == 0).count();
-		Ok(bin[leading_zeros - zcount..].to_vec())
+		let leading_zeros = buffer.iter().take_while(|x| **x == 0).count();
+		let mut result = vec![0u8; zcount];
+		result.extend_from_slice(&buffer[leading_zeros..]);
+		Ok(result)
 	}
 
 	fn from_base58_check(&self, version_bytes: usize) -> Result<(Vec<u8>, Vec<u8>)> {
@@ -14,14 +14,16 @@
 
 //! High level JSON/HTTP client API
 
+use super::socks;
 use clap::crate_version;
 use failure::{Backtrace, Context, Fail, ResultExt};
-use futures::future::{err, ok, Either};
+use futures::future::{err, ok};
 use grin_util::to_base64;
 use http::uri::{InvalidUri, Uri};
-use hyper::header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE, USER_AGENT};
+use http::StatusCode;
+use hyper::header::{HeaderValue, ACCEPT, AUTHORIZATION, CONTENT_TYPE, USER_AGENT};
 use hyper::rt::{Future, Stream};
-use hyper::{Body, Client, Request};
+use hyper::{Body, Client, Request, Response};
 use hyper_rustls;
 use serde::{Deserialize, Serialize};
 use serde_json;
@@ -43,6 +45,40 @@ pub enum ErrorKind {
 	RequestError(String),
 	#[fail(display = "ResponseError error: {}", _0)]
 	ResponseError(String),
+	#[fail(display = "Connection error: {}", _0)]
+	Connection(String),
+	#[fail(display = "Wrong response code: {} with data {:?}", _0, _1)]
+	ResponseStatus(StatusCode, String),
+}
+
+impl Error {
+	pub fn kind(&self) -> ErrorKind {
+		self.inner.get_context().clone()
+	}
+
+	/// Whether the request never reached the server
+	pub fn is_connection(&self) -> bool {
+		match self.kind() {
+			ErrorKind::Connection(_) => true,
+			_ => false,
+		}
+	}
+
+	/// HTTP status of a response that wasn't successful
+	pub fn status(&self) -> Option<StatusCode> {
+		match self.kind() {
+			ErrorKind::ResponseStatus(s, _) => Some(s),
+			_ => None,
+		}
+	}
+
+	/// Body of a response that wasn't successful
+	pub fn body(&self) -> Option<String> {
+		match self.kind() {
+			ErrorKind::ResponseStatus(_, b) => Some(b),
+			_ => None,
+		}
+	}
 }
 
 impl Fail for Error {
@@ -113,6 +149,24 @@ where
 	Ok(())
 }
 
+/// Helper function to issue a HTTP POST request with a JSON body that was
+/// already serialized, and additional headers. The response is ignored
+/// apart from its code.
+pub fn post_raw(
+	url: &str,
+	body: String,
+	headers: Vec<(&'static str, String)>,
+) -> Result<(), Error> {
+	let mut req = build_request(url, "POST", None, Some(body))?;
+	for (name, value) in headers {
+		let value = HeaderValue::from_str(&value)
+			.map_err(|e| ErrorKind::Argument(format!("Invalid header {}: {}", name, e)))?;
+		req.headers_mut().insert(name, value);
+	}
+	send_request(req)?;
+	Ok(())
+}
+
 fn build_request(
 	url: &str,
 	method: &str,
@@ -182,31 +236,42 @@ where
 }
 
 fn send_request_async(req: Request<Body>) -> Box<dyn Future<Item = String, Error = Error> + Send> {
-	let https = hyper_rustls::HttpsConnector::new(1);
-	let client = Client::builder().build::<_, Body>(https);
+	let response: Box<dyn Future<Item = Response<Body>, Error = hyper::Error> + Send> =
+		match socks::proxy() {
+			Some(proxy) => {
+				let client = Client::builder().build::<_, Body>(socks::https_connector(proxy));
+				Box::new(client.request(req))
+			}
+			None => {
+				let https = hyper_rustls::HttpsConnector::new(1);
+				let client = Client::builder().build::<_, Body>(https);
+				Box::new(client.request(req))
+			}
+		};
 	Box::new(
-		client
-			.request(req)
-			.map_err(|e| ErrorKind::RequestError(format!("Cannot make request: {}", e)).into())
-			.and_then(|resp| {
-				if !resp.status().is_success() {
-					Either::A(err(ErrorKind::RequestError(format!(
-						"Wrong response code: {} with data {:?}",
-						resp.status(),
-						resp.body()
-					))
-					.into()))
+		response
+			.map_err(|e| {
+				if e.is_connect() {
+					ErrorKind::Connection(format!("Cannot connect: {}", e)).into()
 				} else {
-					Either::B(
-						resp.into_body()
-							.map_err(|e| {
-								ErrorKind::RequestError(format!("Cannot read response body: {}", e))
-									.into()
-							})
-							.concat2()
-							.and_then(|ch| ok(String::from_utf8_lossy(&ch.to_vec()).to_string())),
-					)
+					ErrorKind::RequestError(format!("Cannot make request: {}", e)).into()
 				}
+			})
+			.and_then(|resp| {
+				let status = resp.status();
+				resp.into_body()
+					.map_err(|e| {
+						ErrorKind::RequestError(format!("Cannot read response body: {}", e)).into()
+					})
+					.concat2()
+					.and_then(move |ch| {
+						let body = String::from_utf8_lossy(&ch.to_vec()).to_string();
+						if !status.is_success() {
+							err(ErrorKind::ResponseStatus(status, body).into())
+						} else {
+							ok(body)
+						}
+					})
 			}),
 	)
 }
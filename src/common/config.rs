@@ -40,10 +40,14 @@ pub struct Wallet713Config {
 	pub grinbox_port: Option<u16>,
 	pub grinbox_protocol_unsecure: Option<bool>,
 	pub grinbox_address_index: Option<u32>,
+	pub grinbox_listen_indices: Option<Vec<u32>>,
+	pub grinbox_listen_window: Option<u32>,
 	pub grin_node_uri: Option<String>,
 	pub grin_node_secret: Option<String>,
+	pub grin_node_broadcast: Option<usize>,
 	pub grinbox_listener_auto_start: Option<bool>,
 	pub keybase_listener_auto_start: Option<bool>,
+	pub keybase_binary: Option<String>,
 	pub max_auto_accept_invoice: Option<u64>,
 	pub default_keybase_ttl: Option<String>,
 	pub owner_api: Option<bool>,
@@ -54,10 +58,122 @@ pub struct Wallet713Config {
 	pub foreign_api_address: Option<String>,
 	pub foreign_api_secret: Option<String>,
 	pub check_updates: Option<bool>,
+	pub offline: Option<bool>,
+	pub tx_repost_blocks: Option<u64>,
+	pub tx_expire_blocks: Option<u64>,
+	pub tx_ttl_blocks: Option<u64>,
+	pub incoming_approval: Option<bool>,
+	pub proxy: Option<String>,
+	// Tables have to be serialized after all plain values
+	pub grin_nodes: Option<Vec<NodeConfig>>,
+	pub receive_policy: Option<ReceivePolicy>,
+	pub relay: Option<RelayConfig>,
+	pub webhooks: Option<Vec<WebhookConfig>>,
 	#[serde(skip)]
 	pub config_home: Option<String>,
 }
 
+/// Additional grin node the wallet can fail over to
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct NodeConfig {
+	pub uri: String,
+	pub secret: Option<String>,
+}
+
+/// Rules a slate received from a peer has to pass before the wallet signs it
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ReceivePolicy {
+	/// Smallest amount accepted, in nanogrins
+	pub min_amount: Option<u64>,
+	/// Largest amount accepted, in nanogrins
+	pub max_amount: Option<u64>,
+	/// Regular expression the sender's message has to match
+	pub message_pattern: Option<String>,
+	/// Only accept slates from these addresses or `@contact` names
+	pub allow: Option<Vec<String>>,
+	/// Never accept slates from these addresses or `@contact` names
+	pub deny: Option<Vec<String>>,
+	/// Most new slates accepted from a single sender per hour
+	pub max_slates_per_hour: Option<u32>,
+	/// Most outputs of received transactions that may be unconfirmed at the same time
+	pub max_pending_outputs: Option<u32>,
+}
+
+/// Grinbox relay started with `--relay`
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RelayConfig {
+	pub listen_address: Option<String>,
+	pub max_subscriptions: Option<usize>,
+	pub max_queued_slates: Option<usize>,
+	pub max_total_queued_slates: Option<usize>,
+	pub max_posts_per_minute: Option<usize>,
+	pub max_ip_posts_per_minute: Option<usize>,
+	pub max_slate_size: Option<usize>,
+	pub queue_ttl_secs: Option<u64>,
+}
+
+impl RelayConfig {
+	pub fn listen_address(&self) -> String {
+		self.listen_address
+			.clone()
+			.unwrap_or(String::from("127.0.0.1:13420"))
+	}
+
+	/// Connections that can subscribe to the same address at once
+	pub fn max_subscriptions(&self) -> usize {
+		self.max_subscriptions.unwrap_or(4)
+	}
+
+	/// Slates held for an address while nobody is subscribed to it
+	pub fn max_queued_slates(&self) -> usize {
+		self.max_queued_slates.unwrap_or(100)
+	}
+
+	/// Slates held for all addresses together
+	pub fn max_total_queued_slates(&self) -> usize {
+		self.max_total_queued_slates.unwrap_or(10000)
+	}
+
+	/// Slates an address, or a single connection, can post per minute
+	pub fn max_posts_per_minute(&self) -> usize {
+		self.max_posts_per_minute.unwrap_or(30)
+	}
+
+	/// Slates all connections from one IP can post per minute, 0 disables
+	pub fn max_ip_posts_per_minute(&self) -> usize {
+		self.max_ip_posts_per_minute.unwrap_or(120)
+	}
+
+	/// Largest encrypted slate the relay accepts, in bytes
+	pub fn max_slate_size(&self) -> usize {
+		self.max_slate_size.unwrap_or(262144)
+	}
+
+	/// Queued slates are dropped after this many seconds
+	pub fn queue_ttl_secs(&self) -> u64 {
+		self.queue_ttl_secs.unwrap_or(86400)
+	}
+}
+
+/// Endpoint that wallet events are posted to
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WebhookConfig {
+	pub url: String,
+	/// Key of the HMAC-SHA256 signature sent with each event
+	pub secret: String,
+	/// Names of the events to post, all of them if unset
+	pub events: Option<Vec<String>>,
+}
+
+impl WebhookConfig {
+	pub fn wants(&self, event: &str) -> bool {
+		match &self.events {
+			Some(events) => events.iter().any(|e| e == event),
+			None => true,
+		}
+	}
+}
+
 impl Wallet713Config {
 	pub fn exists(config_path: Option<&str>, chain: &Option<ChainTypes>) -> Result<bool> {
 		let default_path_buf = Wallet713Config::default_config_path(chain)?;
@@ -141,6 +257,21 @@ impl Wallet713Config {
 		self.grinbox_address_index.unwrap_or(0)
 	}
 
+	/// Address indices the grinbox listener subscribes to: the current one,
+	/// the `grinbox_listen_window` ones before it and `grinbox_listen_indices`
+	pub fn grinbox_listen_indices(&self) -> Vec<u32> {
+		let index = self.grinbox_address_index();
+		let window = self.grinbox_listen_window.unwrap_or(0);
+		let extra = self.grinbox_listen_indices.clone().unwrap_or(vec![]);
+		let mut indices = vec![index];
+		for i in (index.saturating_sub(window)..index).rev().chain(extra) {
+			if !indices.contains(&i) {
+				indices.push(i);
+			}
+		}
+		indices
+	}
+
 	pub fn get_data_path(&self) -> Result<PathBuf> {
 		let mut data_path = PathBuf::new();
 		data_path.push(self.wallet713_data_path.clone());
@@ -178,6 +309,74 @@ impl Wallet713Config {
 		}
 	}
 
+	/// All configured nodes, in order of preference. The node set by
+	/// `grin_node_uri` comes first, the vault713 node is only used if no node is set
+	pub fn grin_nodes(&self) -> Vec<NodeConfig> {
+		let mut nodes = Vec::new();
+		let extra_nodes = self.grin_nodes.clone().unwrap_or(vec![]);
+		if self.grin_node_uri.is_some() || extra_nodes.is_empty() {
+			nodes.push(NodeConfig {
+				uri: self.grin_node_uri(),
+				secret: self.grin_node_secret(),
+			});
+		}
+		for node in extra_nodes {
+			if !nodes.iter().any(|n| n.uri == node.uri) {
+				nodes.push(node);
+			}
+		}
+		nodes
+	}
+
+	/// Run without a node connection, using imported output snapshots
+	pub fn offline(&self) -> bool {
+		self.offline.unwrap_or(false)
+	}
+
+	/// Number of nodes a transaction is posted to
+	pub fn grin_node_broadcast(&self) -> usize {
+		self.grin_node_broadcast.unwrap_or(1).max(1)
+	}
+
+	/// Unconfirmed sent transactions are reposted every this many blocks, 0 disables
+	pub fn tx_repost_blocks(&self) -> u64 {
+		self.tx_repost_blocks.unwrap_or(30)
+	}
+
+	/// Unconfirmed sent transactions are cancelled after this many blocks, 0 disables
+	pub fn tx_expire_blocks(&self) -> u64 {
+		self.tx_expire_blocks.unwrap_or(1440)
+	}
+
+	/// Grinbox and keybase sends the recipient hasn't answered are cancelled after
+	/// this many blocks, 0 disables
+	pub fn tx_ttl_blocks(&self) -> u64 {
+		self.tx_ttl_blocks.unwrap_or(1440)
+	}
+
+	/// Hold slates received over grinbox and keybase until they are accepted
+	pub fn incoming_approval(&self) -> bool {
+		self.incoming_approval.unwrap_or(false)
+	}
+
+	/// SOCKS5 proxy outbound connections go through, `host:port`
+	pub fn proxy(&self) -> Option<String> {
+		self.proxy.clone()
+	}
+
+	/// Rules for slates received from peers, nothing is enforced if unset
+	pub fn receive_policy(&self) -> ReceivePolicy {
+		self.receive_policy.clone().unwrap_or_default()
+	}
+
+	pub fn relay(&self) -> RelayConfig {
+		self.relay.clone().unwrap_or_default()
+	}
+
+	pub fn webhooks(&self) -> Vec<WebhookConfig> {
+		self.webhooks.clone().unwrap_or_default()
+	}
+
 	pub fn grinbox_listener_auto_start(&self) -> bool {
 		self.grinbox_listener_auto_start.unwrap_or(is_cli())
 	}
@@ -186,6 +385,13 @@ impl Wallet713Config {
 		self.keybase_listener_auto_start.unwrap_or(false)
 	}
 
+	/// Path of the keybase executable
+	pub fn keybase_binary(&self) -> String {
+		self.keybase_binary
+			.clone()
+			.unwrap_or(String::from("keybase"))
+	}
+
 	pub fn owner_api_address(&self) -> String {
 		let chain_type = self.chain.as_ref().unwrap_or(&ChainTypes::Mainnet);
 		self.owner_api_address
@@ -22,6 +22,7 @@ use std::fmt::Write;
 
 pub const GRINBOX_ADDRESS_VERSION_MAINNET: [u8; 2] = [1, 11];
 pub const GRINBOX_ADDRESS_VERSION_TESTNET: [u8; 2] = [1, 120];
+const SIGNED_MESSAGE_PREFIX: &str = "wallet713 signed message:\n";
 
 pub trait Hex<T> {
 	fn from_hex(str: &str) -> Result<T>;
@@ -139,6 +140,20 @@ pub fn verify_signature(
 		.map_err(|_| ErrorKind::Secp.into())
 }
 
+/// Signs a message for others to check, prefixed so that it can't be used
+/// to answer a relay challenge
+pub fn sign_message(message: &str, secret_key: &SecretKey) -> Result<Signature> {
+	sign_challenge(&format!("{}{}", SIGNED_MESSAGE_PREFIX, message), secret_key)
+}
+
+pub fn verify_message(message: &str, signature: &Signature, public_key: &PublicKey) -> Result<()> {
+	verify_signature(
+		&format!("{}{}", SIGNED_MESSAGE_PREFIX, message),
+		signature,
+		public_key,
+	)
+}
+
 /// Encode the provided bytes into a hex string
 pub fn to_hex(bytes: Vec<u8>) -> String {
 	let mut s = String::new();
@@ -38,6 +38,10 @@ pub enum ErrorKind {
 	ClosedListener(String),
 	#[fail(display = "Contact '{}' already exists!", 0)]
 	ContactAlreadyExists(String),
+	#[fail(display = "Invalid contact name '{}'", _0)]
+	InvalidContactName(String),
+	#[fail(display = "Invalid contacts file: {}", _0)]
+	InvalidContactsFile(String),
 	#[fail(display = "Invalid base58 character!")]
 	InvalidBase58Character(char, usize),
 	#[fail(display = "Invalid base58 length")]
@@ -64,6 +68,8 @@ pub enum ErrorKind {
 	KeybaseMessageSendError,
 	#[fail(display = "Keybase not found! Consider installing it first")]
 	KeybaseNotFound,
+	#[fail(display = "Keybase API error: {}", _0)]
+	KeybaseApiError(String),
 	#[fail(display = "Grinbox websocket terminated unexpectedly")]
 	GrinboxWebsocketAbnormalTermination,
 	#[fail(display = "Unable to encrypt message")]
@@ -72,6 +78,8 @@ pub enum ErrorKind {
 	Decryption,
 	#[fail(display = "Restore error")]
 	Restore,
+	#[fail(display = "Scan cancelled")]
+	ScanCancelled,
 	#[fail(display = "Unknown account '{}'", 0)]
 	UnknownAccountLabel(String),
 	#[fail(display = "{}", 0)]
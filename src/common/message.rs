@@ -38,27 +38,10 @@ impl EncryptedMessage {
 		receiver_public_key: &PublicKey,
 		secret_key: &SecretKey,
 	) -> Result<EncryptedMessage> {
-		let secp = Secp256k1::new();
-		let mut common_secret = receiver_public_key.clone();
-		common_secret
-			.mul_assign(&secp, secret_key)
-			.map_err(|_| ErrorKind::Encryption)?;
-		let common_secret_ser = common_secret.serialize_vec(&secp, true);
-		let common_secret_slice = &common_secret_ser[1..33];
-
 		let salt: [u8; 8] = thread_rng().gen();
 		let nonce: [u8; 12] = thread_rng().gen();
-		let mut key = [0; 32];
-		pbkdf2::derive(&digest::SHA512, 100, &salt, common_secret_slice, &mut key);
-		let mut enc_bytes = message.as_bytes().to_vec();
-		let suffix_len = aead::CHACHA20_POLY1305.tag_len();
-		for _ in 0..suffix_len {
-			enc_bytes.push(0);
-		}
-		let sealing_key = aead::SealingKey::new(&aead::CHACHA20_POLY1305, &key)
-			.map_err(|_| ErrorKind::Encryption)?;
-		aead::seal_in_place(&sealing_key, &nonce, &[], &mut enc_bytes, suffix_len)
-			.map_err(|_| ErrorKind::Encryption)?;
+		let key = shared_key(receiver_public_key, secret_key, &salt)?;
+		let enc_bytes = seal(&key, &nonce, message.as_bytes().to_vec())?;
 
 		Ok(EncryptedMessage {
 			destination: destination.clone(),
@@ -70,32 +53,52 @@ impl EncryptedMessage {
 
 	pub fn key(&self, sender_public_key: &PublicKey, secret_key: &SecretKey) -> Result<[u8; 32]> {
 		let salt = from_hex(self.salt.clone()).map_err(|_| ErrorKind::Decryption)?;
-
-		let secp = Secp256k1::new();
-		let mut common_secret = sender_public_key.clone();
-		common_secret
-			.mul_assign(&secp, secret_key)
-			.map_err(|_| ErrorKind::Decryption)?;
-		let common_secret_ser = common_secret.serialize_vec(&secp, true);
-		let common_secret_slice = &common_secret_ser[1..33];
-
-		let mut key = [0; 32];
-		pbkdf2::derive(&digest::SHA512, 100, &salt, common_secret_slice, &mut key);
-
-		Ok(key)
+		shared_key(sender_public_key, secret_key, &salt).map_err(|_| ErrorKind::Decryption.into())
 	}
 
 	pub fn decrypt_with_key(&self, key: &[u8; 32]) -> Result<String> {
-		let mut encrypted_message =
+		let encrypted_message =
 			from_hex(self.encrypted_message.clone()).map_err(|_| ErrorKind::Decryption)?;
 		let nonce = from_hex(self.nonce.clone()).map_err(|_| ErrorKind::Decryption)?;
+		let decrypted_data = open(key, &nonce, encrypted_message)?;
 
-		let opening_key = aead::OpeningKey::new(&aead::CHACHA20_POLY1305, key)
-			.map_err(|_| ErrorKind::Decryption)?;
-		let decrypted_data =
-			aead::open_in_place(&opening_key, &nonce, &[], 0, &mut encrypted_message)
-				.map_err(|_| ErrorKind::Decryption)?;
+		String::from_utf8(decrypted_data).map_err(|_| ErrorKind::Decryption.into())
+	}
+}
 
-		String::from_utf8(decrypted_data.to_vec()).map_err(|_| ErrorKind::Decryption.into())
+/// Derive a symmetric key from the Diffie-Hellman secret of both parties
+pub fn shared_key(public_key: &PublicKey, secret_key: &SecretKey, salt: &[u8]) -> Result<[u8; 32]> {
+	let secp = Secp256k1::new();
+	let mut common_secret = public_key.clone();
+	common_secret
+		.mul_assign(&secp, secret_key)
+		.map_err(|_| ErrorKind::Encryption)?;
+	let common_secret_ser = common_secret.serialize_vec(&secp, true);
+	let common_secret_slice = &common_secret_ser[1..33];
+
+	let mut key = [0; 32];
+	pbkdf2::derive(&digest::SHA512, 100, salt, common_secret_slice, &mut key);
+	Ok(key)
+}
+
+/// Encrypt and authenticate data with a shared key
+pub fn seal(key: &[u8; 32], nonce: &[u8], mut data: Vec<u8>) -> Result<Vec<u8>> {
+	let suffix_len = aead::CHACHA20_POLY1305.tag_len();
+	for _ in 0..suffix_len {
+		data.push(0);
 	}
+	let sealing_key =
+		aead::SealingKey::new(&aead::CHACHA20_POLY1305, key).map_err(|_| ErrorKind::Encryption)?;
+	aead::seal_in_place(&sealing_key, nonce, &[], &mut data, suffix_len)
+		.map_err(|_| ErrorKind::Encryption)?;
+	Ok(data)
+}
+
+/// Decrypt data that was sealed with the same shared key
+pub fn open(key: &[u8; 32], nonce: &[u8], mut data: Vec<u8>) -> Result<Vec<u8>> {
+	let opening_key =
+		aead::OpeningKey::new(&aead::CHACHA20_POLY1305, key).map_err(|_| ErrorKind::Decryption)?;
+	let decrypted_data = aead::open_in_place(&opening_key, nonce, &[], 0, &mut data)
+		.map_err(|_| ErrorKind::Decryption)?;
+	Ok(decrypted_data.to_vec())
 }
@@ -23,6 +23,7 @@ pub mod hasher;
 pub mod message;
 pub mod motd;
 pub mod ser;
+pub mod socks;
 
 pub use self::error_kind::ErrorKind;
 pub use self::macros::*;
@@ -12,10 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use super::client;
 use clap::crate_version;
 use colored::Colorize;
 use failure::Error;
-use grin_api::client;
 use semver::Version;
 use serde::{Deserialize, Serialize};
 use std::io;
@@ -0,0 +1,341 @@
+// Copyright 2019 The vault713 Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! SOCKS5 client for outbound connections. Destinations are sent to the
+//! proxy by name, so they are resolved by the proxy and not locally
+
+use futures::future::{self, Future};
+use hyper::client::connect::{Connect, Connected, Destination};
+use hyper_rustls::HttpsConnector;
+use lazy_static::lazy_static;
+use rustls::ClientConfig;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::RwLock;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+use tokio::io::{read_exact, write_all};
+use tokio::net::TcpStream as AsyncTcpStream;
+
+const VERSION: u8 = 5;
+const NO_AUTHENTICATION: u8 = 0;
+const CONNECT: u8 = 1;
+const IPV4: u8 = 1;
+const DOMAIN_NAME: u8 = 3;
+const IPV6: u8 = 4;
+const SUCCEEDED: u8 = 0;
+
+/// Offer a single authentication method, none
+const GREETING: [u8; 3] = [VERSION, 1, NO_AUTHENTICATION];
+/// How long a non-blocking handshake waits for the proxy
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(30);
+
+lazy_static! {
+	/// Proxy all outbound connections go through, `host:port`
+	static ref PROXY: RwLock<Option<String>> = RwLock::new(None);
+}
+
+pub fn set_proxy(proxy: Option<String>) {
+	*PROXY.write().unwrap() = proxy;
+}
+
+pub fn proxy() -> Option<String> {
+	PROXY.read().unwrap().clone()
+}
+
+/// Connect to `host:port` through the proxy
+pub fn connect(proxy: &str, host: &str, port: u16) -> io::Result<TcpStream> {
+	let mut stream = TcpStream::connect(proxy)?;
+	handshake(&mut stream, host, port)?;
+	Ok(stream)
+}
+
+/// Ask the proxy a stream is connected to for a connection to `host:port`
+pub fn handshake<S: Read + Write>(stream: &mut S, host: &str, port: u16) -> io::Result<()> {
+	stream.write_all(&GREETING)?;
+	let mut method = [0u8; 2];
+	stream.read_exact(&mut method)?;
+	check_method(&method)?;
+
+	stream.write_all(&connect_request(host, port)?)?;
+	let mut head = [0u8; 5];
+	stream.read_exact(&mut head)?;
+	let mut bound_address = vec![0u8; check_reply(&head)?];
+	stream.read_exact(&mut bound_address)?;
+	Ok(())
+}
+
+/// Blocking reads and writes on a non-blocking stream, which may still be
+/// connecting, for the few bytes of a handshake
+pub struct Blocking<'a, S> {
+	stream: &'a mut S,
+	deadline: Instant,
+}
+
+impl<'a, S> Blocking<'a, S> {
+	pub fn new(stream: &'a mut S) -> Self {
+		Self {
+			stream,
+			deadline: Instant::now() + HANDSHAKE_TIMEOUT,
+		}
+	}
+
+	fn retry<T, F>(&mut self, mut f: F) -> io::Result<T>
+	where
+		F: FnMut(&mut S) -> io::Result<T>,
+	{
+		loop {
+			match f(&mut *self.stream) {
+				Err(ref e)
+					if e.kind() == io::ErrorKind::WouldBlock
+						|| e.kind() == io::ErrorKind::NotConnected =>
+				{
+					if Instant::now() > self.deadline {
+						return Err(io::Error::new(
+							io::ErrorKind::TimedOut,
+							"proxy didn't answer",
+						));
+					}
+					sleep(Duration::from_millis(10));
+				}
+				result => return result,
+			}
+		}
+	}
+}
+
+impl<'a, S: Read> Read for Blocking<'a, S> {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		self.retry(|s| s.read(buf))
+	}
+}
+
+impl<'a, S: Write> Write for Blocking<'a, S> {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		self.retry(|s| s.write(buf))
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		self.retry(|s| s.flush())
+	}
+}
+
+/// Connect to `host:port` through the proxy, without blocking
+pub fn connect_async(
+	proxy: &str,
+	host: &str,
+	port: u16,
+) -> Box<dyn Future<Item = AsyncTcpStream, Error = io::Error> + Send> {
+	let proxy = match resolve(proxy) {
+		Ok(p) => p,
+		Err(e) => return Box::new(future::err(e)),
+	};
+	let request = match connect_request(host, port) {
+		Ok(r) => r,
+		Err(e) => return Box::new(future::err(e)),
+	};
+
+	Box::new(
+		AsyncTcpStream::connect(&proxy)
+			.and_then(|s| write_all(s, GREETING))
+			.and_then(|(s, _)| read_exact(s, [0u8; 2]))
+			.and_then(|(s, method)| check_method(&method).map(|_| s))
+			.and_then(move |s| write_all(s, request))
+			.and_then(|(s, _)| read_exact(s, [0u8; 5]))
+			.and_then(|(s, head)| {
+				future::result(check_reply(&head))
+					.and_then(move |len| read_exact(s, vec![0u8; len]))
+			})
+			.map(|(s, _)| s),
+	)
+}
+
+/// Connector for hyper clients that goes through the proxy
+#[derive(Clone)]
+pub struct SocksConnector {
+	proxy: String,
+}
+
+impl SocksConnector {
+	pub fn new(proxy: String) -> Self {
+		Self { proxy }
+	}
+}
+
+impl Connect for SocksConnector {
+	type Transport = AsyncTcpStream;
+	type Error = io::Error;
+	type Future = Box<dyn Future<Item = (AsyncTcpStream, Connected), Error = io::Error> + Send>;
+
+	fn connect(&self, dst: Destination) -> Self::Future {
+		let port = dst.port().unwrap_or(match dst.scheme() {
+			"https" => 443,
+			_ => 80,
+		});
+		Box::new(connect_async(&self.proxy, dst.host(), port).map(|s| (s, Connected::new())))
+	}
+}
+
+/// HTTP and HTTPS connector through the proxy
+pub fn https_connector(proxy: String) -> HttpsConnector<SocksConnector> {
+	let mut config = ClientConfig::new();
+	config
+		.root_store
+		.add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
+	HttpsConnector::from((SocksConnector::new(proxy), config))
+}
+
+fn resolve(proxy: &str) -> io::Result<SocketAddr> {
+	proxy.to_socket_addrs()?.next().ok_or(io::Error::new(
+		io::ErrorKind::InvalidInput,
+		"invalid proxy address",
+	))
+}
+
+fn connect_request(host: &str, port: u16) -> io::Result<Vec<u8>> {
+	if host.len() > 255 {
+		return Err(io::Error::new(
+			io::ErrorKind::InvalidInput,
+			"host name is too long",
+		));
+	}
+	let mut request = vec![VERSION, CONNECT, 0, DOMAIN_NAME, host.len() as u8];
+	request.extend_from_slice(host.as_bytes());
+	request.extend_from_slice(&port.to_be_bytes());
+	Ok(request)
+}
+
+fn check_method(reply: &[u8; 2]) -> io::Result<()> {
+	if reply[0] != VERSION || reply[1] != NO_AUTHENTICATION {
+		return Err(io::Error::new(
+			io::ErrorKind::Other,
+			"proxy requires authentication",
+		));
+	}
+	Ok(())
+}
+
+/// Check the head of the reply to a connect request, which ends with the
+/// first byte of the bound address. Returns the number of bytes left
+fn check_reply(head: &[u8; 5]) -> io::Result<usize> {
+	if head[0] != VERSION {
+		return Err(io::Error::new(io::ErrorKind::Other, "not a SOCKS5 proxy"));
+	}
+	if head[1] != SUCCEEDED {
+		let reason = match head[1] {
+			1 => "general failure",
+			2 => "connection not allowed",
+			3 => "network unreachable",
+			4 => "host unreachable",
+			5 => "connection refused",
+			6 => "TTL expired",
+			7 => "command not supported",
+			8 => "address type not supported",
+			_ => "unknown error",
+		};
+		return Err(io::Error::new(
+			io::ErrorKind::Other,
+			format!("proxy failed to connect: {}", reason),
+		));
+	}
+	// Rest of the address and the port
+	match head[3] {
+		IPV4 => Ok(3 + 2),
+		DOMAIN_NAME => Ok(head[4] as usize + 2),
+		IPV6 => Ok(15 + 2),
+		_ => Err(io::Error::new(io::ErrorKind::Other, "invalid proxy reply")),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::net::TcpListener;
+	use std::sync::mpsc::{channel, Receiver};
+	use std::thread::spawn;
+	use tokio::runtime::Runtime;
+
+	/// SOCKS5 proxy on a loopback port that serves a single connection.
+	/// It reports the requested destination and echoes what it receives
+	fn fake_proxy() -> (String, Receiver<(String, u16)>) {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let address = listener.local_addr().unwrap().to_string();
+		let (sender, receiver) = channel();
+		spawn(move || {
+			let (mut stream, _) = listener.accept().unwrap();
+			let mut greeting = [0u8; 3];
+			stream.read_exact(&mut greeting).unwrap();
+			assert_eq!(greeting, GREETING);
+			stream.write_all(&[VERSION, NO_AUTHENTICATION]).unwrap();
+
+			let mut head = [0u8; 5];
+			stream.read_exact(&mut head).unwrap();
+			assert_eq!(head[..4], [VERSION, CONNECT, 0, DOMAIN_NAME]);
+			let mut host = vec![0u8; head[4] as usize];
+			stream.read_exact(&mut host).unwrap();
+			let mut port = [0u8; 2];
+			stream.read_exact(&mut port).unwrap();
+			sender
+				.send((String::from_utf8(host).unwrap(), u16::from_be_bytes(port)))
+				.unwrap();
+
+			let bound = [VERSION, SUCCEEDED, 0, IPV4, 127, 0, 0, 1, 0, 0];
+			stream.write_all(&bound).unwrap();
+			let mut echo = stream.try_clone().unwrap();
+			let _ = io::copy(&mut stream, &mut echo);
+		});
+		(address, receiver)
+	}
+
+	fn echo(mut stream: TcpStream) -> Vec<u8> {
+		stream.write_all(b"ping").unwrap();
+		let mut reply = [0u8; 4];
+		stream.read_exact(&mut reply).unwrap();
+		reply.to_vec()
+	}
+
+	#[test]
+	fn connect_through_proxy() {
+		let (proxy, requested) = fake_proxy();
+		let stream = connect(&proxy, "grinbox.io", 13420).unwrap();
+		assert_eq!(requested.recv().unwrap(), ("grinbox.io".to_owned(), 13420));
+		assert_eq!(echo(stream), b"ping");
+	}
+
+	#[test]
+	fn connect_async_through_proxy() {
+		let (proxy, requested) = fake_proxy();
+		let mut rt = Runtime::new().unwrap();
+		let stream = rt
+			.block_on(connect_async(&proxy, "grinbox.io", 443))
+			.unwrap();
+		assert_eq!(requested.recv().unwrap(), ("grinbox.io".to_owned(), 443));
+
+		let (stream, _) = rt.block_on(write_all(stream, b"ping")).unwrap();
+		let (_, reply) = rt.block_on(read_exact(stream, [0u8; 4])).unwrap();
+		assert_eq!(&reply, b"ping");
+	}
+
+	#[test]
+	fn handshake_on_non_blocking_stream() {
+		let (proxy, requested) = fake_proxy();
+		let mut stream = TcpStream::connect(&proxy).unwrap();
+		stream.set_nonblocking(true).unwrap();
+		handshake(&mut Blocking::new(&mut stream), "grinbox.io", 13420).unwrap();
+		assert_eq!(requested.recv().unwrap(), ("grinbox.io".to_owned(), 13420));
+
+		stream.set_nonblocking(false).unwrap();
+		assert_eq!(echo(stream), b"ping");
+	}
+}
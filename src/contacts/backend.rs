@@ -102,6 +102,8 @@ impl Writeable for Contact {
 		let json = json!({
 			"name": self.name,
 			"address": self.address.to_string(),
+			"fallback_addresses": self.fallback_addresses,
+			"notes": self.notes,
 		});
 		writer.write_bytes(&json.to_string().as_bytes())
 	}
@@ -118,9 +120,51 @@ impl Readable for Contact {
 		let address = parse_address(json["address"].as_str().unwrap())
 			.map_err(|_| CoreError::CorruptedData)?;
 
-		let contact = Contact::new(json["name"].as_str().unwrap(), address)
+		let mut contact = Contact::new(json["name"].as_str().unwrap(), address)
 			.map_err(|_| CoreError::CorruptedData)?;
+		// Absent in contacts stored by older versions
+		if let Some(addresses) = json["fallback_addresses"].as_array() {
+			contact.fallback_addresses = addresses
+				.iter()
+				.filter_map(|a| a.as_str().map(|a| a.to_owned()))
+				.collect();
+		}
+		contact.notes = json["notes"].as_str().map(|n| n.to_owned());
 
 		Ok(contact)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use grin_core::ser::{deserialize_default, ser_vec, ProtocolVersion};
+
+	#[test]
+	fn read_old_contact() {
+		// Older versions only stored a name and a single address
+		let json = r#"{"name":"alice","address":"keybase://alice"}"#;
+		let mut data = (json.len() as u64).to_be_bytes().to_vec();
+		data.extend_from_slice(json.as_bytes());
+
+		let contact: Contact = deserialize_default(&mut &data[..]).unwrap();
+		assert_eq!(contact.name, "alice");
+		assert_eq!(contact.address, "keybase://alice");
+		assert!(contact.fallback_addresses.is_empty());
+		assert_eq!(contact.notes, None);
+	}
+
+	#[test]
+	fn contact_round_trip() {
+		let mut contact = Contact::new("bob", parse_address("keybase://bob").unwrap()).unwrap();
+		contact.fallback_addresses = vec![String::from("https://bob.example.com")];
+		contact.notes = Some(String::from("notes"));
+
+		let data = ser_vec(&contact, ProtocolVersion::local()).unwrap();
+		let read: Contact = deserialize_default(&mut &data[..]).unwrap();
+		assert_eq!(read.name, contact.name);
+		assert_eq!(read.address, contact.address);
+		assert_eq!(read.fallback_addresses, contact.fallback_addresses);
+		assert_eq!(read.notes, contact.notes);
+	}
+}
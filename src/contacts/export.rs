@@ -0,0 +1,252 @@
+// Copyright 2019 The vault713 Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Contacts in files, to move them between wallets
+
+use super::types::{check_contact_name, parse_address, Address, Contact};
+use crate::common::{ErrorKind, Result};
+use std::path::Path;
+
+const CSV_HEADER: [&str; 3] = ["name", "address", "notes"];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ContactsFormat {
+	Json,
+	Csv,
+}
+
+impl ContactsFormat {
+	/// CSV for files ending in `.csv`, JSON otherwise
+	pub fn from_path(path: &str) -> Self {
+		match Path::new(path).extension().and_then(|e| e.to_str()) {
+			Some(e) if e.eq_ignore_ascii_case("csv") => ContactsFormat::Csv,
+			_ => ContactsFormat::Json,
+		}
+	}
+}
+
+pub fn write_contacts(contacts: &Vec<Contact>, format: ContactsFormat) -> Result<String> {
+	match format {
+		ContactsFormat::Json => Ok(serde_json::to_string_pretty(contacts)
+			.map_err(|e| ErrorKind::GenericError(format!("{}", e)))?),
+		ContactsFormat::Csv => {
+			let mut csv = csv_line(&CSV_HEADER);
+			for c in contacts {
+				// Fallback addresses follow the preferred one, separated by spaces
+				let addresses = c.addresses().join(" ");
+				let notes = c.notes.clone().unwrap_or_default();
+				csv.push_str(&csv_line(&[&c.name, &addresses, &notes]));
+			}
+			Ok(csv)
+		}
+	}
+}
+
+/// Parse contacts from a file, checking every name and address
+pub fn read_contacts(data: &str, format: ContactsFormat) -> Result<Vec<Contact>> {
+	let entries: Vec<(String, Vec<String>, Option<String>)> = match format {
+		ContactsFormat::Json => {
+			let contacts: Vec<Contact> = serde_json::from_str(data)
+				.map_err(|e| ErrorKind::InvalidContactsFile(format!("{}", e)))?;
+			contacts
+				.into_iter()
+				.map(|c| {
+					let addresses = c.addresses();
+					(c.name, addresses, c.notes)
+				})
+				.collect()
+		}
+		ContactsFormat::Csv => {
+			let mut records = parse_csv(data)?;
+			if records.first().map(|r| r[0] == CSV_HEADER[0]) == Some(true) {
+				records.remove(0);
+			}
+			let mut entries = Vec::new();
+			for (i, mut record) in records.into_iter().enumerate() {
+				if record.len() < 2 || record.len() > 3 {
+					return Err(ErrorKind::InvalidContactsFile(format!(
+						"record {} should have a name, an address and optionally notes",
+						i + 1
+					)))?;
+				}
+				let notes = match record.len() {
+					3 => Some(record.remove(2)).filter(|n| !n.is_empty()),
+					_ => None,
+				};
+				let addresses = record
+					.remove(1)
+					.split_whitespace()
+					.map(|a| a.to_owned())
+					.collect();
+				entries.push((record.remove(0), addresses, notes));
+			}
+			entries
+		}
+	};
+
+	let mut contacts = Vec::new();
+	for (name, addresses, notes) in entries {
+		let name = name.trim();
+		check_contact_name(name)?;
+		if contacts.iter().any(|c: &Contact| c.name == name) {
+			return Err(ErrorKind::InvalidContactsFile(format!(
+				"contact '{}' appears more than once",
+				name
+			)))?;
+		}
+		let mut parsed: Vec<Box<dyn Address>> = Vec::new();
+		for address in &addresses {
+			parsed.push(parse_address(address.trim()).map_err(|_| {
+				ErrorKind::InvalidContactsFile(format!("invalid address for '{}'", name))
+			})?);
+		}
+		if parsed.is_empty() {
+			return Err(ErrorKind::InvalidContactsFile(format!(
+				"no address for '{}'",
+				name
+			)))?;
+		}
+		let mut contact = Contact::new(name, parse_address(&parsed[0].to_string())?)?;
+		contact.set_addresses(parsed);
+		contact.notes = notes;
+		contacts.push(contact);
+	}
+	Ok(contacts)
+}
+
+fn csv_line(fields: &[&str]) -> String {
+	let fields: Vec<String> = fields
+		.iter()
+		.map(|f| {
+			if f.contains(|c| c == ',' || c == '"' || c == '\n' || c == '\r') {
+				format!("\"{}\"", f.replace('"', "\"\""))
+			} else {
+				f.to_string()
+			}
+		})
+		.collect();
+	format!("{}\n", fields.join(","))
+}
+
+/// Split CSV into records, following RFC 4180 quoting. Empty lines are skipped
+fn parse_csv(data: &str) -> Result<Vec<Vec<String>>> {
+	let mut records = Vec::new();
+	let mut record = Vec::new();
+	let mut field = String::new();
+	let mut quoted = false;
+	let mut chars = data.chars().peekable();
+	while let Some(c) = chars.next() {
+		if quoted {
+			match c {
+				'"' if chars.peek() == Some(&'"') => {
+					chars.next();
+					field.push('"');
+				}
+				'"' => quoted = false,
+				_ => field.push(c),
+			}
+			continue;
+		}
+		match c {
+			'"' => quoted = true,
+			',' => record.push(field.split_off(0)),
+			'\r' => {}
+			'\n' => {
+				record.push(field.split_off(0));
+				if record.len() > 1 || !record[0].is_empty() {
+					records.push(record);
+				}
+				record = Vec::new();
+			}
+			_ => field.push(c),
+		}
+	}
+	if quoted {
+		return Err(ErrorKind::InvalidContactsFile(
+			"unterminated quoted field".to_owned(),
+		))?;
+	}
+	if !field.is_empty() || !record.is_empty() {
+		record.push(field);
+		records.push(record);
+	}
+	Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn contacts() -> Vec<Contact> {
+		let mut alice = Contact::new("alice", parse_address("keybase://alice").unwrap()).unwrap();
+		alice.set_addresses(vec![
+			parse_address("keybase://alice").unwrap(),
+			parse_address("https://alice.example.com:13415").unwrap(),
+		]);
+		alice.notes = Some(String::from("pays \"monthly\", in grin\nsecond line"));
+		let bob = Contact::new("o\"brien,jr", parse_address("keybase://bob").unwrap()).unwrap();
+		vec![alice, bob]
+	}
+
+	fn assert_same(read: &Vec<Contact>, written: &Vec<Contact>) {
+		assert_eq!(read.len(), written.len());
+		for (r, w) in read.iter().zip(written) {
+			assert_eq!(r.name, w.name);
+			assert_eq!(r.addresses(), w.addresses());
+			assert_eq!(r.notes, w.notes);
+		}
+	}
+
+	#[test]
+	fn json_round_trip() {
+		let written = contacts();
+		let data = write_contacts(&written, ContactsFormat::Json).unwrap();
+		let read = read_contacts(&data, ContactsFormat::Json).unwrap();
+		assert_same(&read, &written);
+	}
+
+	#[test]
+	fn csv_round_trip() {
+		let written = contacts();
+		let data = write_contacts(&written, ContactsFormat::Csv).unwrap();
+		assert!(data.starts_with("name,address,notes\n"));
+		let read = read_contacts(&data, ContactsFormat::Csv).unwrap();
+		assert_same(&read, &written);
+	}
+
+	#[test]
+	fn csv_quoting() {
+		let data = "a,\"b,c\",\"say \"\"hi\"\"\"\r\n\n\"multi\nline\",,\nlast";
+		let records = parse_csv(data).unwrap();
+		assert_eq!(
+			records,
+			vec![
+				vec!["a", "b,c", "say \"hi\""],
+				vec!["multi\nline", "", ""],
+				vec!["last"],
+			]
+		);
+		assert!(parse_csv("a,\"unterminated\n").is_err());
+	}
+
+	#[test]
+	fn invalid_contacts() {
+		let csv = ContactsFormat::Csv;
+		assert!(read_contacts("alice\n", csv).is_err());
+		assert!(read_contacts("alice,not an address\n", csv).is_err());
+		assert!(read_contacts("@alice,keybase://alice\n", csv).is_err());
+		assert!(read_contacts("alice,keybase://alice\nalice,keybase://bob\n", csv).is_err());
+		assert!(read_contacts("not json", ContactsFormat::Json).is_err());
+	}
+}
@@ -13,10 +13,12 @@
 // limitations under the License.
 
 mod backend;
+mod export;
 mod types;
 
 pub use self::backend::Backend;
+pub use self::export::{read_contacts, write_contacts, ContactsFormat};
 pub use self::types::{
-	parse_address, Address, AddressBook, AddressBookBackend, AddressType, Contact, GrinboxAddress,
-	KeybaseAddress, DEFAULT_GRINBOX_PORT,
+	check_contact_name, parse_address, Address, AddressBook, AddressBookBackend, AddressType,
+	Contact, GrinboxAddress, KeybaseAddress, DEFAULT_GRINBOX_PORT,
 };
@@ -106,13 +106,55 @@ impl AddressBook {
 		Ok(())
 	}
 
+	/// Store a changed contact, under the name it already has
+	pub fn update_contact(&mut self, contact: &Contact) -> Result<()> {
+		let mut batch = self.backend.batch()?;
+		batch.save_contact(contact)?;
+		batch.commit()?;
+		Ok(())
+	}
+
+	pub fn rename_contact(&mut self, name: &str, new_name: &str) -> Result<()> {
+		if self.get_contact(new_name)?.is_some() {
+			return Err(ErrorKind::ContactAlreadyExists(new_name.to_owned()))?;
+		}
+		let mut contact = match self.get_contact(name)? {
+			Some(c) => c,
+			None => return Ok(()),
+		};
+		contact.name = new_name.to_owned();
+		let mut batch = self.backend.batch()?;
+		batch.delete_contact(name.as_bytes())?;
+		batch.save_contact(&contact)?;
+		batch.commit()?;
+		Ok(())
+	}
+
+	/// Add several contacts at once. Contacts with a name that is already
+	/// taken are skipped, unless `overwrite` is set. Returns the number of
+	/// contacts that were saved
+	pub fn import_contacts(&mut self, contacts: &Vec<Contact>, overwrite: bool) -> Result<usize> {
+		let mut new = Vec::new();
+		for contact in contacts {
+			if overwrite || self.get_contact(&contact.name)?.is_none() {
+				new.push(contact);
+			}
+		}
+		let mut batch = self.backend.batch()?;
+		for contact in &new {
+			batch.save_contact(contact)?;
+		}
+		batch.commit()?;
+		Ok(new.len())
+	}
+
 	pub fn get_contact(&self, name: &str) -> Result<Option<Contact>> {
 		self.backend.get_contact(name.as_bytes())
 	}
 
 	pub fn get_contact_by_address(&mut self, address: &str) -> Result<Option<Contact>> {
 		for contact in self.contacts() {
-			if contact.address == address {
+			if contact.addresses().iter().any(|a| a == address) {
 				return Ok(Some(contact));
 			}
 		}
@@ -124,10 +166,24 @@ impl AddressBook {
 	}
 }
 
+/// Contact names are used as `@name` in place of an address
+pub fn check_contact_name(name: &str) -> Result<()> {
+	if name.is_empty() || name.starts_with('@') || name.contains(char::is_whitespace) {
+		return Err(ErrorKind::InvalidContactName(name.to_owned()))?;
+	}
+	Ok(())
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Contact {
 	pub name: String,
+	/// Preferred address
 	pub address: String,
+	/// Addresses tried in order when the preferred one can't be reached
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub fallback_addresses: Vec<String>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub notes: Option<String>,
 }
 
 impl Contact {
@@ -135,8 +191,31 @@ impl Contact {
 		Ok(Self {
 			name: name.to_string(),
 			address: address.to_string(),
+			fallback_addresses: vec![],
+			notes: None,
 		})
 	}
+
+	/// All addresses, in order of preference
+	pub fn addresses(&self) -> Vec<String> {
+		let mut addresses = vec![self.address.clone()];
+		addresses.extend(self.fallback_addresses.iter().cloned());
+		addresses
+	}
+
+	/// Replace the addresses, the first one is preferred. Duplicates are dropped
+	pub fn set_addresses(&mut self, addresses: Vec<Box<dyn Address>>) {
+		let mut addresses = addresses.iter().map(|a| a.to_string());
+		if let Some(address) = addresses.next() {
+			self.address = address;
+		}
+		self.fallback_addresses.clear();
+		for address in addresses {
+			if address != self.address && !self.fallback_addresses.contains(&address) {
+				self.fallback_addresses.push(address);
+			}
+		}
+	}
 }
 
 impl Display for Contact {
@@ -13,10 +13,11 @@
 // limitations under the License.
 
 use crate::common::ErrorKind;
-use crate::wallet::types::{InitTxArgs, InitTxSendArgs};
+use crate::wallet::types::{InitTxArgs, InitTxSendArgs, ScanStart};
 use clap::ArgMatches;
 use grin_core::core::amount_from_hr_string;
 use std::str::FromStr;
+use uuid::Uuid;
 
 macro_rules! usage {
 	( $r:expr ) => {
@@ -33,7 +34,8 @@ pub enum AccountArgs<'a> {
 #[derive(Clone, Debug)]
 pub enum SendCommandType<'a> {
 	Estimate,
-	File(&'a str),
+	/// File name, armored, grinbox address to encrypt to
+	File(&'a str, bool, Option<&'a str>),
 	Address,
 }
 
@@ -45,16 +47,59 @@ pub enum ProofArgs<'a> {
 
 #[derive(Clone, Debug)]
 pub enum ContactArgs<'a> {
-	Add(&'a str, &'a str),
+	Add(&'a str, Vec<&'a str>, Option<&'a str>),
+	Export(&'a str),
+	Import(&'a str, bool),
+	Notes(&'a str, Option<&'a str>),
 	Remove(&'a str),
+	Rename(&'a str, &'a str),
+	SetAddress(&'a str, Vec<&'a str>),
 }
 
 #[derive(Clone, Debug)]
-pub enum AddressArgs {
+pub enum IncomingArgs<'a> {
+	List,
+	Accept(Uuid),
+	Reject(Uuid, Option<&'a str>),
+}
+
+#[derive(Clone, Debug)]
+pub enum OutboxArgs {
+	List,
+	Retry(Option<u32>),
+	Drop(u32),
+}
+
+#[derive(Clone, Debug)]
+pub enum RequestArgs<'a> {
+	List,
+	/// Amount, memo, expiry in minutes
+	Create(u64, Option<&'a str>, Option<u64>),
+	Cancel(u32),
+}
+
+#[derive(Clone, Debug)]
+pub enum WebhooksArgs {
+	List,
+	Retry,
+	Test,
+}
+
+#[derive(Clone, Debug)]
+pub enum SnapshotArgs<'a> {
+	Request(&'a str),
+	Export(&'a str, &'a str),
+	Import(&'a str),
+}
+
+#[derive(Clone, Debug)]
+pub enum AddressArgs<'a> {
 	Display,
 	Next,
 	Prev,
 	Index(u32),
+	Sign(&'a str, Option<u32>),
+	Verify(&'a str, &'a str, &'a str),
 }
 
 #[derive(Clone, Debug)]
@@ -68,6 +113,12 @@ fn required<'a>(args: &'a ArgMatches, name: &str) -> Result<&'a str, ErrorKind>
 		.ok_or_else(|| ErrorKind::Argument(name.to_owned()))
 }
 
+fn required_values<'a>(args: &'a ArgMatches, name: &str) -> Result<Vec<&'a str>, ErrorKind> {
+	args.values_of(name)
+		.map(|v| v.collect())
+		.ok_or_else(|| ErrorKind::Argument(name.to_owned()))
+}
+
 fn parse<T>(arg: &str) -> Result<T, ErrorKind>
 where
 	T: FromStr,
@@ -109,6 +160,9 @@ pub fn send_command<'a>(
 	if let Some(version) = args.value_of("version") {
 		init_args.target_slate_version = Some(parse(version)?);
 	}
+	if let Some(ttl) = args.value_of("ttl") {
+		init_args.ttl_blocks = Some(parse(ttl)?);
+	}
 
 	let cmd_type = if let Some(address) = args.value_of("address") {
 		init_args.send_args = Some(InitTxSendArgs {
@@ -120,7 +174,12 @@ pub fn send_command<'a>(
 		});
 		SendCommandType::Address
 	} else if let Some(file) = args.value_of("file_name") {
-		SendCommandType::File(file)
+		let encrypt_to = args.value_of("encrypt_to");
+		SendCommandType::File(
+			file,
+			args.is_present("armor") || encrypt_to.is_some(),
+			encrypt_to,
+		)
 	} else if args.is_present("estimate") {
 		init_args.estimate_only = Some(true);
 		SendCommandType::Estimate
@@ -135,6 +194,24 @@ pub fn finalize_command<'a>(args: &'a ArgMatches) -> Result<(&'a str, bool), Err
 	Ok((required(args, "file_name")?, args.is_present("fluff")))
 }
 
+pub fn post_command<'a>(args: &'a ArgMatches) -> Result<(&'a str, bool), ErrorKind> {
+	Ok((required(args, "file_name")?, args.is_present("fluff")))
+}
+
+pub fn snapshot_command<'a>(args: &'a ArgMatches) -> Result<SnapshotArgs<'a>, ErrorKind> {
+	let snapshot_args = match args.subcommand() {
+		("request", Some(args)) => SnapshotArgs::Request(required(args, "file_name")?),
+		("export", Some(args)) => {
+			SnapshotArgs::Export(required(args, "request")?, required(args, "file_name")?)
+		}
+		("import", Some(args)) => SnapshotArgs::Import(required(args, "file_name")?),
+		(_, _) => {
+			usage!(args);
+		}
+	};
+	Ok(snapshot_args)
+}
+
 pub fn repost_command(args: &ArgMatches) -> Result<(u32, bool), ErrorKind> {
 	Ok((parse(required(args, "index")?)?, args.is_present("fluff")))
 }
@@ -143,8 +220,27 @@ pub fn cancel_command(args: &ArgMatches) -> Result<u32, ErrorKind> {
 	Ok(parse(required(args, "index")?)?)
 }
 
-pub fn repair_command(args: &ArgMatches) -> Result<bool, ErrorKind> {
-	Ok(args.is_present("delete_unconfirmed"))
+fn scan_start(args: &ArgMatches, full: bool) -> Result<ScanStart, ErrorKind> {
+	let start = if let Some(height) = args.value_of("from_height") {
+		ScanStart::Height(parse(height)?)
+	} else if let Some(index) = args.value_of("from_index") {
+		ScanStart::Index(parse(index)?)
+	} else if full || args.is_present("full") {
+		ScanStart::full()
+	} else {
+		ScanStart::Resume
+	};
+	Ok(start)
+}
+
+pub fn repair_command(args: &ArgMatches) -> Result<(bool, ScanStart), ErrorKind> {
+	let delete_unconfirmed = args.is_present("delete_unconfirmed");
+	// Locked outputs can be anywhere in the chain, so unlocking them needs a full scan
+	Ok((delete_unconfirmed, scan_start(args, delete_unconfirmed)?))
+}
+
+pub fn restore_command(args: &ArgMatches) -> Result<ScanStart, ErrorKind> {
+	scan_start(args, false)
 }
 
 pub fn listen_command<'a>(args: &'a ArgMatches) -> Result<(&'a str, bool), ErrorKind> {
@@ -174,10 +270,25 @@ pub fn proof_command<'a>(args: &'a ArgMatches) -> Result<ProofArgs<'a>, ErrorKin
 
 pub fn contact_command<'a>(args: &'a ArgMatches) -> Result<ContactArgs<'a>, ErrorKind> {
 	let contact_args = match args.subcommand() {
-		("add", Some(args)) => {
-			ContactArgs::Add(required(args, "name")?, required(args, "address")?)
+		("add", Some(args)) => ContactArgs::Add(
+			required(args, "name")?,
+			required_values(args, "address")?,
+			args.value_of("notes"),
+		),
+		("export", Some(args)) => ContactArgs::Export(required(args, "file_name")?),
+		("import", Some(args)) => {
+			ContactArgs::Import(required(args, "file_name")?, args.is_present("overwrite"))
+		}
+		("notes", Some(args)) => {
+			ContactArgs::Notes(required(args, "name")?, args.value_of("notes"))
 		}
 		("remove", Some(args)) => ContactArgs::Remove(required(args, "name")?),
+		("rename", Some(args)) => {
+			ContactArgs::Rename(required(args, "name")?, required(args, "new_name")?)
+		}
+		("set-address", Some(args)) => {
+			ContactArgs::SetAddress(required(args, "name")?, required_values(args, "address")?)
+		}
 		(_, _) => {
 			usage!(args);
 		}
@@ -185,8 +296,70 @@ pub fn contact_command<'a>(args: &'a ArgMatches) -> Result<ContactArgs<'a>, Erro
 	Ok(contact_args)
 }
 
-pub fn address_command(args: &ArgMatches) -> Result<AddressArgs, ErrorKind> {
-	let address_args = if args.is_present("next") {
+pub fn incoming_command<'a>(args: &'a ArgMatches) -> Result<IncomingArgs<'a>, ErrorKind> {
+	let incoming_args = match args.subcommand() {
+		("accept", Some(args)) => IncomingArgs::Accept(parse(required(args, "id")?)?),
+		("reject", Some(args)) => {
+			IncomingArgs::Reject(parse(required(args, "id")?)?, args.value_of("message"))
+		}
+		_ => IncomingArgs::List,
+	};
+	Ok(incoming_args)
+}
+
+pub fn outbox_command(args: &ArgMatches) -> Result<OutboxArgs, ErrorKind> {
+	let outbox_args = match args.subcommand() {
+		("retry", Some(args)) => match args.value_of("index") {
+			Some(index) => OutboxArgs::Retry(Some(parse(index)?)),
+			None => OutboxArgs::Retry(None),
+		},
+		("drop", Some(args)) => OutboxArgs::Drop(parse(required(args, "index")?)?),
+		_ => OutboxArgs::List,
+	};
+	Ok(outbox_args)
+}
+
+pub fn request_command<'a>(args: &'a ArgMatches) -> Result<RequestArgs<'a>, ErrorKind> {
+	let request_args = match args.subcommand() {
+		("create", Some(args)) => {
+			let amount = required(args, "amount")?;
+			let amount = amount_from_hr_string(amount)
+				.map_err(|_| ErrorKind::ParseNumber(amount.to_owned()))?;
+			let expiry = match args.value_of("expiry") {
+				Some(expiry) => Some(parse(expiry)?),
+				None => None,
+			};
+			RequestArgs::Create(amount, args.value_of("memo"), expiry)
+		}
+		("cancel", Some(args)) => RequestArgs::Cancel(parse(required(args, "index")?)?),
+		_ => RequestArgs::List,
+	};
+	Ok(request_args)
+}
+
+pub fn webhooks_command(args: &ArgMatches) -> Result<WebhooksArgs, ErrorKind> {
+	let webhooks_args = match args.subcommand() {
+		("retry", _) => WebhooksArgs::Retry,
+		("test", _) => WebhooksArgs::Test,
+		_ => WebhooksArgs::List,
+	};
+	Ok(webhooks_args)
+}
+
+pub fn address_command<'a>(args: &'a ArgMatches) -> Result<AddressArgs<'a>, ErrorKind> {
+	let address_args = if let ("sign", Some(args)) = args.subcommand() {
+		let index = match args.value_of("index") {
+			Some(index) => Some(parse(index)?),
+			None => None,
+		};
+		AddressArgs::Sign(required(args, "message")?, index)
+	} else if let ("verify", Some(args)) = args.subcommand() {
+		AddressArgs::Verify(
+			required(args, "address")?,
+			required(args, "message")?,
+			required(args, "signature")?,
+		)
+	} else if args.is_present("next") {
 		AddressArgs::Next
 	} else if args.is_present("prev") {
 		AddressArgs::Prev
@@ -13,16 +13,20 @@
 // limitations under the License.
 
 use super::args::{
-	self, AccountArgs, AddressArgs, ContactArgs, ProofArgs, SeedArgs, SendCommandType,
+	self, AccountArgs, AddressArgs, ContactArgs, IncomingArgs, OutboxArgs, ProofArgs, RequestArgs,
+	SeedArgs, SendCommandType, SnapshotArgs, WebhooksArgs,
 };
 use super::display::{self, InitialPromptOption};
 use crate::api::listener::ListenerInterface;
 use crate::common::motd::get_motd;
 use crate::common::{Arc, ErrorKind, Keychain, Mutex};
-use crate::contacts::Address;
+use crate::contacts::{Address, ContactsFormat, GrinboxAddress};
 use crate::wallet::api::{Foreign, Owner};
-use crate::wallet::types::{NodeClient, TxProof, VersionedSlate, WalletBackend};
-use crate::wallet::Container;
+use crate::wallet::types::{
+	armor, NodeClient, OutputSnapshot, ScanStart, Slate, SlateVersion, TxProof, VersionedSlate,
+	WalletBackend, WalletEventType,
+};
+use crate::wallet::{Container, ErrorKind as WalletErrorKind};
 use clap::{crate_version, load_yaml, App, ArgMatches};
 use colored::Colorize;
 use failure::Error;
@@ -35,7 +39,9 @@ use rustyline::{CompletionType, Config, Context, EditMode, Editor, Helper, Outpu
 use semver::Version;
 use std::borrow::Cow::{self, Borrowed, Owned};
 use std::fs::File;
-use std::io::{Read, Write};
+use std::io::{BufRead, Read, Write};
+use std::path::Path;
+use uuid::Uuid;
 
 const COLORED_PROMPT: &'static str = "\x1b[36mwallet713>\x1b[0m ";
 const PROMPT: &'static str = "wallet713> ";
@@ -87,7 +93,8 @@ where
 			return Ok(());
 		}
 
-		if self.api.config().check_updates() {
+		let offline = self.api.config().offline();
+		if !offline && self.api.config().check_updates() {
 			let _ = get_motd();
 		}
 
@@ -98,7 +105,12 @@ where
 		println!("Use `help` to see available commands");
 		println!();
 
-		self.start_listeners()?;
+		if offline {
+			println!("Running offline, use `snapshot` to update the outputs");
+		} else {
+			self.start_listeners()?;
+			self.api.start_tx_monitor();
+		}
 		self.command_loop();
 		Ok(())
 	}
@@ -143,7 +155,7 @@ where
 		self.api.connect()?;
 		self.api.clear()?;
 		println!("Recovering wallet..");
-		self.api.restore()?;
+		self.api.restore(ScanStart::Resume)?;
 		println!("Wallet recovered successfully");
 		Ok(())
 	}
@@ -285,11 +297,41 @@ where
 			("address", Some(m)) => {
 				let mut idx = self.api.config().grinbox_address_index();
 				match args::address_command(m)? {
+					AddressArgs::Sign(message, index) => {
+						let signed = self.api.sign_message(message, index)?;
+						println!("Address:   {}", signed.address.bright_green());
+						println!("Message:   {}", signed.message);
+						println!("Signature: {}", signed.signature.bright_green());
+						return Ok(false);
+					}
+					AddressArgs::Verify(address, message, signature) => {
+						if self.api.verify_message(address, message, signature)? {
+							println!("Message was signed by {}", address.bright_green());
+						} else {
+							println!(
+								"{} Message was not signed by {}",
+								"ERROR:".bright_red(),
+								address.bright_red()
+							);
+						}
+						return Ok(false);
+					}
 					AddressArgs::Display => {
 						println!(
 							"Your grinbox address is {}",
 							self.api.grinbox_address()?.stripped().bright_green()
 						);
+						let addresses = self.api.grinbox_addresses()?;
+						if addresses.len() > 1 {
+							println!("Listening on:");
+							for (index, address) in addresses {
+								println!(
+									"  {:>4}  {}",
+									index.to_string().bright_green(),
+									address.stripped().bright_green()
+								);
+							}
+						}
 					}
 					AddressArgs::Next => {
 						idx = idx.saturating_add(1);
@@ -315,20 +357,64 @@ where
 				println!("Transaction cancelled successfully");
 			}
 			("check", Some(m)) => {
-				let delete_unconfirmed = args::repair_command(m)?;
+				let (delete_unconfirmed, start) = args::repair_command(m)?;
 				println!("Checking and repairing wallet..");
-				self.api.check_repair(delete_unconfirmed)?;
+				self.api.check_repair(delete_unconfirmed, start)?;
 				println!("Wallet repaired successfully");
 			}
 			("contact", Some(m)) => match args::contact_command(m)? {
-				ContactArgs::Add(name, address) => {
-					self.api.add_contact(name, address)?;
+				ContactArgs::Add(name, addresses, notes) => {
+					self.api.add_contact(name, addresses, notes)?;
 					println!("Contact {} added", name.bright_green());
 				}
+				ContactArgs::Export(file_name) => {
+					let format = ContactsFormat::from_path(file_name);
+					let data = self.api.export_contacts(format)?;
+					let mut file = File::create(file_name.replace("~", &home_dir))?;
+					file.write_all(data.as_bytes())?;
+					println!("Contacts exported to {}", file_name.bright_green());
+				}
+				ContactArgs::Import(file_name, overwrite) => {
+					let mut file = File::open(file_name.replace("~", &home_dir))?;
+					let mut data = String::new();
+					file.read_to_string(&mut data)?;
+					let format = ContactsFormat::from_path(file_name);
+					let (imported, skipped) = self.api.import_contacts(&data, format, overwrite)?;
+					println!(
+						"Imported {} contacts from {}",
+						imported.to_string().bright_green(),
+						file_name.bright_green()
+					);
+					if skipped > 0 {
+						println!(
+							"Skipped {} contacts whose name is already taken, use --overwrite to replace them",
+							skipped.to_string().bright_yellow()
+						);
+					}
+				}
+				ContactArgs::Notes(name, notes) => {
+					self.api.set_contact_notes(name, notes)?;
+					match notes {
+						Some(_) => println!("Notes of contact {} updated", name.bright_green()),
+						None => println!("Notes of contact {} cleared", name.bright_green()),
+					}
+				}
 				ContactArgs::Remove(name) => {
 					self.api.remove_contact(name)?;
 					println!("Contact {} removed", name.bright_green());
 				}
+				ContactArgs::Rename(name, new_name) => {
+					self.api.rename_contact(name, new_name)?;
+					println!(
+						"Contact {} renamed to {}",
+						name.bright_green(),
+						new_name.bright_green()
+					);
+				}
+				ContactArgs::SetAddress(name, addresses) => {
+					self.api.set_contact_addresses(name, addresses)?;
+					println!("Addresses of contact {} updated", name.bright_green());
+				}
 			},
 			("contacts", _) => {
 				display::contacts(self.api.contacts()?);
@@ -339,15 +425,67 @@ where
 			}
 			("finalize", Some(m)) => {
 				let (file_name, fluff) = args::finalize_command(m)?;
-				let mut file = File::open(file_name.replace("~", &home_dir))?;
-				let mut slate = String::new();
-				file.read_to_string(&mut slate)?;
-				let slate: VersionedSlate =
-					serde_json::from_str(&slate).map_err(|_| ErrorKind::ParseSlate)?;
-				let slate = self.api.finalize_tx(&slate.into(), None)?;
-				self.api.post_tx(&slate.tx, fluff)?;
-				println!("Transaction finalized and posted successfully");
+				let (slate, format) = self.read_slate(file_name, &home_dir)?;
+				let slate = self.api.finalize_tx(&slate, None)?;
+				if self.api.config().offline() {
+					// The online wallet is ours, no need to encrypt
+					let format = match format {
+						SlateFormat::Armored(_) => SlateFormat::Armored(None),
+						f => f,
+					};
+					let file_name = output_file_name(file_name, "finalized");
+					self.write_slate(slate, &format, &file_name, &home_dir)?;
+					println!(
+						"Transaction finalized, post {} from an online wallet",
+						file_name.bright_green()
+					);
+				} else {
+					self.api.post_tx(&slate.tx, fluff)?;
+					println!("Transaction finalized and posted successfully");
+				}
 			}
+			("incoming", Some(m)) => match args::incoming_command(m)? {
+				IncomingArgs::List => {
+					display::incoming(self.api.incoming()?);
+				}
+				IncomingArgs::Accept(slate_id) => {
+					let entry = self.api.incoming_slate(&slate_id)?;
+					let slate: Slate = entry.slate.clone().into();
+					self.api.check_ttl(&slate, slate.ttl_cutoff_height)?;
+					let slate = self.foreign.receive_tx(
+						&slate,
+						None,
+						Some(entry.from.clone()),
+						None,
+						entry.address_index,
+					)?;
+					if let Some(index) = entry.address_index {
+						self.api.match_payment_request(index, &slate)?;
+					}
+					if self.api.answer_incoming(&entry, slate)? {
+						println!(
+							"Slate {} sent back to {} successfully",
+							slate_id.to_string().bright_green(),
+							entry.from.bright_green()
+						);
+					} else {
+						println!(
+							"Slate {} signed, it will be sent back to {} from the outbox",
+							slate_id.to_string().bright_green(),
+							entry.from.bright_green()
+						);
+					}
+				}
+				IncomingArgs::Reject(slate_id, reason) => {
+					let entry = self.api.incoming_slate(&slate_id)?;
+					self.api.reject_incoming(&entry, reason)?;
+					println!(
+						"Slate {} rejected, {} was notified",
+						slate_id.to_string().bright_green(),
+						entry.from.bright_green()
+					);
+				}
+			},
 			("info", _) => {
 				let account = self.api.active_account()?;
 				let (validated, wallet_info) = self.api.retrieve_summary_info(true, 10)?;
@@ -365,6 +503,32 @@ where
 				};
 				self.api.start_listener(interface)?;
 			}
+			("outbox", Some(m)) => match args::outbox_command(m)? {
+				OutboxArgs::List => {
+					display::outbox(self.api.outbox()?);
+				}
+				OutboxArgs::Retry(index) => {
+					let slate_id = match index {
+						Some(index) => Some(self.outbox_slate_id(index)?),
+						None => None,
+					};
+					let (delivered, failed) = self.api.retry_outbox(slate_id, true)?;
+					println!(
+						"{} slates delivered, {} still queued",
+						delivered.to_string().bright_green(),
+						failed.to_string().bright_yellow()
+					);
+				}
+				OutboxArgs::Drop(index) => {
+					let slate_id = self.outbox_slate_id(index)?;
+					self.api.drop_outbox_entry(&slate_id)?;
+					println!(
+						"Slate {} removed from the outbox, cancel transaction {} to unlock its inputs",
+						slate_id.to_string().bright_green(),
+						index
+					);
+				}
+			},
 			("outputs", Some(m)) => {
 				let account = self.api.active_account()?;
 				let (validated, height, outputs) =
@@ -376,6 +540,15 @@ where
 				};
 				display::outputs(&account, height, validated, outputs, true);
 			}
+			("post", Some(m)) => {
+				let (file_name, fluff) = args::post_command(m)?;
+				let (slate, _) = self.read_slate(file_name, &home_dir)?;
+				self.api.post_tx(&slate.tx, fluff)?;
+				println!(
+					"Transaction {} posted successfully",
+					slate.id.to_string().bright_green()
+				);
+			}
 			("proof", Some(m)) => {
 				let (sender, receiver, amount, outputs, excess) = match args::proof_command(m)? {
 					ProofArgs::Export(index, file_name) => {
@@ -400,31 +573,35 @@ where
 						self.api.verify_tx_proof(&tx_proof)?
 					}
 				};
-				display::proof(sender, receiver, amount, outputs, excess);
+				let kernel = self.api.kernel_confirmations(&excess);
+				display::proof(sender, receiver, amount, outputs, excess, kernel);
 			}
 			("receive", Some(m)) => {
 				let (file_name, message) = args::receive_command(m)?;
-				let mut file = File::open(file_name.replace("~", &home_dir))?;
-				let mut slate = String::new();
-				file.read_to_string(&mut slate)?;
-				let slate: VersionedSlate =
-					serde_json::from_str(&slate).map_err(|_| ErrorKind::ParseSlate)?;
-				let version = slate.version().clone();
-				let slate = slate.into();
+				let (slate, format) = self.read_slate(file_name, &home_dir)?;
+				if let SlateFormat::Armored(Some(sender)) = &format {
+					cli_message!("Slate encrypted by {}", sender.stripped().bright_green());
+				}
 				let slate = self.foreign.receive_tx(
 					&slate,
 					None,
 					Some("file".to_owned()),
 					message.map(|m| m.to_owned()),
+					None,
 				)?;
-				let mut file_out =
-					File::create(&format!("{}.response", file_name.replace("~", &home_dir)))?;
-				let slate = VersionedSlate::into_version(slate, version);
-				file_out.write_all(serde_json::to_string(&slate)?.as_bytes())?;
-				cli_message!(
-					"Response slate file {} created successfully",
-					format!("{}.response", file_name.bright_green())
+				self.foreign.notify(
+					WalletEventType::SlateReceived,
+					&slate,
+					Some("file".to_owned()),
 				);
+				let file_name = output_file_name(file_name, "response");
+				self.write_slate(slate, &format, &file_name, &home_dir)?;
+				if file_name != STDIO {
+					cli_message!(
+						"Response slate file {} created successfully",
+						file_name.bright_green()
+					);
+				}
 			}
 			("repost", Some(m)) => {
 				let (index, fluff) = args::repost_command(m)?;
@@ -434,9 +611,36 @@ where
 					slate_id.to_string().bright_green()
 				);
 			}
-			("restore", _) => {
+			("request", Some(m)) => match args::request_command(m)? {
+				RequestArgs::List => {
+					self.api.update_payment_requests()?;
+					display::payment_requests(self.api.payment_requests()?);
+				}
+				RequestArgs::Create(amount, memo, expiry) => {
+					let request = self.api.create_payment_request(
+						amount,
+						memo.map(|m| m.to_owned()),
+						expiry,
+					)?;
+					println!(
+						"Payment request {} created, pay {} grin to {}",
+						request.address_index.to_string().bright_green(),
+						amount_to_hr_string(request.amount, false).bright_green(),
+						request.address.bright_green()
+					);
+				}
+				RequestArgs::Cancel(index) => {
+					self.api.cancel_payment_request(index)?;
+					println!(
+						"Payment request {} cancelled",
+						index.to_string().bright_green()
+					);
+				}
+			},
+			("restore", Some(m)) => {
+				let start = args::restore_command(m)?;
 				println!("Restoring wallet..");
-				self.api.restore()?;
+				self.api.restore(start)?;
 				println!("Wallet restored successfully");
 			}
 			("seed", Some(m)) => {
@@ -458,19 +662,33 @@ where
 					SendCommandType::Address => {
 						self.api.init_send_tx(args)?;
 					}
-					SendCommandType::File(file_name) => {
+					SendCommandType::File(file_name, armored, encrypt_to) => {
 						let slate = self.api.init_send_tx(args)?;
-						let mut file = File::create(file_name.replace("~", &home_dir))?;
-						file.write_all(serde_json::to_string_pretty(&slate)?.as_bytes())?;
+						if armored {
+							let encrypt_to = match encrypt_to {
+								Some(address) => Some(GrinboxAddress::from_str(address)?),
+								None => None,
+							};
+							let armored = self.api.armor_slate(&slate, encrypt_to.as_ref())?;
+							write_output(file_name, &home_dir, &armored)?;
+						} else {
+							write_output(
+								file_name,
+								&home_dir,
+								&serde_json::to_string_pretty(&slate)?,
+							)?;
+						}
 						self.api
 							.tx_lock_outputs(&slate, 0, Some("file".to_owned()))?;
 
-						println!(
-							"Slate {} for {} grin saved to {}",
-							slate.id.to_string().bright_green(),
-							amount_to_hr_string(slate.amount, false).bright_green(),
-							file_name.bright_green()
-						);
+						if file_name != STDIO {
+							println!(
+								"Slate {} for {} grin saved to {}",
+								slate.id.to_string().bright_green(),
+								amount_to_hr_string(slate.amount, false).bright_green(),
+								file_name.bright_green()
+							);
+						}
 					}
 					SendCommandType::Estimate => {
 						let strategies = vec!["smallest", "all"]
@@ -486,6 +704,40 @@ where
 					}
 				}
 			}
+			("snapshot", Some(m)) => match args::snapshot_command(m)? {
+				SnapshotArgs::Request(file_name) => {
+					let commits = self.api.snapshot_request()?;
+					let mut file = File::create(file_name.replace("~", &home_dir))?;
+					file.write_all(serde_json::to_string_pretty(&commits)?.as_bytes())?;
+					println!(
+						"Request for {} outputs saved to {}",
+						commits.len(),
+						file_name.bright_green()
+					);
+				}
+				SnapshotArgs::Export(request, file_name) => {
+					let mut file = File::open(request.replace("~", &home_dir))?;
+					let mut commits = String::new();
+					file.read_to_string(&mut commits)?;
+					let commits: Vec<String> = serde_json::from_str(&commits)?;
+					let snapshot = self.api.export_snapshot(commits)?;
+					snapshot.to_file(Path::new(&file_name.replace("~", &home_dir)))?;
+					println!(
+						"Snapshot at height {} saved to {}",
+						snapshot.height,
+						file_name.bright_green()
+					);
+				}
+				SnapshotArgs::Import(file_name) => {
+					let snapshot =
+						OutputSnapshot::from_file(Path::new(&file_name.replace("~", &home_dir)))?;
+					self.api.import_snapshot(&snapshot)?;
+					println!(
+						"Snapshot at height {} imported successfully",
+						snapshot.height
+					);
+				}
+			},
 			("stop", Some(m)) => {
 				let interface = match args::listen_command(m)? {
 					("grinbox", _) | ("", _) => ListenerInterface::Grinbox,
@@ -510,6 +762,27 @@ where
 					&account, height, validated, &txs, proofs, contacts, true, true,
 				);
 			}
+			("webhooks", Some(m)) => match args::webhooks_command(m)? {
+				WebhooksArgs::List => {
+					display::webhook_queue(self.api.webhook_queue()?);
+				}
+				WebhooksArgs::Retry => {
+					let (delivered, failed) = self.api.deliver_webhooks(true)?;
+					println!(
+						"{} events posted, {} still queued",
+						delivered.to_string().bright_green(),
+						failed.to_string().bright_yellow()
+					);
+				}
+				WebhooksArgs::Test => {
+					for (url, error) in self.api.test_webhooks()? {
+						match error {
+							None => println!("{} accepted the test event", url.bright_green()),
+							Some(e) => println!("{} failed: {}", url.bright_red(), e),
+						}
+					}
+				}
+			},
 			_ => {
 				cli_message!("Unknown command");
 			}
@@ -517,6 +790,93 @@ where
 
 		Ok(false)
 	}
+
+	/// Read a JSON or armored slate from a file or stdin
+	fn read_slate(&self, file_name: &str, home_dir: &str) -> Result<(Slate, SlateFormat), Error> {
+		let content = read_input(file_name, home_dir)?;
+		if armor::is_armored(&content) {
+			let (slate, sender) = self.api.dearmor_slate(&content)?;
+			Ok((slate, SlateFormat::Armored(sender)))
+		} else {
+			let slate: VersionedSlate =
+				serde_json::from_str(&content).map_err(|_| ErrorKind::ParseSlate)?;
+			let version = slate.version();
+			Ok((slate.into(), SlateFormat::Json(version)))
+		}
+	}
+
+	/// Write a slate to a file or stdout, in the format it was received in
+	fn write_slate(
+		&self,
+		slate: Slate,
+		format: &SlateFormat,
+		file_name: &str,
+		home_dir: &str,
+	) -> Result<(), Error> {
+		let content = match format {
+			SlateFormat::Json(version) => {
+				serde_json::to_string(&VersionedSlate::into_version(slate, version.clone()))?
+			}
+			SlateFormat::Armored(sender) => self.api.armor_slate(&slate, sender.as_ref())?,
+		};
+		write_output(file_name, home_dir, &content)
+	}
+
+	/// Slate id of the transaction with this index
+	fn outbox_slate_id(&self, index: u32) -> Result<Uuid, Error> {
+		let tx = self.api.retrieve_tx(Some(index), None)?;
+		let slate_id = tx
+			.tx_slate_id
+			.ok_or(WalletErrorKind::TransactionDoesntExist(index.to_string()))?;
+		Ok(slate_id)
+	}
+}
+
+/// File name that reads from stdin or writes to stdout
+const STDIO: &'static str = "-";
+
+/// Format of a slate file. Armored slates that were encrypted by the sender
+/// are answered encrypted
+enum SlateFormat {
+	Json(SlateVersion),
+	Armored(Option<GrinboxAddress>),
+}
+
+fn output_file_name(file_name: &str, extension: &str) -> String {
+	match file_name {
+		STDIO => STDIO.to_owned(),
+		f => format!("{}.{}", f, extension),
+	}
+}
+
+fn read_input(file_name: &str, home_dir: &str) -> Result<String, Error> {
+	let mut content = String::new();
+	if file_name == STDIO {
+		println!("Paste the slate, finish with Ctrl-D for a JSON slate");
+		let stdin = std::io::stdin();
+		for line in stdin.lock().lines() {
+			let line = line?;
+			content.push_str(&line);
+			content.push('\n');
+			if armor::is_armor_end(&line) {
+				break;
+			}
+		}
+	} else {
+		let mut file = File::open(file_name.replace("~", home_dir))?;
+		file.read_to_string(&mut content)?;
+	}
+	Ok(content)
+}
+
+fn write_output(file_name: &str, home_dir: &str, content: &str) -> Result<(), Error> {
+	if file_name == STDIO {
+		println!("{}", content);
+	} else {
+		let mut file = File::create(file_name.replace("~", home_dir))?;
+		file.write_all(content.as_bytes())?;
+	}
+	Ok(())
 }
 
 struct EditorHelper(FilenameCompleter, MatchingBracketHighlighter);
@@ -15,7 +15,8 @@
 use crate::common::ErrorKind;
 use crate::contacts::{Contact, GrinboxAddress};
 use crate::wallet::types::{
-	AcctPathMapping, OutputCommitMapping, OutputStatus, TxLogEntry, WalletInfo,
+	AcctPathMapping, IncomingSlate, OutboxEntry, OutputCommitMapping, OutputStatus, PaymentRequest,
+	Slate, TxLogEntry, WalletInfo, WebhookEntry,
 };
 use clap::crate_version;
 use colored::Colorize;
@@ -317,6 +318,10 @@ pub fn txs(
 			},
 			None => "".to_owned(),
 		};
+		let address = match &t.transport {
+			Some(transport) => format!("{} ({})", address, transport),
+			None => address,
+		};
 		let creation_ts = format!("{}", t.creation_ts.format("%Y-%m-%d %H:%M:%S"));
 		let confirmed = if t.confirmed { "yes" } else { "" }.to_owned();
 		let confirmation_ts = match t.confirmation_ts {
@@ -477,6 +482,7 @@ pub fn proof(
 	amount: u64,
 	outputs: Vec<Commitment>,
 	excess: Commitment,
+	kernel: Result<Option<(u64, u64)>, Error>,
 ) {
 	let outputs = outputs
 		.iter()
@@ -491,14 +497,44 @@ pub fn proof(
 		format!("{}", sender).bright_green()
 	);
 
-	println!("\nOutputs:");
-	for output in outputs {
-		println!("   {}", output.bright_magenta());
+	// Payment proofs are signed by the receiver and don't list its outputs
+	if !outputs.is_empty() {
+		println!("\nOutputs:");
+		for output in outputs {
+			println!("   {}", output.bright_magenta());
+		}
+	} else {
+		println!();
 	}
 	println!("Kernel excess:");
 	println!("   {}", excess.bright_magenta());
-	println!("\n{}: this proof should only be considered valid if the kernel is actually on-chain with sufficient confirmations", "WARNING".bright_yellow());
-	println!("Please use a grin block explorer to verify this is the case. for example:");
+
+	match kernel {
+		Ok(Some((height, confirmations))) => {
+			println!(
+				"\nKernel found on chain at height {} with {} confirmations",
+				height.to_string().bright_green(),
+				confirmations.to_string().bright_green()
+			);
+			return;
+		}
+		Ok(None) => {
+			println!(
+				"\n{}: the kernel was {} on chain. The transaction may never have been posted, do not consider it paid!",
+				"WARNING".bright_red().bold(),
+				"NOT FOUND".bright_red().bold()
+			);
+		}
+		Err(e) => {
+			println!(
+				"\n{}: unable to look up the kernel on chain: {}",
+				"WARNING".bright_yellow(),
+				e
+			);
+			println!("This proof should only be considered valid if the kernel is actually on-chain with sufficient confirmations");
+		}
+	}
+	println!("You can check the kernel with a grin block explorer, for example:");
 	let prefix = if is_floonet() { "floonet." } else { "" };
 	cli_message!("   https://{}grinscan.net/kernel/{}", prefix, excess);
 }
@@ -511,11 +547,161 @@ pub fn contacts(contacts: Vec<Contact>) {
 	table.set_titles(row![
 		mMG->"Name",
 		bMG->"Address",
+		bMG->"Notes",
 	]);
 	for c in contacts {
 		table.add_row(row![
 			bFC->c.name,
-			bGC->c.address,
+			bGC->c.addresses().join("\n"),
+			c.notes.unwrap_or_default(),
+		]);
+	}
+	table.set_format(*FORMAT_NO_BORDER_LINE_SEPARATOR);
+	table.printstd();
+	println!();
+}
+
+pub fn outbox(entries: Vec<OutboxEntry>) {
+	println!("\n____ Outbox ____\n",);
+	if entries.is_empty() {
+		println!("No slates waiting to be delivered\n");
+		return;
+	}
+	let mut table = table!();
+
+	table.set_titles(row![
+		bMG->"Slate Id",
+		bMG->"Method",
+		bMG->"Recipient",
+		bMG->"Queued",
+		bMG->"Attempts",
+		bMG->"Next Attempt",
+		bMG->"Last Error",
+	]);
+	for e in entries {
+		let creation_ts = format!("{}", e.creation_ts.format("%Y-%m-%d %H:%M:%S"));
+		let next_attempt_ts = format!("{}", e.next_attempt_ts.format("%Y-%m-%d %H:%M:%S"));
+		table.add_row(row![
+			bFC->e.slate_id,
+			bFC->e.method,
+			bGC->e.dest,
+			bFB->creation_ts,
+			bFC->e.attempts,
+			bFB->next_attempt_ts,
+			bFR->e.last_error.unwrap_or_default(),
+		]);
+	}
+	table.set_format(*FORMAT_NO_BORDER_LINE_SEPARATOR);
+	table.printstd();
+	println!();
+}
+
+pub fn payment_requests(requests: Vec<PaymentRequest>) {
+	println!("\n____ Payment requests ____\n",);
+	if requests.is_empty() {
+		println!("No payment requests\n");
+		return;
+	}
+	let mut table = table!();
+
+	table.set_titles(row![
+		bMG->"Index",
+		bMG->"Address",
+		bMG->"Amount",
+		bMG->"Memo",
+		bMG->"Created",
+		bMG->"Expires",
+		bMG->"Status",
+		bMG->"Slate Id",
+	]);
+	for r in requests {
+		let creation_ts = format!("{}", r.creation_ts.format("%Y-%m-%d %H:%M:%S"));
+		let expiry_ts = r
+			.expiry_ts
+			.map(|t| format!("{}", t.format("%Y-%m-%d %H:%M:%S")))
+			.unwrap_or_default();
+		let slate_id = r.slate_id.map(|id| id.to_string()).unwrap_or_default();
+		table.add_row(row![
+			bFC->r.address_index,
+			bGC->r.address,
+			bFG->amount_to_hr_string(r.amount, true),
+			bFC->r.memo.unwrap_or_default(),
+			bFB->creation_ts,
+			bFB->expiry_ts,
+			bFR->r.status,
+			bFC->slate_id,
+		]);
+	}
+	table.set_format(*FORMAT_NO_BORDER_LINE_SEPARATOR);
+	table.printstd();
+	println!();
+}
+
+pub fn webhook_queue(entries: Vec<WebhookEntry>) {
+	println!("\n____ Webhook queue ____\n",);
+	if entries.is_empty() {
+		println!("No events waiting to be posted\n");
+		return;
+	}
+	let mut table = table!();
+
+	table.set_titles(row![
+		bMG->"Event Id",
+		bMG->"Event",
+		bMG->"Created",
+		bMG->"Attempts",
+		bMG->"Next Attempt",
+		bMG->"Last Error",
+	]);
+	for e in entries {
+		let creation_ts = format!("{}", e.event.creation_ts.format("%Y-%m-%d %H:%M:%S"));
+		let next_attempt_ts = format!("{}", e.next_attempt_ts.format("%Y-%m-%d %H:%M:%S"));
+		table.add_row(row![
+			bFC->e.event.id,
+			bFC->e.event.event,
+			bFB->creation_ts,
+			bFC->e.attempts,
+			bFB->next_attempt_ts,
+			bFR->e.last_error.unwrap_or_default(),
+		]);
+	}
+	table.set_format(*FORMAT_NO_BORDER_LINE_SEPARATOR);
+	table.printstd();
+	println!();
+}
+
+pub fn incoming(entries: Vec<(IncomingSlate, Option<String>)>) {
+	println!("\n____ Waiting for approval ____\n",);
+	if entries.is_empty() {
+		println!("No slates waiting for approval\n");
+		return;
+	}
+	let mut table = table!();
+
+	table.set_titles(row![
+		bMG->"Slate Id",
+		bMG->"Sender",
+		bMG->"Contact",
+		bMG->"Amount",
+		bMG->"Message",
+		bMG->"Received",
+	]);
+	for (e, contact) in entries {
+		let slate = Slate::from(&e.slate);
+		let message = slate
+			.participant_data
+			.iter()
+			.filter_map(|p| p.message.clone())
+			.next()
+			.unwrap_or_default();
+		let received_ts = format!("{}", e.received_ts.format("%Y-%m-%d %H:%M:%S"));
+		table.add_row(row![
+			bFC->e.slate_id,
+			bGC->e.from,
+			bFC->contact.unwrap_or_default(),
+			bFG->amount_to_hr_string(slate.amount, true),
+			bFC->message,
+			bFB->received_ts,
 		]);
 	}
 	table.set_format(*FORMAT_NO_BORDER_LINE_SEPARATOR);
@@ -16,8 +16,8 @@
 use super::{keys, updater};
 use crate::common::ErrorKind;
 use crate::wallet::types::{
-	NodeClient, OutputCommitMapping, OutputData, OutputStatus, TxLogEntry, TxLogEntryType,
-	WalletBackend,
+	AcctPathMapping, NodeClient, OutputCommitMapping, OutputData, OutputStatus, ScanMonitor,
+	ScanStart, TxLogEntry, TxLogEntryType, WalletBackend,
 };
 use failure::Error;
 use grin_core::consensus::{valid_header_version, WEEK_HEIGHT};
@@ -30,6 +30,9 @@ use log::*;
 use std::collections::HashMap;
 use std::time::Instant;
 
+const RESTORE_SCAN: &'static str = "restore";
+const CHECK_SCAN: &'static str = "check";
+
 /// Utility struct for return values from below
 #[derive(Clone)]
 struct OutputResult {
@@ -141,34 +144,124 @@ where
 	Ok(wallet_outputs)
 }
 
-fn collect_chain_outputs<T, C, K>(wallet: &mut T) -> Result<Vec<OutputResult>, Error>
+/// Find the PMMR index a scan should start at. An interrupted scan is continued,
+/// unless an explicit starting point is requested. A new check only looks at
+/// outputs after the last fully scanned index, a new restore starts from the
+/// beginning since the wallet may hold none of the outputs before it
+fn scan_start_index<T, C, K>(wallet: &mut T, scan: &str, start: &ScanStart) -> Result<u64, Error>
 where
 	T: WalletBackend<C, K>,
 	C: NodeClient,
 	K: Keychain,
+{
+	let index = match start {
+		ScanStart::Resume => match wallet.get_scan_checkpoint(scan)? {
+			Some(index) => {
+				warn!("Resuming {} from index {}", scan, index + 1);
+				index + 1
+			}
+			None if scan == RESTORE_SCAN => 1,
+			None => wallet.get_last_scanned_index()?.unwrap_or(0) + 1,
+		},
+		ScanStart::Height(height) => {
+			let (start_index, _) = wallet
+				.w2n_client()
+				.height_range_to_pmmr_indices(*height, None)?;
+			start_index
+		}
+		ScanStart::Index(index) => *index,
+	};
+	Ok(index.max(1))
+}
+
+/// Scan the output PMMR in batches, handing the outputs belonging to this wallet
+/// to `process`. The last processed index is stored after every batch, so an
+/// interrupted or cancelled scan resumes where it left off. A scan that continues
+/// from the last fully scanned position moves that position forward as it goes
+fn scan_chain_outputs<T, C, K, F>(
+	wallet: &mut T,
+	scan: &str,
+	start: &ScanStart,
+	monitor: &ScanMonitor,
+	mut process: F,
+) -> Result<(), Error>
+where
+	T: WalletBackend<C, K>,
+	C: NodeClient,
+	K: Keychain,
+	F: FnMut(&mut T, Vec<OutputResult>) -> Result<(), Error>,
 {
 	let batch_size = 1000;
-	let mut start_index = 1;
-	let mut result_vec: Vec<OutputResult> = vec![];
-	loop {
-		let (highest_index, last_retrieved_index, outputs) = wallet
-			.w2n_client()
-			.get_outputs_by_pmmr_index(start_index, batch_size)?;
-		warn!(
-			"Checking {} outputs, up to index {}. (Highest index: {})",
-			outputs.len(),
-			highest_index,
-			last_retrieved_index,
-		);
+	let mut start_index = scan_start_index(wallet, scan, start)?;
+	let mut last_scanned = wallet.get_last_scanned_index()?.unwrap_or(0);
+	let contiguous = start_index <= last_scanned + 1;
+	warn!("Starting {} at index {}", scan, start_index);
+
+	monitor.start(start_index);
+	let result = (|| -> Result<(), Error> {
+		let mut last_percentage = None;
+		loop {
+			if monitor.is_cancelled() {
+				warn!("Cancelled {} at index {}", scan, start_index);
+				return Err(ErrorKind::ScanCancelled.into());
+			}
 
-		result_vec.append(&mut identify_utxo_outputs(wallet, outputs.clone())?);
+			let (highest_index, last_retrieved_index, outputs) = wallet
+				.w2n_client()
+				.get_outputs_by_pmmr_index(start_index, batch_size)?;
+			warn!(
+				"Checking {} outputs, up to index {}. (Highest index: {})",
+				outputs.len(),
+				last_retrieved_index,
+				highest_index,
+			);
+
+			// Nothing left to retrieve, we reached the end of the PMMR
+			let last_index = if last_retrieved_index < start_index {
+				highest_index
+			} else {
+				last_retrieved_index
+			};
+
+			let found = identify_utxo_outputs(wallet, outputs)?;
+			process(wallet, found)?;
+
+			let mut batch = wallet.batch()?;
+			batch.save_scan_checkpoint(scan, last_index)?;
+			// Never move the last scanned index backwards
+			if contiguous && last_index > last_scanned {
+				batch.save_last_scanned_index(last_index)?;
+				last_scanned = last_index;
+			}
+			batch.commit()?;
 
-		if highest_index == last_retrieved_index {
-			break;
+			let progress = monitor.update(last_index, highest_index);
+			if last_percentage != Some(progress.percentage) {
+				last_percentage = Some(progress.percentage);
+				match progress.eta {
+					Some(eta) => cli_message!(
+						"Scanning: {}% (about {}m{}s left)",
+						progress.percentage,
+						eta / 60,
+						eta % 60
+					),
+					None => cli_message!("Scanning: {}%", progress.percentage),
+				}
+			}
+
+			if highest_index <= last_index {
+				break;
+			}
+			start_index = last_index + 1;
 		}
-		start_index = last_retrieved_index + 1;
-	}
-	Ok(result_vec)
+
+		let mut batch = wallet.batch()?;
+		batch.delete_scan_checkpoint(scan)?;
+		batch.commit()?;
+		Ok(())
+	})();
+	monitor.finish();
+	result
 }
 
 ///
@@ -189,7 +282,9 @@ where
 	let parent_key_id = output.key_id.parent_path();
 	if !found_parents.contains_key(&parent_key_id) {
 		found_parents.insert(parent_key_id.clone(), 0);
-		if let Some(ref mut s) = tx_stats {
+	}
+	if let Some(ref mut s) = tx_stats {
+		if !s.contains_key(&parent_key_id) {
 			s.insert(
 				parent_key_id.clone(),
 				RestoredTxStats {
@@ -296,87 +391,96 @@ where
 /// Check / repair wallet contents
 /// assume wallet contents have been freshly updated with contents
 /// of latest block
-pub fn check_repair<T, C, K>(wallet: &mut T, delete_unconfirmed: bool) -> Result<(), Error>
+pub fn check_repair<T, C, K>(
+	wallet: &mut T,
+	delete_unconfirmed: bool,
+	start: &ScanStart,
+	monitor: &ScanMonitor,
+) -> Result<(), Error>
 where
 	T: WalletBackend<C, K>,
 	C: NodeClient,
 	K: Keychain,
 {
-	// First, get a definitive list of outputs we own from the chain
 	warn!("Starting wallet check.");
-	let chain_outs = collect_chain_outputs(wallet)?;
-	warn!(
-		"Identified {} wallet_outputs as belonging to this wallet",
-		chain_outs.len(),
-	);
 
-	// Now, get all outputs owned by this wallet (regardless of account)
+	// Get all outputs owned by this wallet (regardless of account)
 	let wallet_outputs = updater::retrieve_outputs(wallet, true, None, None)?;
-
-	let mut missing_outs = vec![];
-	let mut accidental_spend_outs = vec![];
-	let mut locked_outs = vec![];
-
-	// check all definitive outputs exist in the wallet outputs
-	for deffo in chain_outs.into_iter() {
-		let matched_out = wallet_outputs.iter().find(|wo| wo.commit == deffo.commit);
-		match matched_out {
-			Some(s) => {
-				if s.output.status == OutputStatus::Spent {
-					accidental_spend_outs.push((s.output.clone(), deffo.clone()));
-				}
-				if s.output.status == OutputStatus::Locked {
-					locked_outs.push((s.output.clone(), deffo.clone()));
-				}
-			}
-			None => missing_outs.push(deffo),
-		}
-	}
-
-	// mark problem spent outputs as unspent (confirmed against a short-lived fork, for example)
-	for m in accidental_spend_outs.into_iter() {
-		let mut o = m.0;
-		warn!(
-			"Output for {} with ID {} ({:?}) marked as spent but exists in UTXO set. \
-			 Marking unspent and cancelling any associated transaction log entries.",
-			o.value, o.key_id, m.1.commit,
-		);
-		o.status = OutputStatus::Unspent;
-		// any transactions associated with this should be cancelled
-		cancel_tx_log_entry(wallet, &o)?;
-		let mut batch = wallet.batch()?;
-		batch.save_output(&o)?;
-		batch.commit()?;
-	}
-
 	let mut found_parents: HashMap<Identifier, u32> = HashMap::new();
 
-	// Restore missing outputs, adding transaction for it back to the log
-	for m in missing_outs.into_iter() {
+	// Get a definitive list of outputs we own from the chain, one batch at a time
+	scan_chain_outputs(wallet, CHECK_SCAN, start, monitor, |wallet, chain_outs| {
 		warn!(
-			"Confirmed output for {} with ID {} ({:?}) exists in UTXO set but not in wallet. \
-			 Restoring.",
-			m.value, m.key_id, m.commit,
+			"Identified {} wallet_outputs as belonging to this wallet",
+			chain_outs.len(),
 		);
-		restore_missing_output(wallet, m, &mut found_parents, &mut None)?;
-	}
 
-	if delete_unconfirmed {
-		// Unlock locked outputs
-		for m in locked_outs.into_iter() {
+		let mut missing_outs = vec![];
+		let mut accidental_spend_outs = vec![];
+		let mut locked_outs = vec![];
+
+		// check all definitive outputs exist in the wallet outputs
+		for deffo in chain_outs.into_iter() {
+			let matched_out = wallet_outputs.iter().find(|wo| wo.commit == deffo.commit);
+			match matched_out {
+				Some(s) => {
+					if s.output.status == OutputStatus::Spent {
+						accidental_spend_outs.push((s.output.clone(), deffo.clone()));
+					}
+					if s.output.status == OutputStatus::Locked {
+						locked_outs.push((s.output.clone(), deffo.clone()));
+					}
+				}
+				None => missing_outs.push(deffo),
+			}
+		}
+
+		// mark problem spent outputs as unspent (confirmed against a short-lived fork, for example)
+		for m in accidental_spend_outs.into_iter() {
 			let mut o = m.0;
 			warn!(
-				"Confirmed output for {} with ID {} ({:?}) exists in UTXO set and is locked. \
-				 Unlocking and cancelling associated transaction log entries.",
+				"Output for {} with ID {} ({:?}) marked as spent but exists in UTXO set. \
+				 Marking unspent and cancelling any associated transaction log entries.",
 				o.value, o.key_id, m.1.commit,
 			);
 			o.status = OutputStatus::Unspent;
+			// any transactions associated with this should be cancelled
 			cancel_tx_log_entry(wallet, &o)?;
 			let mut batch = wallet.batch()?;
 			batch.save_output(&o)?;
 			batch.commit()?;
 		}
 
+		// Restore missing outputs, adding transaction for it back to the log
+		for m in missing_outs.into_iter() {
+			warn!(
+				"Confirmed output for {} with ID {} ({:?}) exists in UTXO set but not in wallet. \
+				 Restoring.",
+				m.value, m.key_id, m.commit,
+			);
+			restore_missing_output(wallet, m, &mut found_parents, &mut None)?;
+		}
+
+		if delete_unconfirmed {
+			// Unlock locked outputs
+			for m in locked_outs.into_iter() {
+				let mut o = m.0;
+				warn!(
+					"Confirmed output for {} with ID {} ({:?}) exists in UTXO set and is locked. \
+					 Unlocking and cancelling associated transaction log entries.",
+					o.value, o.key_id, m.1.commit,
+				);
+				o.status = OutputStatus::Unspent;
+				cancel_tx_log_entry(wallet, &o)?;
+				let mut batch = wallet.batch()?;
+				batch.save_output(&o)?;
+				batch.commit()?;
+			}
+		}
+		Ok(())
+	})?;
+
+	if delete_unconfirmed {
 		let unconfirmed_outs: Vec<&OutputCommitMapping> = wallet_outputs
 			.iter()
 			.filter(|o| o.output.status == OutputStatus::Unconfirmed)
@@ -396,33 +500,29 @@ where
 		}
 	}
 
-	// restore labels, account paths and child derivation indices
-	let label_base = "account";
-	let mut acct_index = 1;
-	for (path, max_child_index) in found_parents.iter() {
-		// default path already exists
-		if *path != K::derive_key_id(2, 0, 0, 0, 0) {
-			let label = format!("{}_{}", label_base, acct_index);
-			keys::set_acct_path(wallet, &label, path)?;
-			acct_index += 1;
-		}
-		let mut batch = wallet.batch()?;
-		debug!("Next child for account {} is {}", path, max_child_index + 1);
-		batch.save_child_index(path, max_child_index + 1)?;
-		batch.commit()?;
-	}
+	restore_accounts(wallet, &found_parents)?;
 	Ok(())
 }
 
 /// Restore a wallet
-pub fn restore<T, C, K>(wallet: &mut T) -> Result<(), Error>
+pub fn restore<T, C, K>(
+	wallet: &mut T,
+	start: &ScanStart,
+	monitor: &ScanMonitor,
+) -> Result<(), Error>
 where
 	T: WalletBackend<C, K>,
 	C: NodeClient,
 	K: Keychain,
 {
-	// Don't proceed if wallet_data has anything in it
-	if wallet.outputs()?.next().is_some() {
+	let resuming = match start {
+		ScanStart::Resume => wallet.get_scan_checkpoint(RESTORE_SCAN)?.is_some(),
+		_ => false,
+	};
+
+	// Don't proceed if wallet_data has anything in it, unless we are
+	// continuing an earlier restore
+	if !resuming && wallet.outputs()?.next().is_some() {
 		error!("Not restoring. Please back up and remove existing db directory first.");
 		return Err(ErrorKind::WalletShouldBeEmpty.into());
 	}
@@ -430,38 +530,53 @@ where
 	let now = Instant::now();
 	warn!("Starting restore.");
 
-	let result_vec = collect_chain_outputs(wallet)?;
-
-	warn!(
-		"Identified {} wallet_outputs as belonging to this wallet",
-		result_vec.len(),
-	);
-
+	// Pick up the child indices of outputs restored before the interruption
 	let mut found_parents: HashMap<Identifier, u32> = HashMap::new();
-	let mut restore_stats = HashMap::new();
+	if resuming {
+		for o in wallet.outputs()? {
+			let max_child_index = found_parents.entry(o.root_key_id.clone()).or_insert(0);
+			if o.n_child > *max_child_index {
+				*max_child_index = o.n_child;
+			}
+		}
+	}
 
-	// Now save what we have
-	for output in result_vec {
-		restore_missing_output(
-			wallet,
-			output,
-			&mut found_parents,
-			&mut Some(&mut restore_stats),
-		)?;
+	// Keep adding to the tx log entries saved before the interruption
+	let mut restore_stats = HashMap::new();
+	if resuming {
+		for t in wallet.tx_logs()? {
+			if t.tx_type == TxLogEntryType::TxReceived {
+				restore_stats.insert(
+					t.parent_key_id.clone(),
+					RestoredTxStats {
+						log_id: t.id,
+						amount_credited: t.amount_credited,
+						num_outputs: t.num_outputs,
+					},
+				);
+			}
+		}
 	}
 
-	// restore labels, account paths and child derivation indices
-	let label_base = "account";
-	let mut acct_index = 1;
-	for (path, max_child_index) in found_parents.iter() {
-		// default path already exists
-		if *path != K::derive_key_id(2, 0, 0, 0, 0) {
-			let label = format!("{}_{}", label_base, acct_index);
-			keys::set_acct_path(wallet, &label, path)?;
-			acct_index += 1;
+	scan_chain_outputs(wallet, RESTORE_SCAN, start, monitor, |wallet, outputs| {
+		warn!(
+			"Identified {} wallet_outputs as belonging to this wallet",
+			outputs.len(),
+		);
+
+		// Now save what we have
+		for output in outputs {
+			restore_missing_output(
+				wallet,
+				output,
+				&mut found_parents,
+				&mut Some(&mut restore_stats),
+			)?;
 		}
-		// restore tx log entry for non-coinbase outputs
-		if let Some(s) = restore_stats.get(path) {
+
+		// restore tx log entries for non-coinbase outputs, so every
+		// saved output refers to an existing entry if we get interrupted
+		for (path, s) in restore_stats.iter() {
 			let mut batch = wallet.batch()?;
 			let mut t = TxLogEntry::new(path.clone(), TxLogEntryType::TxReceived, s.log_id);
 			t.confirmed = true;
@@ -471,11 +586,10 @@ where
 			batch.save_tx_log_entry(&t)?;
 			batch.commit()?;
 		}
-		let mut batch = wallet.batch()?;
-		batch.save_child_index(path, max_child_index + 1)?;
-		debug!("Next child for account {} is {}", path, max_child_index + 1);
-		batch.commit()?;
-	}
+		Ok(())
+	})?;
+
+	restore_accounts(wallet, &found_parents)?;
 
 	let mut sec = now.elapsed().as_secs();
 	let min = sec / 60;
@@ -484,3 +598,39 @@ where
 
 	Ok(())
 }
+
+/// Restore labels, account paths and child derivation indices
+fn restore_accounts<T, C, K>(
+	wallet: &mut T,
+	found_parents: &HashMap<Identifier, u32>,
+) -> Result<(), Error>
+where
+	T: WalletBackend<C, K>,
+	C: NodeClient,
+	K: Keychain,
+{
+	let mut accounts: Vec<AcctPathMapping> = wallet.accounts()?.collect();
+	let label_base = "account";
+	let mut acct_index = 1;
+	for (path, max_child_index) in found_parents.iter() {
+		// default path and previously restored paths already exist
+		if !accounts.iter().any(|a| a.path == *path) {
+			let mut label = format!("{}_{}", label_base, acct_index);
+			while accounts.iter().any(|a| a.label == label) {
+				acct_index += 1;
+				label = format!("{}_{}", label_base, acct_index);
+			}
+			keys::set_acct_path(wallet, &label, path)?;
+			accounts.push(AcctPathMapping {
+				label,
+				path: path.clone(),
+			});
+			acct_index += 1;
+		}
+		let mut batch = wallet.batch()?;
+		debug!("Next child for account {} is {}", path, max_child_index + 1);
+		batch.save_child_index(path, max_child_index + 1)?;
+		batch.commit()?;
+	}
+	Ok(())
+}
@@ -14,14 +14,15 @@
 
 use super::selection;
 use super::updater;
-use crate::contacts::GrinboxAddress;
+use crate::common::crypto::{sign_challenge, Hex};
+use crate::contacts::{Address, GrinboxAddress};
 use crate::wallet::types::{
-	Context, InitTxArgs, NodeClient, Slate, TxLogEntryType, TxProof, WalletBackend,
+	Context, InitTxArgs, NodeClient, ReceiverProof, Slate, TxLogEntryType, TxProof, WalletBackend,
 };
 use crate::wallet::ErrorKind;
 use failure::Error;
 use grin_keychain::{Identifier, Keychain};
-use grin_util::secp::key::PublicKey;
+use grin_util::secp::key::{PublicKey, SecretKey};
 use grin_util::secp::pedersen::Commitment;
 use grin_util::static_secp_instance;
 use std::collections::HashSet;
@@ -71,7 +72,11 @@ where
 		return Ok(slate);
 	}
 
-	let context = add_inputs_to_slate(
+	if let Some(ttl) = args.ttl_blocks.filter(|t| *t > 0) {
+		slate.ttl_cutoff_height = Some(slate.height + ttl);
+	}
+
+	let mut context = add_inputs_to_slate(
 		w,
 		&mut slate,
 		args.minimum_confirmations,
@@ -83,6 +88,7 @@ where
 		message,
 		true,
 	)?;
+	context.ttl_cutoff_height = slate.ttl_cutoff_height;
 
 	// Save the aggsig context in our DB for when we receive the transaction back
 	{
@@ -374,13 +380,16 @@ where
 			break;
 		}
 	}
-	let tx = match tx {
+	let mut tx = match tx {
 		Some(t) => t,
 		None => return Err(ErrorKind::TransactionDoesntExist(slate.id.to_string()))?,
 	};
+	let (_, height) = updater::node_height(wallet)?;
+	tx.finalized_height = Some(height);
 	{
 		let mut batch = wallet.batch()?;
 		let id = tx.tx_slate_id.unwrap().to_string();
+		batch.save_tx_log_entry(&tx)?;
 		batch.store_tx(&id, &slate.tx)?;
 		if let Some(proof) = tx_proof {
 			batch.store_tx_proof(&id, proof)?;
@@ -544,6 +553,90 @@ where
 	Ok(ret_slate)
 }
 
+/// Ask the receiver of a slate to sign a payment proof for our address.
+/// The receiver fills in its own address when it signs
+pub fn request_payment_proof<K>(
+	keychain: &K,
+	slate: &mut Slate,
+	address_key: &SecretKey,
+) -> Result<(), Error>
+where
+	K: Keychain,
+{
+	let public_key = PublicKey::from_secret_key(keychain.secp(), address_key)?;
+	let sender = GrinboxAddress::new(public_key, None, None);
+	slate.receiver_proof = Some(ReceiverProof {
+		sender_address: sender.public_key,
+		receiver_address: String::new(),
+		receiver_signature: None,
+	});
+	Ok(())
+}
+
+/// Sign the payment proof requested in the slate with the receiver address key.
+/// The kernel excess is known as soon as the receiver output has been added
+pub fn sign_payment_proof<K>(
+	keychain: &K,
+	slate: &mut Slate,
+	address_key: &SecretKey,
+) -> Result<(), Error>
+where
+	K: Keychain,
+{
+	let sender = match &slate.receiver_proof {
+		Some(p) => GrinboxAddress::from_str(&p.sender_address)?,
+		None => return Ok(()),
+	};
+
+	let excess = slate.sum_excess(keychain)?;
+	let message = TxProof::payment_message(slate.amount, &excess, &sender);
+	let signature = sign_challenge(&message, address_key)?;
+	let public_key = PublicKey::from_secret_key(keychain.secp(), address_key)?;
+	let receiver = GrinboxAddress::new(public_key, None, None);
+
+	if let Some(p) = slate.receiver_proof.as_mut() {
+		p.receiver_address = receiver.public_key;
+		p.receiver_signature = Some(signature.to_hex());
+	}
+	Ok(())
+}
+
+/// Build a payment proof from the receiver signature in the slate. Returns
+/// `None` if no proof was requested or the receiver didn't sign it
+pub fn payment_proof<K>(
+	keychain: &K,
+	slate: &Slate,
+	address_key: &SecretKey,
+) -> Result<Option<TxProof>, Error>
+where
+	K: Keychain,
+{
+	let proof = match &slate.receiver_proof {
+		Some(p) => p,
+		None => return Ok(None),
+	};
+	let signature = match &proof.receiver_signature {
+		Some(s) if !proof.receiver_address.is_empty() => s.clone(),
+		_ => return Ok(None),
+	};
+	let public_key = PublicKey::from_secret_key(keychain.secp(), address_key)?;
+	let sender = GrinboxAddress::new(public_key, None, None);
+	if proof.sender_address != sender.public_key {
+		return Err(ErrorKind::VerifyProof.into());
+	}
+
+	let excess = slate.sum_excess(keychain)?;
+	let proof = TxProof::from_payment(
+		proof.receiver_address.clone(),
+		signature,
+		sender,
+		slate.amount,
+		excess,
+	)
+	.map_err(|_| ErrorKind::VerifyProof)?;
+	Ok(Some(proof))
+}
+
 /// Verifies a transaction proof and returns relevant information
 pub fn verify_tx_proof(
 	tx_proof: &TxProof,
@@ -557,6 +650,20 @@ pub fn verify_tx_proof(
 	),
 	Error,
 > {
+	if tx_proof.is_payment() {
+		// The receiver signed the payment, its outputs are not part of the proof
+		let (sender, excess) = tx_proof
+			.verify_payment()
+			.map_err(|_| ErrorKind::VerifyProof)?;
+		return Ok((
+			sender,
+			tx_proof.address.clone(),
+			tx_proof.amount,
+			vec![],
+			excess,
+		));
+	}
+
 	// Check signature on the message and decrypt it
 	// The `destination` of the message is the sender of the tx
 	let (destination, slate) = tx_proof
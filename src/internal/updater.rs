@@ -15,7 +15,7 @@
 use super::keys;
 use crate::wallet::types::{
 	BlockFees, CbData, NodeClient, OutputCommitMapping, OutputData, OutputStatus, TxLogEntry,
-	TxLogEntryType, WalletBackend, WalletInfo,
+	TxLogEntryType, WalletBackend, WalletEvent, WalletEventType, WalletInfo,
 };
 use failure::Error;
 use grin_core::consensus::reward;
@@ -27,9 +27,15 @@ use grin_keychain::{Identifier, Keychain, SwitchCommitmentType};
 use grin_util::from_hex;
 use grin_util::secp::pedersen::Commitment;
 use log::{debug, warn};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
+/// Confirmed kernels less than this many blocks deep are checked for reorgs
+const REORG_CHECK_DEPTH: u64 = 60;
+/// Blocks below the height recorded for a transaction that are still searched
+/// for its kernel, in case of a reorg or a node that was behind
+const KERNEL_SEARCH_MARGIN: u64 = 100;
+
 /// Retrieve all of the outputs (doesn't attempt to update from node)
 pub fn retrieve_outputs<T: ?Sized, C, K>(
 	wallet: &mut T,
@@ -155,6 +161,7 @@ where
 	K: Keychain,
 {
 	let height = wallet.w2n_client().get_chain_height()?;
+	refresh_kernel_state(wallet, height, parent_key_id)?;
 	refresh_output_state(wallet, height, parent_key_id, update_all)?;
 	Ok(height)
 }
@@ -242,6 +249,7 @@ where
 		tx.tx_type = TxLogEntryType::TxReceivedCancelled;
 	}
 	batch.save_tx_log_entry(&tx)?;
+	batch.queue_event(WalletEvent::from_tx(WalletEventType::TxCancelled, &tx))?;
 	batch.commit()?;
 	Ok(())
 }
@@ -275,6 +283,7 @@ where
 			return Ok(());
 		}
 		let mut batch = wallet.batch()?;
+		let mut confirmed_txs = HashSet::new();
 		for (commit, (id, mmr_index)) in wallet_outputs.iter() {
 			if let Ok(mut output) = wallet.get_output(id, mmr_index) {
 				match api_outputs.get(&commit) {
@@ -294,6 +303,10 @@ where
 							t.update_confirmation_ts();
 							output.tx_log_entry = Some(log_id);
 							batch.save_tx_log_entry(&t)?;
+							batch.queue_event(WalletEvent::from_tx(
+								WalletEventType::TxConfirmed,
+								&t,
+							))?;
 						}
 						// also mark the transaction in which this output is involved as confirmed
 						// note that one involved input/output confirmation SHOULD be enough
@@ -304,6 +317,13 @@ where
 									&& t.parent_key_id == *parent_key_id
 							});
 							if let Some(mut t) = tx {
+								// Skip transactions confirmed through their kernel or another output
+								if !t.confirmed && confirmed_txs.insert(t.id) {
+									batch.queue_event(WalletEvent::from_tx(
+										WalletEventType::TxConfirmed,
+										&t,
+									))?;
+								}
 								t.update_confirmation_ts();
 								t.confirmed = true;
 								batch.save_tx_log_entry(&t)?;
@@ -352,6 +372,155 @@ where
 	Ok(())
 }
 
+/// Confirm transactions by looking up their kernel on chain, which also works
+/// for transactions without outputs of ours. Recently confirmed kernels are
+/// checked again, to catch transactions that were dropped by a reorg
+fn refresh_kernel_state<T: ?Sized, C, K>(
+	wallet: &mut T,
+	height: u64,
+	parent_key_id: &Identifier,
+) -> Result<(), Error>
+where
+	T: WalletBackend<C, K>,
+	C: NodeClient,
+	K: Keychain,
+{
+	debug!("Refreshing transaction kernels");
+
+	let txs: Vec<TxLogEntry> = wallet
+		.tx_logs()?
+		.filter(|t| {
+			t.parent_key_id == *parent_key_id
+				&& t.excess.is_some()
+				&& (t.tx_type == TxLogEntryType::TxSent || t.tx_type == TxLogEntryType::TxReceived)
+				&& match (t.confirmed, t.kernel_height) {
+					(false, _) => true,
+					(true, Some(h)) => h + REORG_CHECK_DEPTH > height,
+					(true, None) => false,
+				}
+		})
+		.collect();
+
+	let mut updated = vec![];
+	let mut confirmed = HashMap::new();
+	let mut reorged = vec![];
+	for mut t in txs {
+		let excess = match t.excess.clone() {
+			Some(e) => e,
+			None => continue,
+		};
+		let min_height = kernel_min_height(wallet, &t)?;
+		let kernel = match wallet.w2n_client().get_kernel(&excess, min_height, None) {
+			Ok(k) => k,
+			Err(e) => {
+				// Nodes without kernel lookups still confirm through outputs
+				warn!("Unable to look up transaction kernels: {}", e);
+				return Ok(());
+			}
+		};
+
+		match kernel {
+			Some((_, kernel_height, _)) => {
+				if t.confirmed && t.kernel_height == Some(kernel_height) {
+					continue;
+				}
+				if !t.confirmed {
+					t.confirmed = true;
+					t.update_confirmation_ts();
+					confirmed.insert(t.id, kernel_height);
+				}
+				t.kernel_height = Some(kernel_height);
+				updated.push(t);
+			}
+			None => {
+				if !t.confirmed {
+					continue;
+				}
+				warn!(
+					"Kernel of transaction {} is no longer on chain, it was dropped by a reorg",
+					t.id
+				);
+				t.confirmed = false;
+				t.confirmation_ts = None;
+				t.kernel_height = None;
+				reorged.push(t.id);
+				updated.push(t);
+			}
+		}
+	}
+
+	if updated.is_empty() {
+		return Ok(());
+	}
+
+	// Outputs of the transaction follow its kernel: inputs are locked
+	// and outputs unconfirmed until the kernel is on chain
+	let outputs: Vec<OutputData> = wallet
+		.outputs()?
+		.filter(|o| o.root_key_id == *parent_key_id)
+		.filter(|o| match o.tx_log_entry {
+			Some(id) => confirmed.contains_key(&id) || reorged.contains(&id),
+			None => false,
+		})
+		.collect();
+
+	let mut batch = wallet.batch()?;
+	for t in &updated {
+		batch.save_tx_log_entry(t)?;
+		if confirmed.contains_key(&t.id) {
+			batch.queue_event(WalletEvent::from_tx(WalletEventType::TxConfirmed, t))?;
+		}
+	}
+	for mut o in outputs {
+		let id = o.tx_log_entry.unwrap();
+		match confirmed.get(&id) {
+			Some(kernel_height) => match o.status {
+				OutputStatus::Unconfirmed => {
+					o.height = *kernel_height;
+					o.status = OutputStatus::Unspent;
+				}
+				OutputStatus::Locked => o.status = OutputStatus::Spent,
+				_ => continue,
+			},
+			None => match o.status {
+				OutputStatus::Unspent => o.status = OutputStatus::Unconfirmed,
+				OutputStatus::Spent => o.status = OutputStatus::Locked,
+				_ => continue,
+			},
+		}
+		batch.save_output(&o)?;
+	}
+	batch.commit()?;
+	Ok(())
+}
+
+/// Whether the inputs of an unconfirmed sent transaction were spent by another
+/// transaction. Only certain if the node confirms the kernel is not on chain
+pub fn inputs_spent_elsewhere<T: ?Sized, C, K>(
+	wallet: &mut T,
+	tx: &TxLogEntry,
+) -> Result<bool, Error>
+where
+	T: WalletBackend<C, K>,
+	C: NodeClient,
+	K: Keychain,
+{
+	let spent = wallet.outputs()?.any(|o| {
+		o.root_key_id == tx.parent_key_id
+			&& o.tx_log_entry == Some(tx.id)
+			&& o.status == OutputStatus::Spent
+	});
+	let excess = match (&tx.excess, spent) {
+		(Some(e), true) => e.clone(),
+		_ => return Ok(false),
+	};
+	let min_height = kernel_min_height(wallet, tx)?;
+	match wallet.w2n_client().get_kernel(&excess, min_height, None) {
+		Ok(kernel) => Ok(kernel.is_none()),
+		Err(_) => Ok(false),
+	}
+}
+
 fn clean_old_unconfirmed<T: ?Sized, C, K>(wallet: &mut T, height: u64) -> Result<(), Error>
 where
 	T: WalletBackend<C, K>,
@@ -550,3 +719,55 @@ where
 		}
 	}
 }
+
+/// Look up a kernel on chain by its excess. Returns the height it
+/// was included at and its number of confirmations
+pub fn kernel_confirmations<T: ?Sized, C, K>(
+	wallet: &mut T,
+	excess: &Commitment,
+) -> Result<Option<(u64, u64)>, Error>
+where
+	T: WalletBackend<C, K>,
+	C: NodeClient,
+	K: Keychain,
+{
+	// Bound the search by our own transaction with this kernel, if there is one
+	let tx = wallet
+		.tx_logs()?
+		.find(|t| t.excess.as_ref() == Some(excess));
+	let min_height = match tx {
+		Some(t) => kernel_min_height(wallet, &t)?,
+		None => None,
+	};
+	let client = wallet.w2n_client();
+	let kernel = client.get_kernel(excess, min_height, None)?;
+	match kernel {
+		Some((_, height, _)) => {
+			let tip = client.get_chain_height()?;
+			Ok(Some((height, tip.saturating_sub(height) + 1)))
+		}
+		None => Ok(None),
+	}
+}
+
+/// Lowest height to search for the kernel of a transaction. It can't be on chain
+/// before the transaction was finalized or its outputs were created. `None`
+/// if the wallet doesn't know at which height the transaction was made
+fn kernel_min_height<T: ?Sized, C, K>(wallet: &T, tx: &TxLogEntry) -> Result<Option<u64>, Error>
+where
+	T: WalletBackend<C, K>,
+	C: NodeClient,
+	K: Keychain,
+{
+	let height = match tx.kernel_height.or(tx.finalized_height) {
+		Some(h) => Some(h),
+		None => wallet
+			.outputs()?
+			.filter(|o| {
+				o.root_key_id == tx.parent_key_id && o.tx_log_entry == Some(tx.id) && o.height > 0
+			})
+			.map(|o| o.height)
+			.min(),
+	};
+	Ok(height.map(|h| h.saturating_sub(KERNEL_SEARCH_MARGIN)))
+}
@@ -24,11 +24,12 @@ mod wallet;
 use clap::{crate_version, App, Arg, ArgMatches};
 use colored::*;
 use common::config::Wallet713Config;
+use common::socks;
 use common::{ErrorKind, Result, RuntimeMode};
 use contacts::{AddressBook, Backend};
 use controller::cli::CLI;
 use grin_core::global::{set_mining_mode, ChainTypes};
-use wallet::create_container;
+use wallet::{create_container, create_offline_container};
 
 fn do_config(
 	args: &ArgMatches,
@@ -96,7 +97,10 @@ fn welcome(args: &ArgMatches, runtime_mode: &RuntimeMode) -> Result<Wallet713Con
 		common::set_runtime_mode(runtime_mode);
 	};
 
-	let config = do_config(args, &chain, true, None, args.value_of("config-path"))?;
+	let mut config = do_config(args, &chain, true, None, args.value_of("config-path"))?;
+	if args.is_present("offline") {
+		config.offline = Some(true);
+	}
 	set_mining_mode(config.chain.clone().unwrap_or(ChainTypes::Mainnet));
 
 	Ok(config)
@@ -112,6 +116,8 @@ fn main() {
         .arg(Arg::from_usage("[account] -a, --account=<account> 'the account to use'"))
         .arg(Arg::from_usage("[daemon] -d, --daemon 'run daemon'"))
         .arg(Arg::from_usage("[floonet] -f, --floonet 'use floonet'"))
+        .arg(Arg::from_usage("[offline] -o, --offline 'run without a node connection'"))
+        .arg(Arg::from_usage("[relay] -r, --relay 'run a grinbox relay instead of the wallet'"))
         .get_matches();
 
 	let runtime_mode = match matches.is_present("daemon") {
@@ -131,6 +137,15 @@ fn main() {
 		env_logger::init();
 	}
 
+	socks::set_proxy(config.proxy());
+
+	if matches.is_present("relay") {
+		if let Err(e) = broker::relay::run(config.relay()) {
+			cli_message!("{}: {}", "ERROR".bright_red(), e);
+		}
+		return;
+	}
+
 	let data_path_buf = config.get_data_path().unwrap();
 	let data_path = data_path_buf.to_str().unwrap();
 
@@ -139,10 +154,13 @@ fn main() {
 	let address_book = AddressBook::new(Box::new(address_book_backend))
 		.expect("could not create an address book!");
 
-	let container = create_container(config, address_book).unwrap();
-
-	let cli = CLI::new(container);
-	cli.start();
+	if config.offline() {
+		let container = create_offline_container(config, address_book).unwrap();
+		CLI::new(container).start();
+	} else {
+		let container = create_container(config, address_book).unwrap();
+		CLI::new(container).start();
+	}
 
 	press_any_key();
 }
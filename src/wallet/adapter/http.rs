@@ -14,11 +14,10 @@
 
 /// HTTP Wallet 'plugin' implementation
 use super::Adapter;
-use crate::wallet::types::VersionedSlate;
+use crate::common::client;
+use crate::wallet::types::{Slate, SlateVersion, VersionedSlate};
 use crate::wallet::ErrorKind;
 use failure::Error;
-use grin_api::client;
-use grin_api::Error as APIError;
 use log::*;
 use serde::Serialize;
 use serde_json::json;
@@ -33,8 +32,9 @@ impl HTTPAdapter {
 		Box::new(Self {})
 	}
 
-	/// Check version of the other wallet
-	fn check_other_version(&self, url: &str) -> Result<(), Error> {
+	/// Check version of the other wallet and negotiate the slate version.
+	/// Nothing was sent yet, so any failure means another address can be tried
+	fn check_other_version(&self, url: &str) -> Result<SlateVersion, Error> {
 		let req = json!({
 			"jsonrpc": "2.0",
 			"method": "check_version",
@@ -45,41 +45,52 @@ impl HTTPAdapter {
 		let res: String = post(url, None, &req).map_err(|e| {
 			let report = format!("Performing version check (is recipient listening?): {}", e);
 			error!("{}", report);
-			ErrorKind::ClientCallback(report)
+			ErrorKind::PeerUnreachable(report)
 		})?;
 
-		let res: Value = serde_json::from_str(&res).unwrap();
+		let res: Value = serde_json::from_str(&res).map_err(|e| {
+			let report = format!("Performing version check: invalid response: {}", e);
+			error!("{}", report);
+			ErrorKind::PeerUnreachable(report)
+		})?;
 		trace!("Response: {}", res);
 		if res["error"] != json!(null) {
 			let report = format!(
-				"Posting transaction slate: Error: {}, Message: {}",
+				"Performing version check: Error: {}, Message: {}",
 				res["error"]["code"], res["error"]["message"]
 			);
 			error!("{}", report);
-			return Err(ErrorKind::ClientCallback(report).into());
+			return Err(ErrorKind::PeerUnreachable(report).into());
 		}
 
 		let resp_value = res["result"]["Ok"].clone();
 		trace!("resp_value: {}", resp_value.clone());
 		let foreign_api_version: u16 =
-			serde_json::from_value(resp_value["foreign_api_version"].clone()).unwrap();
-		let supported_slate_versions: Vec<String> =
-			serde_json::from_value(resp_value["supported_slate_versions"].clone()).unwrap();
+			serde_json::from_value(resp_value["foreign_api_version"].clone()).unwrap_or(0);
+		// Versions unknown to us are ignored
+		let supported_slate_versions: Vec<Value> =
+			serde_json::from_value(resp_value["supported_slate_versions"].clone())
+				.unwrap_or_default();
+		let supported_slate_versions: Vec<SlateVersion> = supported_slate_versions
+			.into_iter()
+			.filter_map(|v| serde_json::from_value(v).ok())
+			.collect();
 
 		// trivial tests for now, but will be expanded later
 		if foreign_api_version < 2 {
 			let report = format!("Other wallet reports unrecognized API format.");
 			error!("{}", report);
-			return Err(ErrorKind::ClientCallback(report).into());
+			return Err(ErrorKind::PeerUnreachable(report).into());
 		}
 
-		if !supported_slate_versions.contains(&"V2".to_owned()) {
-			let report = format!("Unable to negotiate slate format with other wallet.");
-			error!("{}", report);
-			return Err(ErrorKind::ClientCallback(report).into());
+		match SlateVersion::negotiate(&supported_slate_versions) {
+			Some(version) => Ok(version),
+			None => {
+				let report = format!("Unable to negotiate slate format with other wallet.");
+				error!("{}", report);
+				Err(ErrorKind::PeerUnreachable(report).into())
+			}
 		}
-
-		Ok(())
 	}
 }
 
@@ -100,7 +111,9 @@ impl Adapter for HTTPAdapter {
 		let url = format!("{}/v2/foreign", dest);
 		debug!("Posting transaction slate to {}", url);
 
-		self.check_other_version(&url)?;
+		// Never send a newer version than requested
+		let version = std::cmp::min(slate.version(), self.check_other_version(&url)?);
+		let slate = VersionedSlate::into_version(Slate::from(slate), version);
 
 		// Note: not using easy-jsonrpc as don't want the dependencies in this crate
 		let req = json!({
@@ -118,7 +131,7 @@ impl Adapter for HTTPAdapter {
 		let res: String = post(url.as_str(), None, &req).map_err(|e| {
 			let report = format!("Posting transaction slate (is recipient listening?): {}", e);
 			error!("{}", report);
-			ErrorKind::ClientCallback(report)
+			callback_error(&e, report)
 		})?;
 
 		let res: Value = serde_json::from_str(&res).unwrap();
@@ -145,7 +158,7 @@ impl Adapter for HTTPAdapter {
 	}
 }
 
-pub fn post<IN>(url: &str, api_secret: Option<String>, input: &IN) -> Result<String, APIError>
+pub fn post<IN>(url: &str, api_secret: Option<String>, input: &IN) -> Result<String, client::Error>
 where
 	IN: Serialize,
 {
@@ -153,3 +166,12 @@ where
 	let res = client::send_request(req)?;
 	Ok(res)
 }
+
+/// Only a request that never reached the other wallet counts as unreachable,
+/// once it got there the slate may have been signed
+fn callback_error(e: &client::Error, report: String) -> ErrorKind {
+	match e.is_connection() {
+		true => ErrorKind::PeerUnreachable(report),
+		false => ErrorKind::ClientCallback(report),
+	}
+}
@@ -17,7 +17,7 @@ use super::Adapter;
 use crate::api::listener::ListenerInterface;
 use crate::common::{Arc, Keychain, Mutex};
 use crate::wallet::types::{NodeClient, VersionedSlate, WalletBackend};
-use crate::wallet::Container;
+use crate::wallet::{Container, ErrorKind};
 use failure::Error;
 
 #[derive(Clone)]
@@ -57,8 +57,12 @@ where
 	}
 
 	fn send_tx_async(&self, dest: &str, slate: &VersionedSlate) -> Result<(), Error> {
+		// Nothing was handed over if publishing fails
 		let c = self.container.lock();
-		c.listener(ListenerInterface::Keybase)?
-			.publish(slate, &dest.to_owned())
+		let result = match c.listener(ListenerInterface::Keybase) {
+			Ok(listener) => listener.publish(slate, &dest.to_owned()),
+			Err(e) => Err(e.into()),
+		};
+		result.map_err(|e| ErrorKind::PeerUnreachable(e.to_string()).into())
 	}
 }
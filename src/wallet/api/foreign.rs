@@ -12,17 +12,21 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use super::{check_middleware, VersionInfo};
+use super::{check_middleware, policy, Owner, VersionInfo};
+use crate::common::hasher::derive_address_key;
 use crate::common::{Arc, Keychain, Mutex, MutexGuard};
+use crate::contacts::parse_address;
 use crate::internal::{tx, updater};
 use crate::wallet::types::{
-	BlockFees, CbData, NodeClient, NodeVersionInfo, Slate, SlateVersion, WalletBackend,
+	BlockFees, CbData, NodeClient, NodeVersionInfo, Slate, SlateVersion, TxLogEntryType,
+	WalletBackend, WalletEvent, WalletEventType,
 };
 use crate::wallet::Container;
 use colored::Colorize;
 use failure::Error;
 use gotham_derive::StateData;
 use grin_core::core::amount_to_hr_string;
+use log::error;
 use std::marker::PhantomData;
 
 const FOREIGN_API_VERSION: u16 = 2;
@@ -108,7 +112,7 @@ where
 
 		Ok(VersionInfo {
 			foreign_api_version: FOREIGN_API_VERSION,
-			supported_slate_versions: vec![SlateVersion::V2],
+			supported_slate_versions: SlateVersion::supported(),
 		})
 	}
 
@@ -147,8 +151,19 @@ where
 		dest_acct_name: Option<&str>,
 		address: Option<String>,
 		message: Option<String>,
+		address_index: Option<u32>,
 	) -> Result<Slate, Error> {
 		self.open_and_close(|c| {
+			let index = address_index.unwrap_or(c.config.grinbox_address_index());
+			let policy = c.config.receive_policy();
+			let sender = address.as_ref().and_then(|a| parse_address(a).ok());
+			policy::check_sender(
+				&policy,
+				&c.address_book,
+				slate,
+				sender.as_ref().map(|a| a.as_ref()),
+			)?;
+			policy::check_slate(&policy, slate)?;
 			let w = c.backend()?;
 
 			if let Some(m) = self.middleware.as_ref() {
@@ -158,13 +173,44 @@ where
 					Some(slate),
 				)?;
 			}
+			policy::check_pending(&policy, w, slate)?;
+
+			let mut slate = tx::receive_tx(w, slate, dest_acct_name, address.clone(), message)?;
+
+			if address_index.is_some() {
+				let (txs, _) = updater::retrieve_txs(w, None, Some(slate.id), None, false, false)?;
+				let mut batch = w.batch()?;
+				for mut t in txs {
+					if t.tx_type == TxLogEntryType::TxReceived {
+						t.address_index = address_index;
+						batch.save_tx_log_entry(&t)?;
+					}
+				}
+				batch.commit()?;
+			}
 
-			let slate = tx::receive_tx(w, slate, dest_acct_name, address.clone(), message)?;
+			if slate.receiver_proof.is_some() {
+				// The output is already stored, a proof we can't sign shouldn't fail the receive
+				let keychain = w.keychain();
+				if let Err(e) = derive_address_key(keychain, index)
+					.map_err(Error::from)
+					.and_then(|key| tx::sign_payment_proof(keychain, &mut slate, &key))
+				{
+					error!("Unable to sign payment proof: {}", e);
+					slate.receiver_proof = None;
+				}
+			}
 
-			let from = match address {
+			let mut from = match address {
 				Some(a) => format!(" from {}", a.bright_green()),
 				None => String::new(),
 			};
+			if let Some(i) = address_index {
+				from.push_str(&format!(
+					" on address index {}",
+					i.to_string().bright_green()
+				));
+			}
 
 			cli_message!(
 				"Slate {} for {} grin received{}",
@@ -177,6 +223,15 @@ where
 		})
 	}
 
+	/// Post an event about a slate from `address` to the event streams and webhooks
+	pub fn notify(&self, event: WalletEventType, slate: &Slate, address: Option<String>) {
+		let mut event = WalletEvent::new(event);
+		event.slate_id = Some(slate.id);
+		event.amount = Some(slate.amount);
+		event.address = address;
+		Owner::new(self.container.clone()).notify(event);
+	}
+
 	/*pub fn finalize_invoice_tx(&self, slate: &Slate) -> Result<Slate, Error> {
 		let mut w = self.wallet.lock();
 		if let Some(m) = self.middleware.as_ref() {
@@ -14,15 +14,17 @@
 
 pub mod foreign;
 pub mod owner;
+pub mod policy;
+pub mod tx_monitor;
 pub mod types;
+pub mod webhook;
 
 pub use self::foreign::Foreign;
 use self::foreign::ForeignCheckMiddlewareFn;
 pub use self::owner::Owner;
+pub use self::tx_monitor::TxMonitor;
 pub use self::types::*;
-use crate::wallet::types::{
-	NodeVersionInfo, Slate, CURRENT_SLATE_VERSION, GRIN_BLOCK_HEADER_VERSION,
-};
+use crate::wallet::types::{NodeVersionInfo, Slate, GRIN_BLOCK_HEADER_VERSION, MIN_SLATE_VERSION};
 use crate::wallet::ErrorKind;
 use failure::Error;
 
@@ -40,7 +42,7 @@ pub fn check_middleware(
 				bhv = n.block_header_version;
 			}
 			if let Some(s) = slate {
-				if s.version_info.version < CURRENT_SLATE_VERSION
+				if s.version_info.version < MIN_SLATE_VERSION
 					|| (bhv == 1 && s.version_info.block_header_version != 1)
 					|| (bhv > 1 && s.version_info.block_header_version < GRIN_BLOCK_HEADER_VERSION)
 				{
@@ -12,33 +12,45 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use super::{webhook, TxMonitor};
 use crate::api::listener::*;
 use crate::cli_message;
 use crate::common::config::Wallet713Config;
+use crate::common::crypto::{sign_message, verify_message, Hex, Signature};
 use crate::common::hasher::derive_address_key;
 use crate::common::{Arc, Keychain, Mutex, MutexGuard};
-use crate::contacts::{parse_address, AddressType, Contact, GrinboxAddress};
+use crate::contacts::{
+	check_contact_name, parse_address, read_contacts, write_contacts, Address, AddressType,
+	Contact, ContactsFormat, GrinboxAddress, KeybaseAddress,
+};
 use crate::internal::*;
 use crate::wallet::adapter::{Adapter, GrinboxAdapter, HTTPAdapter, KeybaseAdapter};
 use crate::wallet::types::{
-	AcctPathMapping, InitTxArgs, NodeClient, NodeHeightResult, NodeVersionInfo,
-	OutputCommitMapping, Slate, SlateVersion, TxLogEntry, TxProof, TxWrapper, VersionedSlate,
-	WalletBackend, WalletInfo,
+	armor, AcctPathMapping, IncomingSlate, InitTxArgs, NodeClient, NodeHeightResult,
+	NodeVersionInfo, OutboxEntry, OutputCommitMapping, OutputSnapshot, OutputStatus,
+	PaymentRequest, PaymentRequestStatus, ScanMonitor, ScanProgress, ScanStart, SignedMessage,
+	Slate, SlateVersion, SnapshotOutput, TxLogEntry, TxLogEntryType, TxProof, TxWrapper,
+	VersionedSlate, WalletBackend, WalletEvent, WalletEventType, WalletInfo, WebhookEntry,
+	PAYMENT_REQUEST_FIRST_INDEX,
 };
 use crate::wallet::{Container, ErrorKind};
+use chrono::{Duration, Utc};
 use colored::Colorize;
 use failure::Error;
+use futures::sync::mpsc::{unbounded, UnboundedReceiver};
 use gotham_derive::StateData;
 use grin_core::core::hash::Hashed;
 use grin_core::core::{amount_to_hr_string, Transaction};
 use grin_core::ser::{ser_vec, ProtocolVersion};
-use grin_keychain::Identifier;
-use grin_util::secp::key::PublicKey;
+use grin_keychain::{Identifier, SwitchCommitmentType};
+use grin_util::secp::key::{PublicKey, SecretKey};
 use grin_util::secp::pedersen::Commitment;
-use grin_util::{to_hex, ZeroingString};
-use log::{debug, error};
+use grin_util::{from_hex, to_hex, ZeroingString};
+use log::{debug, error, warn};
 use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
+use std::path::Path;
+use std::thread::spawn;
 use uuid::Uuid;
 
 #[derive(StateData)]
@@ -150,6 +162,11 @@ where
 		}
 	}
 
+	/// Repost and expire stuck sent transactions in the background
+	pub fn start_tx_monitor(&self) {
+		TxMonitor::new(self.container.clone()).spawn();
+	}
+
 	/// Stop all running listeners
 	pub fn stop_listeners(&self) -> Result<HashSet<ListenerInterface>, Error> {
 		let mut c = self.container.lock();
@@ -176,6 +193,26 @@ where
 		})
 	}
 
+	/// All addresses the grinbox listener subscribes to, with their index
+	pub fn grinbox_addresses(&self) -> Result<Vec<(u32, GrinboxAddress)>, Error> {
+		self.open_and_close(|c| {
+			let indices = c.grinbox_indices()?;
+			let domain = c.config.grinbox_domain.clone();
+			let port = c.config.grinbox_port;
+			let keychain = c.backend()?.keychain();
+			let mut addresses = Vec::with_capacity(indices.len());
+			for index in indices {
+				let sec_key = derive_address_key(keychain, index)?;
+				let pub_key = PublicKey::from_secret_key(keychain.secp(), &sec_key)?;
+				addresses.push((
+					index,
+					GrinboxAddress::new(pub_key, Some(domain.clone()), port),
+				));
+			}
+			Ok(addresses)
+		})
+	}
+
 	pub fn set_grinbox_address_index(&self, index: u32) -> Result<GrinboxAddress, Error> {
 		let grinbox = self.stop_listener(ListenerInterface::Grinbox)?;
 		{
@@ -191,6 +228,42 @@ where
 		self.grinbox_address()
 	}
 
+	/// Sign a message with the key of the grinbox address at `index`, or the
+	/// current one
+	pub fn sign_message(&self, message: &str, index: Option<u32>) -> Result<SignedMessage, Error> {
+		self.open_and_close(|c| {
+			let index = index.unwrap_or(c.config.grinbox_address_index());
+			let keychain = c.backend()?.keychain();
+			let sec_key = derive_address_key(keychain, index)?;
+			let pub_key = PublicKey::from_secret_key(keychain.secp(), &sec_key)?;
+			let address = GrinboxAddress::new(
+				pub_key,
+				Some(c.config.grinbox_domain.clone()),
+				c.config.grinbox_port,
+			);
+			let signature = sign_message(message, &sec_key)?;
+
+			Ok(SignedMessage {
+				address: address.stripped(),
+				message: message.to_owned(),
+				signature: signature.to_hex(),
+			})
+		})
+	}
+
+	/// Whether a message was signed by the key of a grinbox address
+	pub fn verify_message(
+		&self,
+		address: &str,
+		message: &str,
+		signature: &str,
+	) -> Result<bool, Error> {
+		let address = GrinboxAddress::from_str(address)
+			.map_err(|_| ErrorKind::NotGrinboxAddress(address.to_owned()))?;
+		let signature = Signature::from_hex(signature).map_err(|_| ErrorKind::InvalidSignature)?;
+		Ok(verify_message(message, &signature, &address.public_key()?).is_ok())
+	}
+
 	pub fn accounts(&self) -> Result<Vec<AcctPathMapping>, Error> {
 		let mut c = self.container.lock();
 		let w = c.backend()?;
@@ -222,10 +295,19 @@ where
 		Ok(contacts)
 	}
 
-	pub fn add_contact(&self, name: &str, address: &str) -> Result<(), Error> {
-		let address = parse_address(address)?;
+	/// Add a contact, the first address is preferred when sending
+	pub fn add_contact(
+		&self,
+		name: &str,
+		addresses: Vec<&str>,
+		notes: Option<&str>,
+	) -> Result<(), Error> {
+		check_contact_name(name)?;
+		let addresses = parse_addresses(addresses)?;
 		let mut c = self.container.lock();
-		let contact = Contact::new(name, address)?;
+		let mut contact = Contact::new(name, parse_address(&addresses[0].to_string())?)?;
+		contact.set_addresses(addresses);
+		contact.notes = notes.map(|n| n.to_owned());
 		c.address_book.add_contact(&contact)?;
 		Ok(())
 	}
@@ -240,6 +322,61 @@ where
 		Ok(())
 	}
 
+	pub fn rename_contact(&self, name: &str, new_name: &str) -> Result<(), Error> {
+		check_contact_name(new_name)?;
+		self.contact(name)?;
+		let mut c = self.container.lock();
+		c.address_book.rename_contact(name, new_name)?;
+		Ok(())
+	}
+
+	/// Replace the addresses of a contact, the first one is preferred
+	pub fn set_contact_addresses(&self, name: &str, addresses: Vec<&str>) -> Result<(), Error> {
+		let addresses = parse_addresses(addresses)?;
+		let mut contact = self.contact(name)?;
+		contact.set_addresses(addresses);
+		let mut c = self.container.lock();
+		c.address_book.update_contact(&contact)?;
+		Ok(())
+	}
+
+	pub fn set_contact_notes(&self, name: &str, notes: Option<&str>) -> Result<(), Error> {
+		let mut contact = self.contact(name)?;
+		contact.notes = notes.map(|n| n.to_owned());
+		let mut c = self.container.lock();
+		c.address_book.update_contact(&contact)?;
+		Ok(())
+	}
+
+	/// Add the contacts in a JSON or CSV file. Returns the number of contacts
+	/// that were added and the number that were skipped because the name
+	/// was already taken
+	pub fn import_contacts(
+		&self,
+		data: &str,
+		format: ContactsFormat,
+		overwrite: bool,
+	) -> Result<(usize, usize), Error> {
+		let contacts = read_contacts(data, format)?;
+		let mut c = self.container.lock();
+		let imported = c.address_book.import_contacts(&contacts, overwrite)?;
+		Ok((imported, contacts.len() - imported))
+	}
+
+	pub fn export_contacts(&self, format: ContactsFormat) -> Result<String, Error> {
+		let contacts = self.contacts()?;
+		Ok(write_contacts(&contacts, format)?)
+	}
+
+	fn contact(&self, name: &str) -> Result<Contact, Error> {
+		let c = self.container.lock();
+		let contact = c
+			.address_book
+			.get_contact(name)?
+			.ok_or(ErrorKind::ContactNotFound(name.to_owned()))?;
+		Ok(contact)
+	}
+
 	pub fn retrieve_outputs(
 		&self,
 		include_spent: bool,
@@ -320,7 +457,7 @@ where
 		})
 	}
 
-	fn retrieve_tx(
+	pub fn retrieve_tx(
 		&self,
 		tx_id: Option<u32>,
 		tx_slate_id: Option<Uuid>,
@@ -359,84 +496,228 @@ where
 	}
 
 	pub fn init_send_tx(&self, mut args: InitTxArgs) -> Result<Slate, Error> {
+		// Methods and addresses to try, in order of preference
+		let mut destinations = vec![];
 		if let Some(sa) = &mut args.send_args {
-			if sa.dest.starts_with("@") {
+			let addresses = if sa.dest.starts_with("@") {
 				// Look up contact by address
 				let c = self.container.lock();
 				let contact = c.address_book.get_contact(&sa.dest[1..])?;
-				sa.dest = contact
+				contact
 					.ok_or(ErrorKind::ContactNotFound(sa.dest.clone()))?
-					.address;
-			}
-
-			if sa.method.is_none() {
-				// Try to infer method from the address
-				let address = parse_address(&sa.dest)?;
-				sa.method = Some(
-					match address.address_type() {
-						AddressType::Http => "http",
-						AddressType::Grinbox => "grinbox",
-						AddressType::Keybase => "keybase",
+					.addresses()
+			} else {
+				vec![sa.dest.clone()]
+			};
+
+			match sa.method.clone() {
+				Some(method) => {
+					// Only the contact's addresses the method can send to
+					for address in &addresses {
+						match parse_address(address) {
+							Ok(a) if address_method(&*a) == method => {
+								destinations.push((method.clone(), a.stripped(), a.to_string()))
+							}
+							_ => {}
+						}
 					}
-					.to_owned(),
-				);
-				sa.dest = address.stripped();
+					if destinations.is_empty() {
+						let address = addresses[0].clone();
+						destinations.push((method, address.clone(), address));
+					}
+				}
+				None => {
+					// Infer the method from the address
+					for address in &addresses {
+						let address = parse_address(address)?;
+						destinations.push((
+							address_method(&*address).to_owned(),
+							address.stripped(),
+							address.to_string(),
+						));
+					}
+				}
 			}
+			sa.method = Some(destinations[0].0.clone());
+			sa.dest = destinations[0].1.clone();
+		}
+		// Only slates sent over grinbox or keybase expire by default, a file or
+		// cold storage slate can take any time to come back
+		let answered_later = destinations
+			.iter()
+			.any(|(method, _, _)| method == "grinbox" || method == "keybase");
+		if args.ttl_blocks.is_none() && answered_later {
+			let c = self.container.lock();
+			args.ttl_blocks = Some(c.config.tx_ttl_blocks());
 		}
-		let mut send_args = args.send_args.clone();
-		let version = match args.target_slate_version {
-			Some(v) => SlateVersion::try_from(v)?,
-			None => SlateVersion::default(),
+		let send_args = args.send_args.clone();
+		let target_version = match args.target_slate_version {
+			Some(v) => Some(SlateVersion::try_from(v)?),
+			None => None,
 		};
 		let mut slate = self.open_and_close(|c| {
+			let index = c.config.grinbox_address_index();
 			let w = c.backend()?;
-			tx::init_send_tx(w, args)
+			let mut slate = tx::init_send_tx(w, args)?;
+			let keychain = w.keychain();
+			let address_key = derive_address_key(keychain, index)?;
+			tx::request_payment_proof(keychain, &mut slate, &address_key)?;
+			Ok(slate)
 		})?;
 
 		// Helper functionality. If send arguments exist, attempt to send
-		match &mut send_args {
-			Some(sa) => {
-				let vslate = VersionedSlate::into_version(slate.clone(), version);
-				let adapter: Box<dyn Adapter> = match sa.method.clone().unwrap().as_ref() {
-					"http" => HTTPAdapter::new(),
-					"grinbox" => GrinboxAdapter::new(&self.container),
-					"keybase" => KeybaseAdapter::new(&self.container),
-					_ => {
-						error!("unsupported payment method");
-						return Err(ErrorKind::ClientCallback(
-							"unsupported payment method".to_owned(),
-						))?;
-					}
-				};
+		let sa = match send_args {
+			Some(sa) => sa,
+			None => return Ok(slate),
+		};
 
-				if adapter.supports_sync() {
-					slate = adapter.send_tx_sync(&sa.dest, &vslate)?.into();
-				} else {
-					adapter.send_tx_async(&sa.dest, &vslate)?;
+		// Outputs are locked before the first asynchronous attempt,
+		// a failed delivery is retried from the outbox
+		let mut locked = false;
+		let mut queued = None;
+		let mut last_error = None;
+		let mut delivered = None;
+		for (i, (method, dest, shown)) in destinations.iter().enumerate() {
+			// Grinbox and keybase peers can't be asked, use the version they sent us last
+			let version = target_version.clone().unwrap_or_else(|| {
+				let c = self.container.lock();
+				c.peer_slate_versions.get(dest).cloned().unwrap_or_default()
+			});
+			let vslate = VersionedSlate::into_version(slate.clone(), version);
+			let adapter: Box<dyn Adapter> = match method.as_ref() {
+				"http" => HTTPAdapter::new(),
+				"grinbox" => GrinboxAdapter::new(&self.container),
+				"keybase" => KeybaseAdapter::new(&self.container),
+				_ => {
+					error!("unsupported payment method");
+					return Err(ErrorKind::ClientCallback(
+						"unsupported payment method".to_owned(),
+					))?;
 				}
-				self.tx_lock_outputs(&slate, 0, Some(sa.dest.clone()))?;
+			};
 
-				cli_message!(
-					"Slate {} for {} grin sent successfully to {}",
-					slate.id.to_string().bright_green(),
-					amount_to_hr_string(slate.amount, false).bright_green(),
-					format!("{}", parse_address(&sa.dest)?).bright_green()
-				);
-
-				if adapter.supports_sync() {
-					if sa.finalize {
-						slate = self.finalize_tx(&slate, None)?;
-					};
-
-					if sa.post_tx {
-						self.post_tx(&slate.tx, sa.fluff)?;
+			let sync = adapter.supports_sync();
+			let result = if sync {
+				adapter.send_tx_sync(dest, &vslate).map(|s| {
+					slate = s.into();
+				})
+			} else {
+				if !locked {
+					self.tx_lock_outputs(&slate, 0, Some(dest.clone()))?;
+					locked = true;
+				}
+				adapter.send_tx_async(dest, &vslate).map_err(|e| {
+					if queued.is_none() {
+						queued = Some((
+							method.clone(),
+							dest.clone(),
+							shown.clone(),
+							vslate,
+							e.to_string(),
+						));
 					}
+					e
+				})
+			};
+
+			match result {
+				Ok(_) => {
+					delivered = Some((method.clone(), dest.clone(), shown.clone(), sync));
+					break;
 				}
+				Err(e) => {
+					if !is_unreachable(&e) {
+						// The peer got the slate and may have signed it, sending it
+						// another way could have it signed twice
+						if locked {
+							if let Err(c) = self.cancel_tx(None, Some(slate.id)) {
+								warn!("Unable to cancel slate {}: {}", slate.id, c);
+							}
+						}
+						return Err(e);
+					}
+					if let Some((_, _, next)) = destinations.get(i + 1) {
+						cli_message!(
+							"{} slate {} could not be delivered to {}: {}. Trying {} instead",
+							"WARNING:".bright_yellow(),
+							slate.id.to_string().bright_green(),
+							shown.bright_green(),
+							e,
+							next.bright_green()
+						);
+					}
+					last_error = Some(e);
+				}
+			}
+		}
+
+		let (method, dest, shown, sync) = match delivered {
+			Some(d) => d,
+			None => match queued {
+				Some((method, dest, shown, vslate, error)) => {
+					let mut entry =
+						OutboxEntry::new(slate.id, method.clone(), dest.clone(), vslate);
+					entry.failed(error.clone());
+					self.open_and_close(|c| {
+						let w = c.backend()?;
+						let mut batch = w.batch()?;
+						batch.save_outbox_entry(&entry)?;
+						batch.commit()?;
+						Ok(())
+					})?;
+					self.set_tx_transport(&slate.id, &method, &dest)?;
+					cli_message!(
+						"{} slate {} could not be delivered to {}: {}. It was queued in the outbox and will be retried",
+						"WARNING:".bright_yellow(),
+						slate.id.to_string().bright_green(),
+						shown.bright_green(),
+						error
+					);
+					return Ok(slate);
+				}
+				None => return Err(last_error.unwrap()),
+			},
+		};
 
-				Ok(slate)
+		if sync && !locked {
+			self.tx_lock_outputs(&slate, 0, Some(dest.clone()))?;
+		}
+		self.set_tx_transport(&slate.id, &method, &dest)?;
+
+		cli_message!(
+			"Slate {} for {} grin sent successfully to {}",
+			slate.id.to_string().bright_green(),
+			amount_to_hr_string(slate.amount, false).bright_green(),
+			shown.bright_green()
+		);
+
+		if sync {
+			if sa.finalize {
+				slate = self.finalize_tx(&slate, None)?;
+			};
+
+			if sa.post_tx {
+				self.post_tx(&slate.tx, sa.fluff)?;
 			}
-			None => Ok(slate),
 		}
+
+		Ok(slate)
+	}
+
+	/// Record the address and method a sent slate went to, which can differ
+	/// from the one its outputs were locked for after falling back
+	fn set_tx_transport(&self, slate_id: &Uuid, method: &str, dest: &str) -> Result<(), Error> {
+		self.open_and_close(|c| {
+			let w = c.backend()?;
+			if let Some(mut tx) = w.get_tx_log_by_slate_id(&slate_id.to_string())? {
+				tx.address = Some(dest.to_owned());
+				tx.transport = Some(method.to_owned());
+				let mut batch = w.batch()?;
+				batch.save_tx_log_entry(&tx)?;
+				batch.commit()?;
+			}
+			Ok(())
+		})
 	}
 
 	/*pub fn issue_invoice_tx(&self, args: IssueInvoiceTxArgs) -> Result<Slate, Error> {
@@ -472,20 +753,50 @@ where
 		slate: &Slate,
 		tx_proof: Option<&mut TxProof>,
 	) -> Result<Slate, Error> {
-		self.open_and_close(|c| {
+		let (slate, event) = self.open_and_close(|c| {
+			let index = c.config.grinbox_address_index();
 			let w = c.backend()?;
+
+			// Without a grinbox proof, fall back to the proof signed by the receiver
+			let mut payment_proof = None;
+			let tx_proof = match tx_proof {
+				Some(p) => Some(p),
+				None => {
+					// A proof we can't verify, e.g. one requested from another address
+					// index, shouldn't stop the transaction from being finalized
+					let keychain = w.keychain();
+					match derive_address_key(keychain, index)
+						.map_err(Error::from)
+						.and_then(|key| tx::payment_proof(keychain, slate, &key))
+					{
+						Ok(p) => payment_proof = p,
+						Err(e) => warn!("Dropping payment proof of slate {}: {}", slate.id, e),
+					}
+					payment_proof.as_mut()
+				}
+			};
+
 			let mut slate = slate.clone();
 			slate = tx::finalize_tx(w, &slate, tx_proof)?;
 			cli_message!(
 				"Slate {} finalized successfully",
 				slate.id.to_string().bright_green()
 			);
-			Ok(slate)
-		})
+			let (txs, _) = updater::retrieve_txs(w, None, Some(slate.id), None, false, false)?;
+			let event = txs
+				.iter()
+				.find(|t| t.tx_type == TxLogEntryType::TxSent)
+				.map(|t| WalletEvent::from_tx(WalletEventType::SlateFinalized, t));
+			Ok((slate, event))
+		})?;
+		if let Some(event) = event {
+			self.notify(event);
+		}
+		Ok(slate)
 	}
 
 	pub fn post_tx(&self, tx: &Transaction, fluff: bool) -> Result<(), Error> {
-		self.open_and_close(|c| {
+		let event = self.open_and_close(|c| {
 			let w = c.backend()?;
 			let tx_hex = to_hex(ser_vec(tx, ProtocolVersion(1)).unwrap());
 			let res = w.w2n_client().post_tx(&TxWrapper { tx_hex }, fluff);
@@ -498,9 +809,18 @@ where
 					tx.hash(),
 					fluff
 				);
-				Ok(())
+				let excess = tx.kernels().first().map(|k| k.excess);
+				let entry = w
+					.tx_logs()?
+					.find(|t| t.excess.is_some() && t.excess == excess);
+				Ok(match entry {
+					Some(t) => WalletEvent::from_tx(WalletEventType::TxPosted, &t),
+					None => WalletEvent::new(WalletEventType::TxPosted),
+				})
 			}
-		})
+		})?;
+		self.notify(event);
+		Ok(())
 	}
 
 	pub fn cancel_tx(&self, tx_id: Option<u32>, tx_slate_id: Option<Uuid>) -> Result<(), Error> {
@@ -515,6 +835,552 @@ where
 		})
 	}
 
+	/// Sent transactions that are not confirmed yet, either finalized or waiting
+	/// for an answer before their TTL runs out, with whether their inputs were
+	/// spent by another transaction. Also returns the chain height
+	pub fn stuck_txs(&self) -> Result<(u64, Vec<(TxLogEntry, bool)>), Error> {
+		self.open_and_close(|c| {
+			let w = c.backend()?;
+			let parent_key_id = w.get_parent_key_id();
+			let height = updater::refresh_outputs(w, &parent_key_id, false)?;
+
+			let (txs, _) = updater::retrieve_txs(w, None, None, Some(&parent_key_id), true, false)?;
+			let mut stuck = vec![];
+			for t in txs {
+				if t.tx_type != TxLogEntryType::TxSent
+					|| (t.finalized_height.is_none() && t.ttl_cutoff_height.is_none())
+				{
+					continue;
+				}
+				let spent_elsewhere = updater::inputs_spent_elsewhere(w, &t)?;
+				stuck.push((t, spent_elsewhere));
+			}
+			Ok((height, stuck))
+		})
+	}
+
+	/// Slates waiting to be delivered over grinbox or keybase
+	pub fn outbox(&self) -> Result<Vec<OutboxEntry>, Error> {
+		self.open_and_close(|c| {
+			let w = c.backend()?;
+			let mut entries: Vec<OutboxEntry> = w.outbox()?.collect();
+			entries.sort_by_key(|e| e.creation_ts);
+			Ok(entries)
+		})
+	}
+
+	/// Try to deliver queued slates through the running listeners. Unless `force`
+	/// is set, only entries whose backoff has passed are attempted.
+	/// Returns the number of delivered and failed slates
+	pub fn retry_outbox(
+		&self,
+		slate_id: Option<Uuid>,
+		force: bool,
+	) -> Result<(usize, usize), Error> {
+		self.deliver_outbox(
+			|e| slate_id.map(|id| id == e.slate_id).unwrap_or(true) && (force || e.is_due()),
+			|e| {
+				let c = self.container.lock();
+				c.listener(method_interface(&e.method))?.publish_from(
+					&e.slate,
+					&e.dest,
+					e.address_index,
+				)
+			},
+		)
+	}
+
+	/// Try to deliver the queued slates selected by `filter` with `post`,
+	/// dropping the ones of cancelled transactions. The slates are posted
+	/// without holding the wallet, so slow transports don't block other calls
+	pub fn deliver_outbox<F, P>(&self, select: F, post: P) -> Result<(usize, usize), Error>
+	where
+		F: Fn(&OutboxEntry) -> bool,
+		P: Fn(&OutboxEntry) -> Result<(), Error>,
+	{
+		let entries = self.open_and_close(|c| {
+			let w = c.backend()?;
+			let cancelled: Vec<Uuid> = w
+				.tx_logs()?
+				.filter(|t| t.tx_type == TxLogEntryType::TxSentCancelled)
+				.filter_map(|t| t.tx_slate_id)
+				.collect();
+			let (dropped, entries): (Vec<OutboxEntry>, Vec<OutboxEntry>) = w
+				.outbox()?
+				.filter(|e| select(e))
+				.partition(|e| cancelled.contains(&e.slate_id));
+
+			let mut batch = w.batch()?;
+			for entry in dropped {
+				batch.delete_outbox_entry(&entry.slate_id)?;
+			}
+			batch.commit()?;
+			Ok(entries)
+		})?;
+
+		let results: Vec<(OutboxEntry, Result<(), Error>)> = entries
+			.into_iter()
+			.map(|entry| {
+				let result = post(&entry);
+				(entry, result)
+			})
+			.collect();
+
+		self.open_and_close(|c| {
+			let w = c.backend()?;
+			let queued: Vec<Uuid> = w.outbox()?.map(|e| e.slate_id).collect();
+			let mut delivered = 0;
+			let mut failed = 0;
+			let mut batch = w.batch()?;
+			for (mut entry, result) in results {
+				match result {
+					Ok(_) => {
+						batch.delete_outbox_entry(&entry.slate_id)?;
+						delivered += 1;
+						cli_message!(
+							"Slate {} delivered from the outbox to {}",
+							entry.slate_id.to_string().bright_green(),
+							entry.dest.bright_green()
+						);
+					}
+					Err(e) => {
+						debug!("Unable to deliver slate {}: {}", entry.slate_id, e);
+						// Don't bring back an entry that was dropped in the meantime
+						if queued.contains(&entry.slate_id) {
+							entry.failed(e.to_string());
+							batch.save_outbox_entry(&entry)?;
+						}
+						failed += 1;
+					}
+				}
+			}
+			batch.commit()?;
+			Ok((delivered, failed))
+		})
+	}
+
+	/// Remove a slate from the outbox without delivering it
+	pub fn drop_outbox_entry(&self, slate_id: &Uuid) -> Result<(), Error> {
+		self.open_and_close(|c| {
+			let w = c.backend()?;
+			if !w.outbox()?.any(|e| e.slate_id == *slate_id) {
+				return Err(ErrorKind::OutboxEntryNotFound(slate_id.to_string()).into());
+			}
+			let mut batch = w.batch()?;
+			batch.delete_outbox_entry(slate_id)?;
+			batch.commit()?;
+			Ok(())
+		})
+	}
+
+	/// Hold a received slate until it is accepted or rejected
+	pub fn queue_incoming(&self, entry: &IncomingSlate) -> Result<(), Error> {
+		self.open_and_close(|c| {
+			let w = c.backend()?;
+			let mut batch = w.batch()?;
+			batch.save_incoming_slate(entry)?;
+			batch.commit()?;
+			Ok(())
+		})
+	}
+
+	/// Received slates waiting for approval, with the name of the sender
+	/// if it is in the address book
+	pub fn incoming(&self) -> Result<Vec<(IncomingSlate, Option<String>)>, Error> {
+		self.open_and_close(|c| {
+			let mut entries: Vec<IncomingSlate> = c.backend()?.incoming()?.collect();
+			entries.sort_by_key(|e| e.received_ts);
+			let mut incoming = Vec::with_capacity(entries.len());
+			for e in entries {
+				let address = match e.method.as_str() {
+					"keybase" => KeybaseAddress::from_str(&e.from)
+						.map(|a| format!("keybase://{}", a.username)),
+					_ => GrinboxAddress::from_str(&e.from).map(|a| a.to_string()),
+				};
+				let contact = match address {
+					Ok(a) => c.address_book.get_contact_by_address(&a)?.map(|c| c.name),
+					Err(_) => None,
+				};
+				incoming.push((e, contact));
+			}
+			Ok(incoming)
+		})
+	}
+
+	pub fn incoming_slate(&self, slate_id: &Uuid) -> Result<IncomingSlate, Error> {
+		self.open_and_close(|c| {
+			c.backend()?
+				.incoming()?
+				.find(|e| e.slate_id == *slate_id)
+				.ok_or_else(|| ErrorKind::IncomingSlateNotFound(slate_id.to_string()).into())
+		})
+	}
+
+	/// Send the answer to a received slate back to the sender and remove it from
+	/// the approval queue. An answer that can't be delivered right now is queued
+	/// in the outbox. Returns whether it was delivered
+	pub fn answer_incoming(&self, entry: &IncomingSlate, slate: Slate) -> Result<bool, Error> {
+		let mut event = WalletEvent::new(WalletEventType::ResponseSent);
+		event.slate_id = Some(slate.id);
+		event.amount = Some(slate.amount);
+		event.address = Some(entry.from.clone());
+		let rejected = slate.rejection.is_some();
+
+		let vslate = VersionedSlate::into_version(slate, entry.slate.version());
+		// Publish without holding the wallet, like the outbox does
+		let result = {
+			let c = self.container.lock();
+			c.listener(method_interface(&entry.method))
+				.map_err(Error::from)
+				.and_then(|l| l.publish_from(&vslate, &entry.from, entry.address_index))
+		};
+
+		let sent = self.open_and_close(|c| {
+			let w = c.backend()?;
+			let mut batch = w.batch()?;
+			batch.delete_incoming_slate(&entry.slate_id)?;
+			if let Err(e) = &result {
+				let mut outbox = OutboxEntry::new(
+					entry.slate_id,
+					entry.method.clone(),
+					entry.from.clone(),
+					vslate,
+				);
+				outbox.address_index = entry.address_index;
+				outbox.failed(e.to_string());
+				batch.save_outbox_entry(&outbox)?;
+			}
+			batch.commit()?;
+			Ok(result.is_ok())
+		})?;
+		if sent && !rejected {
+			self.notify(event);
+		}
+		Ok(sent)
+	}
+
+	/// Send a received slate back unsigned, telling the sender it was rejected
+	pub fn reject_incoming(
+		&self,
+		entry: &IncomingSlate,
+		reason: Option<&str>,
+	) -> Result<bool, Error> {
+		let mut slate: Slate = entry.slate.clone().into();
+		slate.rejection = Some(reason.unwrap_or("rejected by the receiver").to_owned());
+		self.answer_incoming(entry, slate)
+	}
+
+	/// Create a payment request with a grinbox address of its own
+	pub fn create_payment_request(
+		&self,
+		amount: u64,
+		memo: Option<String>,
+		expiry_minutes: Option<u64>,
+	) -> Result<PaymentRequest, Error> {
+		let request = self.open_and_close(|c| {
+			let domain = c.config.grinbox_domain.clone();
+			let port = c.config.grinbox_port;
+			let w = c.backend()?;
+			let index = w
+				.payment_requests()?
+				.map(|r| r.address_index + 1)
+				.max()
+				.unwrap_or(PAYMENT_REQUEST_FIRST_INDEX);
+			let keychain = w.keychain();
+			let sec_key = derive_address_key(keychain, index)?;
+			let pub_key = PublicKey::from_secret_key(keychain.secp(), &sec_key)?;
+			let address = GrinboxAddress::new(pub_key, Some(domain), port);
+			let expiry_ts = expiry_minutes.map(|m| Utc::now() + Duration::minutes(m as i64));
+			let request = PaymentRequest::new(index, address.stripped(), amount, memo, expiry_ts);
+
+			let mut batch = w.batch()?;
+			batch.save_payment_request(&request)?;
+			batch.commit()?;
+			Ok(request)
+		})?;
+
+		self.restart_grinbox()?;
+		Ok(request)
+	}
+
+	/// All payment requests, oldest first
+	pub fn payment_requests(&self) -> Result<Vec<PaymentRequest>, Error> {
+		self.open_and_close(|c| Ok(c.backend()?.payment_requests()?.collect()))
+	}
+
+	pub fn payment_request(&self, address_index: u32) -> Result<PaymentRequest, Error> {
+		self.payment_requests()?
+			.into_iter()
+			.find(|r| r.address_index == address_index)
+			.ok_or(ErrorKind::PaymentRequestNotFound(address_index).into())
+	}
+
+	/// Stop waiting for the payment of an open request
+	pub fn cancel_payment_request(&self, address_index: u32) -> Result<(), Error> {
+		let mut request = self.payment_request(address_index)?;
+		if request.status != PaymentRequestStatus::Open {
+			return Err(ErrorKind::GenericError(format!(
+				"Payment request {} is {}",
+				address_index, request.status
+			))
+			.into());
+		}
+		request.status = PaymentRequestStatus::Cancelled;
+		self.save_payment_request(&request)?;
+		self.restart_grinbox()
+	}
+
+	/// Link a slate received on the address of a payment request to the request
+	pub fn match_payment_request(&self, address_index: u32, slate: &Slate) -> Result<(), Error> {
+		if address_index < PAYMENT_REQUEST_FIRST_INDEX {
+			return Ok(());
+		}
+		let mut request = self.payment_request(address_index)?;
+		if request.status != PaymentRequestStatus::Open {
+			cli_message!(
+				"{} slate {} was sent to payment request {}, which is {}",
+				"WARNING:".bright_yellow(),
+				slate.id.to_string().bright_green(),
+				address_index,
+				request.status
+			);
+			return Ok(());
+		}
+		if slate.amount < request.amount {
+			cli_message!(
+				"{} slate {} pays {} grin of the {} grin of payment request {}",
+				"WARNING:".bright_yellow(),
+				slate.id.to_string().bright_green(),
+				amount_to_hr_string(slate.amount, false),
+				amount_to_hr_string(request.amount, false),
+				address_index
+			);
+			return Ok(());
+		}
+		request.status = PaymentRequestStatus::Received;
+		request.slate_id = Some(slate.id);
+		request.amount_received = slate.amount;
+		self.save_payment_request(&request)?;
+		cli_message!(
+			"Payment for request {} received, waiting for confirmation",
+			address_index.to_string().bright_green()
+		);
+		Ok(())
+	}
+
+	/// Expire open requests and mark received ones paid once their transaction confirms.
+	/// The grinbox listener stops listening on the addresses of expired requests
+	pub fn update_payment_requests(&self) -> Result<(), Error> {
+		let requests = self.payment_requests()?;
+		let mut expired = false;
+		for mut request in requests {
+			if request.is_expired() {
+				request.status = PaymentRequestStatus::Expired;
+				self.save_payment_request(&request)?;
+				expired = true;
+				continue;
+			}
+			if request.status != PaymentRequestStatus::Received {
+				continue;
+			}
+			let tx = match self.retrieve_tx(None, request.slate_id) {
+				Ok(tx) => tx,
+				Err(_) => continue,
+			};
+			if tx.tx_type == TxLogEntryType::TxReceivedCancelled {
+				// The payment was cancelled, wait for another one
+				request.status = PaymentRequestStatus::Open;
+				request.slate_id = None;
+				request.amount_received = 0;
+				self.save_payment_request(&request)?;
+			} else if tx.confirmed {
+				request.status = PaymentRequestStatus::Paid;
+				self.save_payment_request(&request)?;
+				cli_message!(
+					"Payment request {} was paid",
+					request.address_index.to_string().bright_green()
+				);
+			}
+		}
+		if expired {
+			self.restart_grinbox()?;
+		}
+		Ok(())
+	}
+
+	fn save_payment_request(&self, request: &PaymentRequest) -> Result<(), Error> {
+		self.open_and_close(|c| {
+			let mut batch = c.backend()?.batch()?;
+			batch.save_payment_request(request)?;
+			batch.commit()?;
+			Ok(())
+		})
+	}
+
+	/// Restart the grinbox listener, if it runs, to subscribe to the current set of addresses
+	fn restart_grinbox(&self) -> Result<(), Error> {
+		if self.stop_listener(ListenerInterface::Grinbox)? {
+			self.start_listener(ListenerInterface::Grinbox)?;
+		}
+		Ok(())
+	}
+
+	/// Push an event to the open event streams, then queue it for the webhooks
+	/// and try to post it right away
+	pub fn notify(&self, event: WalletEvent) {
+		self.notify_all(vec![event]);
+	}
+
+	/// Push events to the open event streams, then queue them for the webhooks
+	fn notify_all(&self, events: Vec<WalletEvent>) {
+		if events.is_empty() {
+			return;
+		}
+		self.container
+			.lock()
+			.event_subscribers
+			.retain(|s| events.iter().all(|e| s.unbounded_send(e.clone()).is_ok()));
+		self.queue_webhooks(events);
+	}
+
+	/// Queue events for the webhooks and try to post them right away.
+	/// Nothing is queued when no webhooks are configured
+	fn queue_webhooks(&self, events: Vec<WalletEvent>) {
+		if events.is_empty() || self.config().webhooks().is_empty() {
+			return;
+		}
+
+		let result = self.open_and_close(|c| {
+			let mut batch = c.backend()?.batch()?;
+			for event in events {
+				batch.save_webhook_entry(&WebhookEntry::new(event))?;
+			}
+			batch.commit()?;
+			Ok(())
+		});
+		match result {
+			Ok(_) => {
+				let owner = Owner::new(self.container.clone());
+				spawn(move || {
+					if let Err(e) = owner.deliver_webhooks(false) {
+						debug!("Unable to post wallet events: {}", e);
+					}
+				});
+			}
+			Err(e) => debug!("Unable to queue wallet event: {}", e),
+		}
+	}
+
+	/// Stream of the wallet events from now on
+	pub fn subscribe_events(&self) -> UnboundedReceiver<WalletEvent> {
+		let (sender, receiver) = unbounded();
+		self.container.lock().event_subscribers.push(sender);
+		receiver
+	}
+
+	/// Whether anything consumes wallet events
+	pub fn has_event_consumers(&self) -> bool {
+		let c = self.container.lock();
+		!c.event_subscribers.is_empty() || !c.config.webhooks().is_empty()
+	}
+
+	/// Wallet events waiting to be posted to webhooks, oldest first
+	pub fn webhook_queue(&self) -> Result<Vec<WebhookEntry>, Error> {
+		self.open_and_close(|c| {
+			let mut entries: Vec<WebhookEntry> = c.backend()?.webhook_queue()?.collect();
+			entries.sort_by_key(|e| e.event.creation_ts);
+			Ok(entries)
+		})
+	}
+
+	/// Post queued events to the webhooks that didn't accept them yet. Unless
+	/// `force` is set, only entries whose backoff has passed are attempted.
+	/// Returns the number of delivered and failed events
+	pub fn deliver_webhooks(&self, force: bool) -> Result<(usize, usize), Error> {
+		// Event threads and the monitor both deliver, one run at a time so
+		// an entry isn't posted twice or saved again after it was deleted
+		let delivery = self.container.lock().webhook_delivery.clone();
+		let _delivery = delivery.lock();
+
+		let webhooks = self.config().webhooks();
+		let entries = self.webhook_queue()?;
+		let mut delivered = vec![];
+		let mut failed = vec![];
+		for mut entry in entries {
+			if !force && !entry.is_due() {
+				continue;
+			}
+			let event = entry.event.event.to_string();
+			let mut error = None;
+			for webhook in webhooks
+				.iter()
+				.filter(|w| w.wants(&event) && !entry.delivered.contains(&w.url))
+			{
+				match webhook::post(webhook, &entry.event) {
+					Ok(_) => entry.delivered.push(webhook.url.clone()),
+					Err(e) => {
+						debug!("Unable to post event {} to {}: {}", event, webhook.url, e);
+						error = Some(format!("{}: {}", webhook.url, e));
+					}
+				}
+			}
+			match error {
+				None => delivered.push(entry),
+				Some(e) => {
+					entry.failed(e);
+					failed.push(entry);
+				}
+			}
+		}
+
+		self.open_and_close(|c| {
+			let mut batch = c.backend()?.batch()?;
+			for entry in &delivered {
+				batch.delete_webhook_entry(&entry.event.id)?;
+			}
+			for entry in &failed {
+				if entry.is_exhausted() {
+					warn!(
+						"Dropped event {} after {} failed attempts",
+						entry.event.id, entry.attempts
+					);
+					batch.delete_webhook_entry(&entry.event.id)?;
+				} else {
+					batch.save_webhook_entry(entry)?;
+				}
+			}
+			batch.commit()?;
+			Ok(())
+		})?;
+		Ok((delivered.len(), failed.len()))
+	}
+
+	/// Post a test event to every webhook, returning the error of each one that failed
+	pub fn test_webhooks(&self) -> Result<Vec<(String, Option<String>)>, Error> {
+		let webhooks = self.config().webhooks();
+		if webhooks.is_empty() {
+			return Err(ErrorKind::GenericError("No webhooks are configured".to_owned()).into());
+		}
+		let event = WalletEvent::new(WalletEventType::Test);
+		Ok(webhooks
+			.iter()
+			.map(|w| {
+				let error = webhook::post(w, &event).err().map(|e| e.to_string());
+				(w.url.clone(), error)
+			})
+			.collect())
+	}
+
+	/// Fail if the time-to-live of the slate ran out
+	pub fn check_ttl(&self, slate: &Slate, ttl_cutoff_height: Option<u64>) -> Result<(), Error> {
+		if let Some(cutoff) = ttl_cutoff_height {
+			if self.node_height()?.height >= cutoff {
+				return Err(ErrorKind::SlateExpired(slate.id.to_string()).into());
+			}
+		}
+		Ok(())
+	}
+
 	pub fn get_stored_tx(&self, slate_id: &Uuid) -> Result<Option<Transaction>, Error> {
 		let mut c = self.container.lock();
 		let w = c.backend()?;
@@ -548,6 +1414,41 @@ where
 		slate.verify_messages()
 	}
 
+	/// Armor a slate for copy-paste, optionally encrypted to a grinbox address
+	pub fn armor_slate(
+		&self,
+		slate: &Slate,
+		encrypt_to: Option<&GrinboxAddress>,
+	) -> Result<String, Error> {
+		match encrypt_to {
+			Some(address) => {
+				let public_key = address.public_key()?;
+				let sec_key = self.grinbox_key()?;
+				armor::armor(slate, Some((&public_key, &sec_key)))
+			}
+			None => armor::armor(slate, None),
+		}
+	}
+
+	/// Read an armored slate, decrypting it with the key of our grinbox address.
+	/// Returns the address of the sender for encrypted slates
+	pub fn dearmor_slate(&self, armored: &str) -> Result<(Slate, Option<GrinboxAddress>), Error> {
+		let sec_key = self.grinbox_key()?;
+		let (slate, sender) = armor::dearmor(armored, Some(&sec_key))?;
+		let domain = self.container.lock().config.grinbox_domain.clone();
+		Ok((
+			slate,
+			sender.map(|public_key| GrinboxAddress::new(public_key, Some(domain), None)),
+		))
+	}
+
+	fn grinbox_key(&self) -> Result<SecretKey, Error> {
+		self.open_and_close(|c| {
+			let index = c.config.grinbox_address_index();
+			derive_address_key(c.backend()?.keychain(), index)
+		})
+	}
+
 	pub fn get_stored_tx_proof(
 		&self,
 		tx_id: Option<u32>,
@@ -581,12 +1482,20 @@ where
 		tx::verify_tx_proof(tx_proof)
 	}
 
-	pub fn restore(&self) -> Result<(), Error> {
+	/// Height at which a kernel was included on chain and its number of confirmations
+	pub fn kernel_confirmations(&self, excess: &Commitment) -> Result<Option<(u64, u64)>, Error> {
+		self.open_and_close(|c| {
+			let w = c.backend()?;
+			updater::kernel_confirmations(w, excess)
+		})
+	}
+
+	pub fn restore(&self, start: ScanStart) -> Result<(), Error> {
 		let grinbox = self.stop_listener(ListenerInterface::Grinbox)?;
 
 		self.open_and_close(|c| {
 			let w = c.backend()?;
-			w.restore()
+			w.restore(&start, &ScanMonitor::global())
 		})?;
 
 		if grinbox {
@@ -596,12 +1505,116 @@ where
 		Ok(())
 	}
 
-	pub fn check_repair(&self, delete_unconfirmed: bool) -> Result<(), Error> {
+	pub fn check_repair(&self, delete_unconfirmed: bool, start: ScanStart) -> Result<(), Error> {
 		self.open_and_close(|c| {
 			let w = c.backend()?;
 			let parent_key_id = w.get_parent_key_id();
 			updater::refresh_outputs(w, &parent_key_id, true)?;
-			w.check_repair(delete_unconfirmed)
+			w.check_repair(delete_unconfirmed, &start, &ScanMonitor::global())
+		})
+	}
+
+	/// Progress of the running (or last) restore or check.
+	/// Doesn't need the wallet lock, so it can be polled while a scan runs
+	pub fn scan_progress(&self) -> ScanProgress {
+		ScanMonitor::global().progress()
+	}
+
+	/// Stop the running restore or check after its current batch.
+	/// The next restore or check resumes from there
+	pub fn cancel_scan(&self) -> bool {
+		ScanMonitor::global().cancel()
+	}
+
+	/// Commitments of all outputs an offline wallet needs the chain state of
+	pub fn snapshot_request(&self) -> Result<Vec<String>, Error> {
+		self.open_and_close(|c| {
+			let w = c.backend()?;
+			let accounts: Vec<AcctPathMapping> = w.accounts()?.collect();
+			let mut commits = Vec::new();
+			for account in accounts {
+				for (commit, _) in updater::map_wallet_outputs(w, &account.path, true)? {
+					commits.push(to_hex(commit.0.to_vec()));
+				}
+			}
+			Ok(commits)
+		})
+	}
+
+	/// Look up the outputs of a snapshot request on the node
+	pub fn export_snapshot(&self, requested: Vec<String>) -> Result<OutputSnapshot, Error> {
+		self.open_and_close(|c| {
+			let w = c.backend()?;
+			let mut commits = Vec::with_capacity(requested.len());
+			for commit in &requested {
+				let bytes = from_hex(commit.clone())
+					.map_err(|_| ErrorKind::GenericError(format!("Invalid commit {}", commit)))?;
+				commits.push(Commitment::from_vec(bytes));
+			}
+
+			let client = w.w2n_client();
+			let node_version = client.get_version_info();
+			let height = client.get_chain_height()?;
+			let outputs = client
+				.get_outputs_from_node(commits)?
+				.into_iter()
+				.map(|(_, (commit, height, mmr_index))| SnapshotOutput {
+					commit,
+					height,
+					mmr_index,
+				})
+				.collect();
+
+			Ok(OutputSnapshot {
+				height,
+				node_version,
+				created: Utc::now(),
+				requested,
+				outputs,
+			})
+		})
+	}
+
+	/// Store a snapshot for an offline wallet and update the outputs with it.
+	/// The snapshot has to cover all confirmed outputs, otherwise they would
+	/// be considered spent
+	pub fn import_snapshot(&self, snapshot: &OutputSnapshot) -> Result<(), Error> {
+		self.open_and_close(|c| {
+			let data_path = c.config.as_wallet_config()?.data_file_dir;
+			let w = c.backend()?;
+			let confirmed: Vec<_> = w
+				.outputs()?
+				.filter(|o| o.status == OutputStatus::Unspent || o.status == OutputStatus::Locked)
+				.collect();
+			let mut missing = 0;
+			for o in confirmed {
+				let commit = match o.commit {
+					Some(c) => c,
+					None => {
+						let commit = w.keychain().commit(
+							o.value,
+							&o.key_id,
+							&SwitchCommitmentType::Regular,
+						)?;
+						to_hex(commit.0.to_vec())
+					}
+				};
+				if !snapshot.requested.contains(&commit) {
+					missing += 1;
+				}
+			}
+			if missing > 0 {
+				return Err(ErrorKind::GenericError(format!(
+					"Snapshot doesn't include {} outputs of this wallet, please create a new request",
+					missing
+				))
+				.into());
+			}
+
+			snapshot.to_file(&OutputSnapshot::path(Path::new(&data_path)))?;
+			let parent_key_id = w.get_parent_key_id();
+			updater::refresh_outputs(w, &parent_key_id, true)?;
+			Ok(())
 		})
 	}
 
@@ -649,6 +1662,10 @@ where
 				let _ = w.unwrap().close();
 			}
 		}
+		// Events of the updater, published once the container is unlocked
+		let events = c.raw_backend().take_events();
+		drop(c);
+		self.notify_all(events);
 		res
 	}
 }
@@ -665,3 +1682,40 @@ where
 		}
 	}
 }
+
+/// Whether sending failed before the peer got the slate
+fn is_unreachable(e: &Error) -> bool {
+	match e.downcast_ref::<ErrorKind>() {
+		Some(ErrorKind::PeerUnreachable(_)) => true,
+		_ => false,
+	}
+}
+
+/// Parse addresses given in order of preference, at least one is needed
+fn parse_addresses(addresses: Vec<&str>) -> Result<Vec<Box<dyn Address>>, Error> {
+	if addresses.is_empty() {
+		return Err(ErrorKind::GenericError("No address given".to_owned()))?;
+	}
+	let mut parsed = Vec::new();
+	for address in addresses {
+		parsed.push(parse_address(address)?);
+	}
+	Ok(parsed)
+}
+
+/// Method used to send slates to an address
+fn address_method(address: &dyn Address) -> &'static str {
+	match address.address_type() {
+		AddressType::Http => "http",
+		AddressType::Grinbox => "grinbox",
+		AddressType::Keybase => "keybase",
+	}
+}
+
+/// Listener that delivers slates sent with this method
+fn method_interface(method: &str) -> ListenerInterface {
+	match method {
+		"keybase" => ListenerInterface::Keybase,
+		_ => ListenerInterface::Grinbox,
+	}
+}
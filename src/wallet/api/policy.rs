@@ -0,0 +1,312 @@
+// Copyright 2019 The vault713 Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Evaluation of the `receive_policy` rules against received slates
+
+use crate::common::config::ReceivePolicy;
+use crate::common::Keychain;
+use crate::contacts::{parse_address, Address, AddressBook};
+use crate::wallet::types::{NodeClient, Slate, TxLogEntryType, WalletBackend};
+use crate::wallet::ErrorKind;
+use chrono::{DateTime, Duration, Utc};
+use failure::Error;
+use grin_core::core::amount_to_hr_string;
+use log::warn;
+use regex::Regex;
+use std::collections::HashMap;
+
+/// Check the sender against the allow and deny lists
+pub fn check_sender(
+	policy: &ReceivePolicy,
+	address_book: &AddressBook,
+	slate: &Slate,
+	from: Option<&dyn Address>,
+) -> Result<(), Error> {
+	let sender = from
+		.map(|a| a.stripped())
+		.unwrap_or("unknown sender".to_owned());
+	if let Some(deny) = &policy.deny {
+		if deny.iter().any(|e| matches(address_book, e, from)) {
+			return reject(slate, format!("{} is on the deny list", sender));
+		}
+	}
+	if let Some(allow) = &policy.allow {
+		if !allow.iter().any(|e| matches(address_book, e, from)) {
+			return reject(slate, format!("{} is not on the allow list", sender));
+		}
+	}
+	Ok(())
+}
+
+/// Check the amount and the sender's message
+pub fn check_slate(policy: &ReceivePolicy, slate: &Slate) -> Result<(), Error> {
+	if let Some(min) = policy.min_amount {
+		if slate.amount < min {
+			return reject(
+				slate,
+				format!("amount is below {} grin", amount_to_hr_string(min, false)),
+			);
+		}
+	}
+	if let Some(max) = policy.max_amount {
+		if slate.amount > max {
+			return reject(
+				slate,
+				format!("amount is above {} grin", amount_to_hr_string(max, false)),
+			);
+		}
+	}
+	if let Some(pattern) = &policy.message_pattern {
+		let re = match Regex::new(pattern) {
+			Ok(re) => re,
+			Err(e) => return reject(slate, format!("invalid message_pattern: {}", e)),
+		};
+		let message = slate
+			.participant_data
+			.iter()
+			.find(|p| p.id == 0)
+			.and_then(|p| p.message.clone())
+			.unwrap_or_default();
+		if !re.is_match(&message) {
+			return reject(slate, format!("message \"{}\" isn't allowed", message));
+		}
+	}
+	Ok(())
+}
+
+/// Record a new slate from the sender, unless it already used up its hourly limit
+pub fn check_rate(
+	policy: &ReceivePolicy,
+	history: &mut HashMap<String, Vec<DateTime<Utc>>>,
+	slate: &Slate,
+	from: &dyn Address,
+) -> Result<(), Error> {
+	let limit = match policy.max_slates_per_hour {
+		Some(l) => l as usize,
+		None => return Ok(()),
+	};
+	let now = Utc::now();
+	let cutoff = now - Duration::hours(1);
+	// Forget senders that didn't send anything in the last hour
+	for times in history.values_mut() {
+		times.retain(|t| *t > cutoff);
+	}
+	history.retain(|_, times| !times.is_empty());
+	let times = history.entry(from.to_string()).or_insert_with(Vec::new);
+	if times.len() >= limit {
+		return reject(
+			slate,
+			format!(
+				"{} already sent {} slates in the last hour",
+				from.stripped(),
+				limit
+			),
+		);
+	}
+	times.push(now);
+	Ok(())
+}
+
+/// Check the number of outputs of received transactions that aren't confirmed yet
+pub fn check_pending<W, C, K>(
+	policy: &ReceivePolicy,
+	wallet: &mut W,
+	slate: &Slate,
+) -> Result<(), Error>
+where
+	W: WalletBackend<C, K>,
+	C: NodeClient,
+	K: Keychain,
+{
+	let max = match policy.max_pending_outputs {
+		Some(m) => m as usize,
+		None => return Ok(()),
+	};
+	let pending: usize = wallet
+		.tx_logs()?
+		.filter(|t| t.tx_type == TxLogEntryType::TxReceived && !t.confirmed)
+		.map(|t| t.num_outputs)
+		.sum();
+	if pending >= max {
+		return reject(
+			slate,
+			format!("{} received outputs are still unconfirmed", pending),
+		);
+	}
+	Ok(())
+}
+
+/// Whether an allow or deny list entry, an address or `@contact`, refers to the sender
+fn matches(address_book: &AddressBook, entry: &str, from: Option<&dyn Address>) -> bool {
+	let from = match from {
+		Some(f) => f,
+		None => return false,
+	};
+	let addresses = if entry.starts_with('@') {
+		match address_book.get_contact(&entry[1..]) {
+			Ok(Some(contact)) => contact.addresses(),
+			_ => return false,
+		}
+	} else {
+		vec![entry.to_owned()]
+	};
+	addresses
+		.iter()
+		.any(|address| match parse_address(address) {
+			Ok(a) => a.address_type() == from.address_type() && a.stripped() == from.stripped(),
+			Err(_) => false,
+		})
+}
+
+fn reject(slate: &Slate, reason: String) -> Result<(), Error> {
+	warn!("Rejected slate {}: {}", slate.id, reason);
+	Err(ErrorKind::SlateRejected(slate.id.to_string(), reason).into())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::contacts::{Backend, Contact, KeybaseAddress};
+	use std::env;
+	use std::fs::remove_dir_all;
+	use std::path::PathBuf;
+	use uuid::Uuid;
+
+	struct TestAddressBook {
+		dir: PathBuf,
+		address_book: AddressBook,
+	}
+
+	impl TestAddressBook {
+		fn new() -> Self {
+			let dir = env::temp_dir().join(format!("wallet713-test-{}", Uuid::new_v4()));
+			let backend = Backend::new(dir.to_str().unwrap()).unwrap();
+			let mut address_book = AddressBook::new(Box::new(backend)).unwrap();
+			let alice = KeybaseAddress::from_str("keybase://alice").unwrap();
+			let mut contact = Contact::new("alice", Box::new(alice)).unwrap();
+			contact.set_addresses(vec![
+				Box::new(KeybaseAddress::from_str("keybase://alice").unwrap()),
+				Box::new(KeybaseAddress::from_str("keybase://alice_backup").unwrap()),
+			]);
+			address_book.add_contact(&contact).unwrap();
+			Self { dir, address_book }
+		}
+	}
+
+	impl Drop for TestAddressBook {
+		fn drop(&mut self) {
+			let _ = remove_dir_all(&self.dir);
+		}
+	}
+
+	fn keybase(username: &str) -> KeybaseAddress {
+		KeybaseAddress::from_str(&format!("keybase://{}", username)).unwrap()
+	}
+
+	fn rejected(result: Result<(), Error>) -> bool {
+		match result {
+			Err(e) => match e.downcast_ref::<ErrorKind>() {
+				Some(ErrorKind::SlateRejected(_, _)) => true,
+				_ => false,
+			},
+			Ok(_) => false,
+		}
+	}
+
+	#[test]
+	fn slate_amount_and_message() {
+		let mut policy = ReceivePolicy::default();
+		let mut slate = Slate::blank(2);
+		slate.amount = 5_000_000_000;
+		assert!(check_slate(&policy, &slate).is_ok());
+
+		policy.min_amount = Some(1_000_000_000);
+		policy.max_amount = Some(10_000_000_000);
+		assert!(check_slate(&policy, &slate).is_ok());
+		slate.amount = 999_999_999;
+		assert!(rejected(check_slate(&policy, &slate)));
+		slate.amount = 10_000_000_001;
+		assert!(rejected(check_slate(&policy, &slate)));
+
+		// Without a message only a pattern matching the empty string passes
+		slate.amount = 5_000_000_000;
+		policy.message_pattern = Some(String::from("^invoice-[0-9]+$"));
+		assert!(rejected(check_slate(&policy, &slate)));
+		policy.message_pattern = Some(String::from("("));
+		assert!(rejected(check_slate(&policy, &slate)));
+	}
+
+	#[test]
+	fn rate_per_sender() {
+		let mut policy = ReceivePolicy::default();
+		let mut history = HashMap::new();
+		let slate = Slate::blank(2);
+		let alice = keybase("alice");
+		let bob = keybase("bob");
+
+		// No limit, nothing is recorded
+		assert!(check_rate(&policy, &mut history, &slate, &alice).is_ok());
+		assert!(history.is_empty());
+
+		policy.max_slates_per_hour = Some(2);
+		assert!(check_rate(&policy, &mut history, &slate, &alice).is_ok());
+		assert!(check_rate(&policy, &mut history, &slate, &alice).is_ok());
+		assert!(rejected(check_rate(&policy, &mut history, &slate, &alice)));
+		assert!(check_rate(&policy, &mut history, &slate, &bob).is_ok());
+
+		// Senders whose window is empty are forgotten
+		let old = Utc::now() - Duration::hours(2);
+		history.insert(alice.to_string(), vec![old, old]);
+		assert!(check_rate(&policy, &mut history, &slate, &bob).is_ok());
+		assert!(!history.contains_key(&alice.to_string()));
+		assert!(check_rate(&policy, &mut history, &slate, &alice).is_ok());
+	}
+
+	#[test]
+	fn allow_and_deny_lists() {
+		let book = TestAddressBook::new();
+		let mut policy = ReceivePolicy::default();
+		let slate = Slate::blank(2);
+		let alice = keybase("alice_backup");
+		let bob = keybase("bob");
+		assert!(check_sender(&policy, &book.address_book, &slate, Some(&alice)).is_ok());
+		assert!(check_sender(&policy, &book.address_book, &slate, None).is_ok());
+
+		// Contacts match any of their addresses
+		policy.allow = Some(vec![String::from("@alice")]);
+		assert!(check_sender(&policy, &book.address_book, &slate, Some(&alice)).is_ok());
+		assert!(rejected(check_sender(
+			&policy,
+			&book.address_book,
+			&slate,
+			Some(&bob)
+		)));
+		assert!(rejected(check_sender(
+			&policy,
+			&book.address_book,
+			&slate,
+			None
+		)));
+
+		policy.allow = None;
+		policy.deny = Some(vec![String::from("keybase://bob"), String::from("@nobody")]);
+		assert!(check_sender(&policy, &book.address_book, &slate, Some(&alice)).is_ok());
+		assert!(rejected(check_sender(
+			&policy,
+			&book.address_book,
+			&slate,
+			Some(&bob)
+		)));
+	}
+}
@@ -0,0 +1,209 @@
+// Copyright 2019 The vault713 Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::Owner;
+use crate::cli_message;
+use crate::common::{Arc, Keychain, Mutex};
+use crate::wallet::types::{NodeClient, WalletBackend, WalletEvent, WalletEventType, WalletInfo};
+use crate::wallet::Container;
+use colored::Colorize;
+use failure::Error;
+use log::{debug, warn};
+use std::collections::HashMap;
+use std::thread::{sleep, spawn, JoinHandle};
+use std::time::Duration;
+
+/// How often stuck transactions are checked
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+/// Confirmations of the balance reported in `balance_changed` events, as shown by `info`
+const BALANCE_CONFIRMATIONS: u64 = 10;
+
+/// Background policy for sent transactions that don't confirm, for example
+/// because they were dropped from the mempool. They are reposted every
+/// `tx_repost_blocks` blocks and cancelled after `tx_expire_blocks` blocks,
+/// or as soon as their inputs are spent by another transaction. Sends the
+/// recipient never answered are cancelled once their TTL runs out. Slates
+/// in the outbox are retried once their backoff has passed. Changes of the
+/// balance are reported as wallet events
+pub struct TxMonitor<W, C, K>
+where
+	W: WalletBackend<C, K>,
+	C: NodeClient,
+	K: Keychain,
+{
+	owner: Owner<W, C, K>,
+	/// Height of the last repost of each transaction
+	reposted: HashMap<u32, u64>,
+	/// Wallet summary of the last check
+	balance: Option<WalletInfo>,
+}
+
+impl<W, C, K> TxMonitor<W, C, K>
+where
+	W: WalletBackend<C, K>,
+	C: NodeClient,
+	K: Keychain,
+{
+	pub fn new(container: Arc<Mutex<Container<W, C, K>>>) -> Self {
+		Self {
+			owner: Owner::new(container),
+			reposted: HashMap::new(),
+			balance: None,
+		}
+	}
+
+	/// Check stuck transactions, the outbox, payment requests, the balance and webhooks
+	/// periodically in a background thread
+	pub fn spawn(mut self) -> JoinHandle<()> {
+		spawn(move || loop {
+			sleep(CHECK_INTERVAL);
+			if let Err(e) = self.check() {
+				debug!("Unable to check stuck transactions: {}", e);
+			}
+			if let Err(e) = self.owner.retry_outbox(None, false) {
+				debug!("Unable to retry outbox: {}", e);
+			}
+			if let Err(e) = self.owner.update_payment_requests() {
+				debug!("Unable to update payment requests: {}", e);
+			}
+			if let Err(e) = self.check_balance() {
+				debug!("Unable to check balance: {}", e);
+			}
+			if let Err(e) = self.owner.deliver_webhooks(false) {
+				debug!("Unable to post wallet events: {}", e);
+			}
+		})
+	}
+
+	/// Notify event consumers when the amounts of the wallet summary changed
+	/// since the last check
+	fn check_balance(&mut self) -> Result<(), Error> {
+		if !self.owner.has_event_consumers() {
+			return Ok(());
+		}
+		let (_, info) = self
+			.owner
+			.retrieve_summary_info(false, BALANCE_CONFIRMATIONS)?;
+		let amounts = |i: &WalletInfo| {
+			(
+				i.total,
+				i.amount_awaiting_finalization,
+				i.amount_awaiting_confirmation,
+				i.amount_immature,
+				i.amount_currently_spendable,
+				i.amount_locked,
+			)
+		};
+		if let Some(balance) = &self.balance {
+			if amounts(balance) != amounts(&info) {
+				let mut event = WalletEvent::new(WalletEventType::BalanceChanged);
+				event.amount = Some(info.total);
+				event.balance = Some(info.clone());
+				self.owner.notify(event);
+			}
+		}
+		self.balance = Some(info);
+		Ok(())
+	}
+
+	pub fn check(&mut self) -> Result<(), Error> {
+		let config = self.owner.config();
+		let repost_blocks = config.tx_repost_blocks();
+		let expire_blocks = config.tx_expire_blocks();
+
+		let (height, stuck) = self.owner.stuck_txs()?;
+		self.reposted
+			.retain(|id, _| stuck.iter().any(|(t, _)| t.id == *id));
+
+		for (tx, spent_elsewhere) in stuck {
+			let slate_id = tx
+				.tx_slate_id
+				.map(|id| id.to_string())
+				.unwrap_or(tx.id.to_string());
+
+			let finalized_height = match tx.finalized_height {
+				Some(h) => h,
+				None => {
+					// Not finalized, the recipient hasn't answered yet
+					match tx.ttl_cutoff_height {
+						Some(cutoff) if height >= cutoff => {
+							// Leave it for the next check rather than skip the other transactions
+							if let Err(e) = self.owner.cancel_tx(Some(tx.id), None) {
+								warn!("Unable to cancel transaction {}: {}", slate_id, e);
+								continue;
+							}
+							cli_message!(
+								"{} transaction {} was cancelled, the recipient did not respond before block {}",
+								"WARNING:".bright_yellow(),
+								slate_id.bright_green(),
+								cutoff
+							);
+						}
+						_ => {}
+					}
+					continue;
+				}
+			};
+			let age = height.saturating_sub(finalized_height);
+
+			let reason = if spent_elsewhere {
+				Some("its inputs were spent by another transaction".to_owned())
+			} else if expire_blocks > 0 && age >= expire_blocks {
+				Some(format!(
+					"it did not confirm within {} blocks",
+					expire_blocks
+				))
+			} else {
+				None
+			};
+
+			if let Some(reason) = reason {
+				if let Err(e) = self.owner.cancel_tx(Some(tx.id), None) {
+					warn!("Unable to cancel transaction {}: {}", slate_id, e);
+					continue;
+				}
+				self.reposted.remove(&tx.id);
+				cli_message!(
+					"{} transaction {} was cancelled, {}",
+					"WARNING:".bright_yellow(),
+					slate_id.bright_green(),
+					reason
+				);
+				continue;
+			}
+
+			if repost_blocks == 0 {
+				continue;
+			}
+			let last_post = self
+				.reposted
+				.get(&tx.id)
+				.cloned()
+				.unwrap_or(finalized_height);
+			if height.saturating_sub(last_post) >= repost_blocks {
+				self.reposted.insert(tx.id, height);
+				if let Err(e) = self.owner.repost_tx(Some(tx.id), None, false) {
+					debug!("Unable to repost transaction {}: {}", slate_id, e);
+					continue;
+				}
+				cli_message!(
+					"Transaction {} is not confirmed after {} blocks, reposted it",
+					slate_id.bright_green(),
+					age
+				);
+			}
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,51 @@
+// Copyright 2019 The vault713 Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Posting of wallet events to the configured webhooks
+
+use crate::common::client;
+use crate::common::config::WebhookConfig;
+use crate::wallet::types::WalletEvent;
+use failure::Error;
+use grin_util::to_hex;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Header with the hex encoded HMAC-SHA256 of the body, prefixed with `sha256=`
+pub const SIGNATURE_HEADER: &str = "x-wallet713-signature";
+/// Header with the name of the event
+pub const EVENT_HEADER: &str = "x-wallet713-event";
+
+/// Post an event to a webhook, signed with its secret
+pub fn post(webhook: &WebhookConfig, event: &WalletEvent) -> Result<(), Error> {
+	let body = serde_json::to_string(event)?;
+	let signature = format!("sha256={}", sign(&webhook.secret, &body));
+	client::post_raw(
+		&webhook.url,
+		body,
+		vec![
+			(SIGNATURE_HEADER, signature),
+			(EVENT_HEADER, event.event.to_string()),
+		],
+	)?;
+	Ok(())
+}
+
+fn sign(secret: &str, body: &str) -> String {
+	let mut mac = HmacSha256::new_varkey(secret.as_bytes()).expect("HMAC can take key of any size");
+	mac.input(body.as_bytes());
+	to_hex(mac.result().code().to_vec())
+}
@@ -13,11 +13,12 @@
 // limitations under the License.
 
 use super::types::{
-	AcctPathMapping, ChildNumber, Context, Identifier, NodeClient, OutputData, Result, Transaction,
-	TxLogEntry, TxProof, WalletBackend, WalletBackendBatch, WalletSeed,
+	AcctPathMapping, ChildNumber, Context, Identifier, IncomingSlate, NodeClient, OutboxEntry,
+	OutputData, PaymentRequest, Result, ScanMonitor, ScanStart, Transaction, TxLogEntry, TxProof,
+	WalletBackend, WalletBackendBatch, WalletEvent, WalletSeed, WebhookEntry,
 };
 use crate::common::config::WalletConfig;
-use crate::common::{ErrorKind, Keychain};
+use crate::common::{ErrorKind, Keychain, Mutex};
 use crate::internal::restore;
 use blake2_rfc::blake2b::Blake2b;
 use chrono::Utc;
@@ -33,6 +34,7 @@ use std::fs::{self, File};
 use std::io::{Read, Write};
 use std::ops::Deref;
 use std::path::Path;
+use uuid::Uuid;
 
 pub const DB_DIR: &'static str = "db";
 pub const TX_SAVE_DIR: &'static str = "saved_txs";
@@ -45,6 +47,12 @@ const PRIVATE_TX_CONTEXT_PREFIX: u8 = 'p' as u8;
 const TX_LOG_ENTRY_PREFIX: u8 = 't' as u8;
 const TX_LOG_ID_PREFIX: u8 = 'i' as u8;
 const ACCOUNT_PATH_MAPPING_PREFIX: u8 = 'a' as u8;
+const SCAN_CHECKPOINT_PREFIX: u8 = 's' as u8;
+const LAST_SCANNED_INDEX_PREFIX: u8 = 'l' as u8;
+const OUTBOX_PREFIX: u8 = 'b' as u8;
+const INCOMING_PREFIX: u8 = 'r' as u8;
+const PAYMENT_REQUEST_PREFIX: u8 = 'q' as u8;
+const WEBHOOK_PREFIX: u8 = 'e' as u8;
 
 fn private_ctx_xor_keys<K>(
 	keychain: &K,
@@ -88,6 +96,8 @@ where
 	parent_key_id: Identifier,
 	config: WalletConfig,
 	w2n_client: C,
+	/// Events queued by committed batches
+	events: Mutex<Vec<WalletEvent>>,
 }
 
 impl<C, K> Backend<C, K>
@@ -108,6 +118,7 @@ where
 			parent_key_id: K::derive_key_id(2, 0, 0, 0, 0),
 			config: config.clone(),
 			w2n_client: client,
+			events: Mutex::new(Vec::new()),
 		})
 	}
 
@@ -361,6 +372,37 @@ where
 		))
 	}
 
+	fn outbox<'a>(&'a self) -> Result<Box<dyn Iterator<Item = OutboxEntry> + 'a>> {
+		Ok(Box::new(
+			self.db()?.iter(&[OUTBOX_PREFIX]).unwrap().map(|x| x.1),
+		))
+	}
+
+	fn incoming<'a>(&'a self) -> Result<Box<dyn Iterator<Item = IncomingSlate> + 'a>> {
+		Ok(Box::new(
+			self.db()?.iter(&[INCOMING_PREFIX]).unwrap().map(|x| x.1),
+		))
+	}
+
+	fn payment_requests<'a>(&'a self) -> Result<Box<dyn Iterator<Item = PaymentRequest> + 'a>> {
+		Ok(Box::new(
+			self.db()?
+				.iter(&[PAYMENT_REQUEST_PREFIX])
+				.unwrap()
+				.map(|x| x.1),
+		))
+	}
+
+	fn webhook_queue<'a>(&'a self) -> Result<Box<dyn Iterator<Item = WebhookEntry> + 'a>> {
+		Ok(Box::new(
+			self.db()?.iter(&[WEBHOOK_PREFIX]).unwrap().map(|x| x.1),
+		))
+	}
+
+	fn take_events(&mut self) -> Vec<WalletEvent> {
+		self.events.lock().drain(..).collect()
+	}
+
 	fn get_acct_path(&self, label: &str) -> Result<Option<AcctPathMapping>> {
 		let acct_key = to_key(ACCOUNT_PATH_MAPPING_PREFIX, &mut label.as_bytes().to_vec());
 		let ser = self.db()?.get_ser(&acct_key)?;
@@ -414,6 +456,7 @@ where
 			_store: self,
 			db: RefCell::new(Some(self.db()?.batch()?)),
 			keychain: self.keychain.clone(),
+			events: Vec::new(),
 		}))
 	}
 
@@ -449,13 +492,29 @@ where
 		Ok(last_confirmed_height)
 	}
 
-	fn restore(&mut self) -> Result<()> {
-		restore::restore(self).context(ErrorKind::Restore)?;
+	fn get_scan_checkpoint(&self, scan: &str) -> Result<Option<u64>> {
+		let key = to_key(SCAN_CHECKPOINT_PREFIX, &mut scan.as_bytes().to_vec());
+		self.db()?.get_ser(&key).map_err(|e| e.into())
+	}
+
+	fn get_last_scanned_index(&self) -> Result<Option<u64>> {
+		let key = to_key(LAST_SCANNED_INDEX_PREFIX, &mut vec![]);
+		self.db()?.get_ser(&key).map_err(|e| e.into())
+	}
+
+	fn restore(&mut self, start: &ScanStart, monitor: &ScanMonitor) -> Result<()> {
+		restore::restore(self, start, monitor).context(ErrorKind::Restore)?;
 		Ok(())
 	}
 
-	fn check_repair(&mut self, delete_unconfirmed: bool) -> Result<()> {
-		restore::check_repair(self, delete_unconfirmed).context(ErrorKind::Restore)?;
+	fn check_repair(
+		&mut self,
+		delete_unconfirmed: bool,
+		start: &ScanStart,
+		monitor: &ScanMonitor,
+	) -> Result<()> {
+		restore::check_repair(self, delete_unconfirmed, start, monitor)
+			.context(ErrorKind::Restore)?;
 		Ok(())
 	}
 
@@ -484,6 +543,8 @@ where
 	db: RefCell<Option<grin_store::Batch<'a>>>,
 	/// Keychain
 	keychain: Option<K>,
+	/// Events to hand to the backend on commit
+	events: Vec<WalletEvent>,
 }
 
 #[allow(missing_docs)]
@@ -575,6 +636,24 @@ where
 		Ok(())
 	}
 
+	fn save_scan_checkpoint(&mut self, scan: &str, index: u64) -> Result<()> {
+		let key = to_key(SCAN_CHECKPOINT_PREFIX, &mut scan.as_bytes().to_vec());
+		self.db.borrow().as_ref().unwrap().put_ser(&key, &index)?;
+		Ok(())
+	}
+
+	fn delete_scan_checkpoint(&mut self, scan: &str) -> Result<()> {
+		let key = to_key(SCAN_CHECKPOINT_PREFIX, &mut scan.as_bytes().to_vec());
+		let _ = self.db.borrow().as_ref().unwrap().delete(&key);
+		Ok(())
+	}
+
+	fn save_last_scanned_index(&mut self, index: u64) -> Result<()> {
+		let key = to_key(LAST_SCANNED_INDEX_PREFIX, &mut vec![]);
+		self.db.borrow().as_ref().unwrap().put_ser(&key, &index)?;
+		Ok(())
+	}
+
 	fn save_child_index(&mut self, parent_key_id: &Identifier, index: u32) -> Result<()> {
 		let deriv_key = to_key(DERIV_PREFIX, &mut parent_key_id.to_bytes().to_vec());
 		self.db
@@ -658,9 +737,60 @@ where
 			.map_err(|e| e.into())
 	}
 
+	fn save_outbox_entry(&mut self, entry: &OutboxEntry) -> Result<()> {
+		let key = to_key(OUTBOX_PREFIX, &mut entry.slate_id.as_bytes().to_vec());
+		self.db.borrow().as_ref().unwrap().put_ser(&key, entry)?;
+		Ok(())
+	}
+
+	fn delete_outbox_entry(&mut self, slate_id: &Uuid) -> Result<()> {
+		let key = to_key(OUTBOX_PREFIX, &mut slate_id.as_bytes().to_vec());
+		let _ = self.db.borrow().as_ref().unwrap().delete(&key);
+		Ok(())
+	}
+
+	fn save_incoming_slate(&mut self, entry: &IncomingSlate) -> Result<()> {
+		let key = to_key(INCOMING_PREFIX, &mut entry.slate_id.as_bytes().to_vec());
+		self.db.borrow().as_ref().unwrap().put_ser(&key, entry)?;
+		Ok(())
+	}
+
+	fn delete_incoming_slate(&mut self, slate_id: &Uuid) -> Result<()> {
+		let key = to_key(INCOMING_PREFIX, &mut slate_id.as_bytes().to_vec());
+		let _ = self.db.borrow().as_ref().unwrap().delete(&key);
+		Ok(())
+	}
+
+	fn save_payment_request(&mut self, request: &PaymentRequest) -> Result<()> {
+		let key = to_key(
+			PAYMENT_REQUEST_PREFIX,
+			&mut request.address_index.to_be_bytes().to_vec(),
+		);
+		self.db.borrow().as_ref().unwrap().put_ser(&key, request)?;
+		Ok(())
+	}
+
+	fn save_webhook_entry(&mut self, entry: &WebhookEntry) -> Result<()> {
+		let key = to_key(WEBHOOK_PREFIX, &mut entry.event.id.as_bytes().to_vec());
+		self.db.borrow().as_ref().unwrap().put_ser(&key, entry)?;
+		Ok(())
+	}
+
+	fn delete_webhook_entry(&mut self, event_id: &Uuid) -> Result<()> {
+		let key = to_key(WEBHOOK_PREFIX, &mut event_id.as_bytes().to_vec());
+		let _ = self.db.borrow().as_ref().unwrap().delete(&key);
+		Ok(())
+	}
+
+	fn queue_event(&mut self, event: WalletEvent) -> Result<()> {
+		self.events.push(event);
+		Ok(())
+	}
+
 	fn commit(&mut self) -> Result<()> {
 		let db = self.db.replace(None);
 		db.unwrap().commit()?;
+		self._store.events.lock().extend(self.events.drain(..));
 		Ok(())
 	}
 }
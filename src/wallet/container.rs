@@ -18,11 +18,17 @@ use crate::common::config::Wallet713Config;
 use crate::common::{Arc, Keychain, Mutex};
 use crate::contacts::AddressBook;
 use crate::wallet::backend::Backend;
-use crate::wallet::types::{HTTPNodeClient, NodeClient, WalletBackend};
+use crate::wallet::types::{
+	FailoverNodeClient, NodeClient, PaymentRequestStatus, SlateVersion, SnapshotNodeClient,
+	WalletBackend, WalletEvent,
+};
+use chrono::{DateTime, Utc};
 use failure::Error;
+use futures::sync::mpsc::UnboundedSender;
 use grin_keychain::ExtKeychain;
 use std::collections::HashMap;
 use std::marker::PhantomData;
+use std::path::Path;
 
 pub struct Container<W, C, K>
 where
@@ -35,6 +41,14 @@ where
 	pub address_book: AddressBook,
 	pub account: String,
 	pub listeners: HashMap<ListenerInterface, Box<dyn Listener>>,
+	/// Slate versions last used by grinbox and keybase peers
+	pub peer_slate_versions: HashMap<String, SlateVersion>,
+	/// Arrival times of new slates from grinbox and keybase peers, for the hourly limit
+	pub received_slates: HashMap<String, Vec<DateTime<Utc>>>,
+	/// Open event streams of the owner API
+	pub event_subscribers: Vec<UnboundedSender<WalletEvent>>,
+	/// Held while queued events are posted to the webhooks
+	pub webhook_delivery: Arc<Mutex<()>>,
 	phantom_c: PhantomData<C>,
 	phantom_k: PhantomData<K>,
 }
@@ -52,6 +66,10 @@ where
 			address_book,
 			account: String::from("default"),
 			listeners: HashMap::with_capacity(4),
+			peer_slate_versions: HashMap::new(),
+			received_slates: HashMap::new(),
+			event_subscribers: Vec::new(),
+			webhook_delivery: Arc::new(Mutex::new(())),
 			phantom_c: PhantomData,
 			phantom_k: PhantomData,
 		};
@@ -74,20 +92,48 @@ where
 			.get(&interface)
 			.ok_or(ErrorKind::NoListener(format!("{}", interface)))
 	}
+
+	/// Address indices the grinbox listener subscribes to. Every open
+	/// payment request has an address of its own
+	pub fn grinbox_indices(&mut self) -> Result<Vec<u32>, Error> {
+		let mut indices = self.config.grinbox_listen_indices();
+		indices.extend(
+			self.backend()?
+				.payment_requests()?
+				.filter(|r| r.status == PaymentRequestStatus::Open && !r.is_expired())
+				.map(|r| r.address_index),
+		);
+		Ok(indices)
+	}
 }
 
 pub fn create_container(
 	config: Wallet713Config,
 	address_book: AddressBook,
 ) -> Result<
-	Arc<Mutex<Container<Backend<HTTPNodeClient, ExtKeychain>, HTTPNodeClient, ExtKeychain>>>,
+	Arc<
+		Mutex<Container<Backend<FailoverNodeClient, ExtKeychain>, FailoverNodeClient, ExtKeychain>>,
+	>,
+	Error,
+> {
+	let wallet_config = config.as_wallet_config()?;
+	let client = FailoverNodeClient::new(config.grin_nodes(), config.grin_node_broadcast());
+	let backend = Backend::new(&wallet_config, client)?;
+	Ok(Container::new(config, backend, address_book))
+}
+
+/// Container for a wallet without a node connection
+pub fn create_offline_container(
+	config: Wallet713Config,
+	address_book: AddressBook,
+) -> Result<
+	Arc<
+		Mutex<Container<Backend<SnapshotNodeClient, ExtKeychain>, SnapshotNodeClient, ExtKeychain>>,
+	>,
 	Error,
 > {
 	let wallet_config = config.as_wallet_config()?;
-	let client = HTTPNodeClient::new(
-		&wallet_config.check_node_api_http_addr,
-		config.grin_node_secret().clone(),
-	);
+	let client = SnapshotNodeClient::new(Path::new(&wallet_config.data_file_dir));
 	let backend = Backend::new(&wallet_config, client)?;
 	Ok(Container::new(config, backend, address_book))
 }
@@ -229,6 +229,50 @@ pub enum ErrorKind {
 	#[fail(display = "Unable to verify proof")]
 	VerifyProof,
 
+	/// Request never got a response from the node
+	#[fail(display = "Node unreachable: {}", _0)]
+	NodeUnreachable(String),
+
+	/// Armored slate could not be read
+	#[fail(display = "Invalid armored slate: {}", _0)]
+	ArmoredSlate(String),
+
+	/// Operation needs a node, but the wallet runs offline
+	#[fail(display = "Wallet is offline: {}", _0)]
+	Offline(String),
+
+	/// No queued slate with this id
+	#[fail(display = "Slate {} is not in the outbox", _0)]
+	OutboxEntryNotFound(String),
+
+	/// No payment request with this address index
+	#[fail(display = "Payment request {} doesn't exist", _0)]
+	PaymentRequestNotFound(u32),
+
+	/// No received slate with this id waits for approval
+	#[fail(display = "Slate {} is not waiting for approval", _0)]
+	IncomingSlateNotFound(String),
+
+	/// Slate arrived after its time-to-live ran out
+	#[fail(display = "Slate {} has expired", _0)]
+	SlateExpired(String),
+
+	/// Received slate didn't pass the receive policy
+	#[fail(display = "Slate {} rejected: {}", _0, _1)]
+	SlateRejected(String, String),
+
+	/// Slate couldn't be handed to the peer at all, sending it another way is safe
+	#[fail(display = "Peer unreachable: {}", _0)]
+	PeerUnreachable(String),
+
+	/// Messages are only signed with grinbox address keys
+	#[fail(display = "Not a grinbox address: {}", _0)]
+	NotGrinboxAddress(String),
+
+	/// Signature isn't hex encoded DER
+	#[fail(display = "Invalid signature")]
+	InvalidSignature,
+
 	/// Other
 	#[fail(display = "Generic error: {}", _0)]
 	GenericError(String),
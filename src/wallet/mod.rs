@@ -21,5 +21,5 @@ mod seed;
 pub mod types;
 
 pub use self::backend::Backend;
-pub use self::container::{create_container, Container};
+pub use self::container::{create_container, create_offline_container, Container};
 pub use self::error::ErrorKind;
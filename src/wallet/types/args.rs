@@ -61,6 +61,9 @@ pub struct InitTxArgs {
 	/// 'true', the amount field in the slate will contain the total amount locked, not the provided
 	/// transaction amount
 	pub estimate_only: Option<bool>,
+	/// Number of blocks after which the transaction is cancelled if the recipient
+	/// hasn't responded. If `None` the slate never expires
+	pub ttl_blocks: Option<u64>,
 	/// Sender arguments. If present, the underlying function will also attempt to send the
 	/// transaction to a destination and optionally finalize the result
 	pub send_args: Option<InitTxSendArgs>,
@@ -94,6 +97,7 @@ impl Default for InitTxArgs {
 			message: None,
 			target_slate_version: None,
 			estimate_only: Some(false),
+			ttl_blocks: None,
 			send_args: None,
 		}
 	}
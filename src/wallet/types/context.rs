@@ -43,6 +43,9 @@ pub struct Context {
 	pub output_commits: Vec<Commitment>,
 	/// Input commitments
 	pub input_commits: Vec<Commitment>,
+	/// Chain height after which the slate expires
+	#[serde(default)]
+	pub ttl_cutoff_height: Option<u64>,
 }
 
 impl Context {
@@ -64,6 +67,7 @@ impl Context {
 			fee: 0,
 			output_commits: vec![],
 			input_commits: vec![],
+			ttl_cutoff_height: None,
 		}
 	}
 }
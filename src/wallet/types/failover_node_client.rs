@@ -0,0 +1,437 @@
+// Copyright 2019 The vault713 Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{HTTPNodeClient, NodeClient, NodeVersionInfo, TxWrapper};
+use crate::common::config::NodeConfig;
+use crate::common::{Arc, Mutex};
+use crate::wallet::ErrorKind;
+use failure::Error;
+use grin_core::core::TxKernel;
+use grin_util::secp::pedersen::{Commitment, RangeProof};
+use log::{debug, warn};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// How long the result of a health check is trusted
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+struct FailoverState {
+	/// Indices of the nodes, best node first
+	ranking: Vec<usize>,
+	last_check: Option<Instant>,
+}
+
+/// Node client that spreads its requests over several grin nodes.
+/// Nodes are health checked periodically and ranked by chain height,
+/// requests go to the most synced node and fail over to the next one on errors
+#[derive(Clone)]
+pub struct FailoverNodeClient<C = HTTPNodeClient>
+where
+	C: NodeClient,
+{
+	nodes: Vec<C>,
+	broadcast: usize,
+	state: Arc<Mutex<FailoverState>>,
+}
+
+impl FailoverNodeClient {
+	pub fn new(nodes: Vec<NodeConfig>, broadcast: usize) -> Self {
+		let nodes = nodes
+			.into_iter()
+			.map(|n| HTTPNodeClient::new(&n.uri, n.secret))
+			.collect();
+		Self::from_nodes(nodes, broadcast)
+	}
+}
+
+impl<C> FailoverNodeClient<C>
+where
+	C: NodeClient,
+{
+	pub fn from_nodes(nodes: Vec<C>, broadcast: usize) -> Self {
+		let state = FailoverState {
+			ranking: (0..nodes.len()).collect(),
+			last_check: None,
+		};
+		Self {
+			nodes,
+			broadcast: broadcast.max(1),
+			state: Arc::new(Mutex::new(state)),
+		}
+	}
+
+	/// Chain height of a node, if it is reachable. Nodes too old to report
+	/// their version are ranked by their height like the others
+	fn health(&mut self, index: usize) -> Option<u64> {
+		let node = &mut self.nodes[index];
+		match node.get_version_info() {
+			Some(_) => node.get_chain_height().ok(),
+			None => None,
+		}
+	}
+
+	/// Check all nodes and rank them. Nodes that respond are ordered by chain
+	/// height, ties keep the configured order. Unresponsive nodes go last
+	pub fn check_health(&mut self) {
+		let mut health: Vec<(usize, Option<u64>)> =
+			(0..self.nodes.len()).map(|i| (i, self.health(i))).collect();
+		health.sort_by(|(a, ha), (b, hb)| hb.cmp(ha).then(a.cmp(b)));
+
+		for (i, height) in health.iter() {
+			match height {
+				Some(height) => debug!("Node {} at height {}", self.nodes[*i].node_url(), height),
+				None => warn!("Node {} is unavailable", self.nodes[*i].node_url()),
+			}
+		}
+
+		let mut state = self.state.lock();
+		state.ranking = health.into_iter().map(|(i, _)| i).collect();
+		state.last_check = Some(Instant::now());
+	}
+
+	/// Node indices in order of preference, refreshing the health check if it expired
+	fn ranking(&self) -> Vec<usize> {
+		if self.nodes.len() > 1 {
+			let expired = match self.state.lock().last_check {
+				Some(t) => t.elapsed() > HEALTH_CHECK_INTERVAL,
+				None => true,
+			};
+			if expired {
+				// Version info is cached per client, so check on a copy
+				let mut client = self.clone();
+				client.check_health();
+			}
+		}
+		self.state.lock().ranking.clone()
+	}
+
+	/// Move a node that failed a request to the back of the ranking
+	fn demote(&self, index: usize) {
+		let mut state = self.state.lock();
+		if let Some(pos) = state.ranking.iter().position(|i| *i == index) {
+			let i = state.ranking.remove(pos);
+			state.ranking.push(i);
+		}
+	}
+
+	fn with_failover<F, T>(&self, f: F) -> Result<T, Error>
+	where
+		F: Fn(&C) -> Result<T, Error>,
+	{
+		let mut last_error = None;
+		for i in self.ranking() {
+			let node = &self.nodes[i];
+			match f(node) {
+				Ok(res) => return Ok(res),
+				Err(e) => {
+					warn!("Node {} failed, trying next node: {}", node.node_url(), e);
+					self.demote(i);
+					last_error = Some(e);
+				}
+			}
+		}
+		Err(last_error
+			.unwrap_or(ErrorKind::ClientCallback("No nodes configured".to_owned()).into()))
+	}
+
+	fn preferred(&self) -> usize {
+		self.state.lock().ranking.first().cloned().unwrap_or(0)
+	}
+}
+
+impl<C> NodeClient for FailoverNodeClient<C>
+where
+	C: NodeClient,
+{
+	fn node_url(&self) -> &str {
+		self.nodes[self.preferred()].node_url()
+	}
+
+	/// Changes the first configured node
+	fn set_node_url(&mut self, node_url: &str) {
+		self.nodes[0].set_node_url(node_url);
+		self.state.lock().last_check = None;
+	}
+
+	fn node_api_secret(&self) -> Option<String> {
+		self.nodes[self.preferred()].node_api_secret()
+	}
+
+	/// Changes the secret of the first configured node
+	fn set_node_api_secret(&mut self, node_api_secret: Option<String>) {
+		self.nodes[0].set_node_api_secret(node_api_secret);
+		self.state.lock().last_check = None;
+	}
+
+	fn get_version_info(&mut self) -> Option<NodeVersionInfo> {
+		for i in self.ranking() {
+			if let Some(v) = self.nodes[i].get_version_info() {
+				return Some(v);
+			}
+		}
+		None
+	}
+
+	/// Posts the transaction to the `broadcast` best nodes.
+	/// Succeeds if at least one of them accepted it. Only nodes that
+	/// couldn't be reached are demoted, not ones rejecting the transaction
+	fn post_tx(&self, tx: &TxWrapper, fluff: bool) -> Result<(), Error> {
+		let mut posted = 0;
+		let mut last_error = None;
+		for i in self.ranking() {
+			if posted >= self.broadcast {
+				break;
+			}
+			let node = &self.nodes[i];
+			match node.post_tx(tx, fluff) {
+				Ok(_) => posted += 1,
+				Err(e) => {
+					warn!("Unable to post transaction to {}: {}", node.node_url(), e);
+					if is_unreachable(&e) {
+						self.demote(i);
+					}
+					last_error = Some(e);
+				}
+			}
+		}
+		match (posted, last_error) {
+			(0, Some(e)) => Err(e),
+			(0, None) => Err(ErrorKind::ClientCallback("No nodes configured".to_owned()).into()),
+			_ => Ok(()),
+		}
+	}
+
+	fn get_chain_height(&self) -> Result<u64, Error> {
+		self.with_failover(|n| n.get_chain_height())
+	}
+
+	fn get_outputs_from_node(
+		&self,
+		wallet_outputs: Vec<Commitment>,
+	) -> Result<HashMap<Commitment, (String, u64, u64)>, Error> {
+		self.with_failover(|n| n.get_outputs_from_node(wallet_outputs.clone()))
+	}
+
+	fn get_outputs_by_pmmr_index(
+		&self,
+		start_height: u64,
+		max_outputs: u64,
+	) -> Result<(u64, u64, Vec<(Commitment, RangeProof, bool, u64, u64)>), Error> {
+		self.with_failover(|n| n.get_outputs_by_pmmr_index(start_height, max_outputs))
+	}
+
+	fn height_range_to_pmmr_indices(
+		&self,
+		start_height: u64,
+		end_height: Option<u64>,
+	) -> Result<(u64, u64), Error> {
+		self.with_failover(|n| n.height_range_to_pmmr_indices(start_height, end_height))
+	}
+
+	fn get_kernel(
+		&self,
+		excess: &Commitment,
+		min_height: Option<u64>,
+		max_height: Option<u64>,
+	) -> Result<Option<(TxKernel, u64, u64)>, Error> {
+		self.with_failover(|n| n.get_kernel(excess, min_height, max_height))
+	}
+}
+
+fn is_unreachable(e: &Error) -> bool {
+	match e.downcast_ref::<ErrorKind>() {
+		Some(ErrorKind::NodeUnreachable(_)) => true,
+		_ => false,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Node that only answers health checks and posts
+	#[derive(Clone)]
+	struct StubNode {
+		url: String,
+		height: u64,
+		verified: Option<bool>,
+		/// Whether requests reach the node
+		up: Arc<Mutex<bool>>,
+		/// Whether the node accepts transactions
+		accepts: bool,
+		posts: Arc<Mutex<Vec<String>>>,
+	}
+
+	impl StubNode {
+		fn new(url: &str, height: u64, posts: &Arc<Mutex<Vec<String>>>) -> Self {
+			Self {
+				url: url.to_owned(),
+				height,
+				verified: Some(true),
+				up: Arc::new(Mutex::new(true)),
+				accepts: true,
+				posts: posts.clone(),
+			}
+		}
+
+		fn check_up(&self) -> Result<(), Error> {
+			match *self.up.lock() {
+				true => Ok(()),
+				false => Err(ErrorKind::NodeUnreachable(self.url.clone()).into()),
+			}
+		}
+	}
+
+	impl NodeClient for StubNode {
+		fn node_url(&self) -> &str {
+			&self.url
+		}
+
+		fn set_node_url(&mut self, node_url: &str) {
+			self.url = node_url.to_owned();
+		}
+
+		fn node_api_secret(&self) -> Option<String> {
+			None
+		}
+
+		fn set_node_api_secret(&mut self, _node_api_secret: Option<String>) {}
+
+		fn get_version_info(&mut self) -> Option<NodeVersionInfo> {
+			self.check_up().ok()?;
+			Some(NodeVersionInfo {
+				node_version: "3.0.0".into(),
+				block_header_version: 2,
+				verified: self.verified,
+			})
+		}
+
+		fn post_tx(&self, _tx: &TxWrapper, _fluff: bool) -> Result<(), Error> {
+			self.check_up()?;
+			if !self.accepts {
+				return Err(ErrorKind::ClientCallback("rejected".to_owned()).into());
+			}
+			self.posts.lock().push(self.url.clone());
+			Ok(())
+		}
+
+		fn get_chain_height(&self) -> Result<u64, Error> {
+			self.check_up()?;
+			Ok(self.height)
+		}
+
+		fn get_outputs_from_node(
+			&self,
+			_wallet_outputs: Vec<Commitment>,
+		) -> Result<HashMap<Commitment, (String, u64, u64)>, Error> {
+			unimplemented!()
+		}
+
+		fn get_outputs_by_pmmr_index(
+			&self,
+			_start_height: u64,
+			_max_outputs: u64,
+		) -> Result<(u64, u64, Vec<(Commitment, RangeProof, bool, u64, u64)>), Error> {
+			unimplemented!()
+		}
+
+		fn height_range_to_pmmr_indices(
+			&self,
+			_start_height: u64,
+			_end_height: Option<u64>,
+		) -> Result<(u64, u64), Error> {
+			unimplemented!()
+		}
+
+		fn get_kernel(
+			&self,
+			_excess: &Commitment,
+			_min_height: Option<u64>,
+			_max_height: Option<u64>,
+		) -> Result<Option<(TxKernel, u64, u64)>, Error> {
+			unimplemented!()
+		}
+	}
+
+	fn tx() -> TxWrapper {
+		TxWrapper {
+			tx_hex: String::new(),
+		}
+	}
+
+	#[test]
+	fn ranks_reachable_nodes_by_height() {
+		let posts = Arc::new(Mutex::new(vec![]));
+		let behind = StubNode::new("behind", 10, &posts);
+		// A node without /v1/version is still used
+		let mut unverified = StubNode::new("unverified", 12, &posts);
+		unverified.verified = Some(false);
+		let down = StubNode::new("down", 20, &posts);
+		*down.up.lock() = false;
+
+		let mut client = FailoverNodeClient::from_nodes(vec![down, behind, unverified], 1);
+		client.check_health();
+		assert_eq!(client.state.lock().ranking, vec![2, 1, 0]);
+		assert_eq!(client.node_url(), "unverified");
+		assert_eq!(client.get_chain_height().unwrap(), 12);
+	}
+
+	#[test]
+	fn fails_over_to_next_node() {
+		let posts = Arc::new(Mutex::new(vec![]));
+		let first = StubNode::new("first", 12, &posts);
+		let second = StubNode::new("second", 11, &posts);
+		let first_up = first.up.clone();
+
+		let mut client = FailoverNodeClient::from_nodes(vec![second, first], 1);
+		client.check_health();
+		assert_eq!(client.node_url(), "first");
+
+		*first_up.lock() = false;
+		assert_eq!(client.get_chain_height().unwrap(), 11);
+		assert_eq!(client.node_url(), "second");
+
+		*client.nodes[0].up.lock() = false;
+		assert!(client.get_chain_height().is_err());
+	}
+
+	#[test]
+	fn broadcasts_to_best_nodes() {
+		let posts = Arc::new(Mutex::new(vec![]));
+		let a = StubNode::new("a", 12, &posts);
+		let b = StubNode::new("b", 11, &posts);
+		let c = StubNode::new("c", 10, &posts);
+		let b_up = b.up.clone();
+
+		let mut client = FailoverNodeClient::from_nodes(vec![a.clone(), b, c], 2);
+		client.check_health();
+		client.post_tx(&tx(), false).unwrap();
+		assert_eq!(*posts.lock(), vec!["a", "b"]);
+
+		// An unreachable node is skipped and demoted
+		posts.lock().clear();
+		*b_up.lock() = false;
+		client.post_tx(&tx(), false).unwrap();
+		assert_eq!(*posts.lock(), vec!["a", "c"]);
+		assert_eq!(client.state.lock().ranking, vec![0, 2, 1]);
+
+		// A node rejecting the transaction keeps its place
+		let mut rejecting = a;
+		rejecting.accepts = false;
+		let mut client = FailoverNodeClient::from_nodes(vec![rejecting], 1);
+		client.check_health();
+		assert!(client.post_tx(&tx(), false).is_err());
+		assert_eq!(client.state.lock().ranking, vec![0]);
+	}
+}
@@ -0,0 +1,64 @@
+// Copyright 2019 The vault713 Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::VersionedSlate;
+use chrono::prelude::*;
+use grin_core::ser;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Slate received over grinbox or keybase that waits for approval
+/// before it is signed
+#[derive(Serialize, Deserialize, Clone)]
+pub struct IncomingSlate {
+	/// Id of the slate
+	pub slate_id: Uuid,
+	/// Transport the slate came in with, `grinbox` or `keybase`
+	pub method: String,
+	/// Address of the sender, the reply is sent there
+	pub from: String,
+	/// The slate, in the version the sender used
+	pub slate: VersionedSlate,
+	/// When the slate was received
+	pub received_ts: DateTime<Utc>,
+	/// Index of our grinbox address the slate was sent to
+	#[serde(default)]
+	pub address_index: Option<u32>,
+}
+
+impl IncomingSlate {
+	pub fn new(slate_id: Uuid, method: String, from: String, slate: VersionedSlate) -> Self {
+		Self {
+			slate_id,
+			method,
+			from,
+			slate,
+			received_ts: Utc::now(),
+			address_index: None,
+		}
+	}
+}
+
+impl ser::Writeable for IncomingSlate {
+	fn write<W: ser::Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		writer.write_bytes(&serde_json::to_vec(self).map_err(|_| ser::Error::CorruptedData)?)
+	}
+}
+
+impl ser::Readable for IncomingSlate {
+	fn read(reader: &mut dyn ser::Reader) -> Result<IncomingSlate, ser::Error> {
+		let data = reader.read_bytes_len_prefix()?;
+		serde_json::from_slice(&data[..]).map_err(|_| ser::Error::CorruptedData)
+	}
+}
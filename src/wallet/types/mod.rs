@@ -18,19 +18,30 @@ mod block_fees;
 mod block_identifier;
 mod cb_data;
 mod context;
+mod failover_node_client;
+mod incoming_slate;
 mod node_client;
+mod outbox_entry;
 mod output_commit_mapping;
 mod output_data;
 mod output_status;
+mod payment_request;
+mod scan_progress;
+mod signed_message;
+#[cfg(test)]
+mod simulated_node_client;
 mod slate;
+mod snapshot_node_client;
 mod tx_log_entry;
 mod tx_log_entry_type;
 mod tx_proof;
 mod tx_wrapper;
 mod wallet_backend;
 mod wallet_backend_batch;
+mod wallet_event;
 mod wallet_info;
 mod wallet_inst;
+mod webhook_entry;
 
 pub use self::acct_path_mapping::AcctPathMapping;
 pub use self::args::*;
@@ -38,13 +49,25 @@ pub use self::block_fees::BlockFees;
 pub use self::block_identifier::BlockIdentifier;
 pub use self::cb_data::CbData;
 pub use self::context::Context;
+pub use self::failover_node_client::FailoverNodeClient;
+pub use self::incoming_slate::IncomingSlate;
 pub use self::node_client::{HTTPNodeClient, NodeClient, NodeVersionInfo};
+pub use self::outbox_entry::OutboxEntry;
 pub use self::output_commit_mapping::OutputCommitMapping;
 pub use self::output_data::OutputData;
 pub use self::output_status::OutputStatus;
+pub use self::payment_request::{
+	PaymentRequest, PaymentRequestStatus, PAYMENT_REQUEST_FIRST_INDEX,
+};
+pub use self::scan_progress::{ScanMonitor, ScanProgress, ScanStart};
+pub use self::signed_message::SignedMessage;
+#[cfg(test)]
+pub use self::simulated_node_client::SimulatedNodeClient;
 pub use self::slate::{
-	Slate, SlateVersion, VersionedSlate, CURRENT_SLATE_VERSION, GRIN_BLOCK_HEADER_VERSION,
+	armor, ReceiverProof, Slate, SlateVersion, VersionedSlate, CURRENT_SLATE_VERSION,
+	GRIN_BLOCK_HEADER_VERSION, MIN_SLATE_VERSION,
 };
+pub use self::snapshot_node_client::{OutputSnapshot, SnapshotNodeClient, SnapshotOutput};
 pub use self::tx_log_entry::TxLogEntry;
 pub use self::tx_log_entry_type::TxLogEntryType;
 pub use self::tx_proof::ErrorKind as TxProofErrorKind;
@@ -52,8 +75,10 @@ pub use self::tx_proof::TxProof;
 pub use self::tx_wrapper::TxWrapper;
 pub use self::wallet_backend::WalletBackend;
 pub use self::wallet_backend_batch::WalletBackendBatch;
+pub use self::wallet_event::{WalletEvent, WalletEventType};
 pub use self::wallet_info::WalletInfo;
 pub use self::wallet_inst::WalletInst;
+pub use self::webhook_entry::WebhookEntry;
 pub use super::seed::{EncryptedWalletSeed, WalletSeed};
 pub use crate::common::{Arc, Mutex, MutexGuard, Result};
 pub use grin_core::core::hash::Hash;
@@ -18,9 +18,11 @@ use crate::wallet::ErrorKind;
 use failure::Error;
 use futures::stream;
 use futures::Stream;
-use grin_api::{Output, OutputListing, OutputType, Tip};
+use grin_api::{LocatedTxKernel, Output, OutputListing, OutputType, Tip};
+use grin_core::core::TxKernel;
 use grin_util::secp::pedersen::{Commitment, RangeProof};
 use grin_util::to_hex;
+use http::StatusCode;
 use log::error;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -77,6 +79,24 @@ pub trait NodeClient: Sync + Send + Clone + 'static {
 		start_height: u64,
 		max_outputs: u64,
 	) -> Result<(u64, u64, Vec<(Commitment, RangeProof, bool, u64, u64)>), Error>;
+
+	/// Return the PMMR index range (first, last) of the outputs in
+	/// a range of block heights. Without an end height, the range runs to the tip
+	fn height_range_to_pmmr_indices(
+		&self,
+		start_height: u64,
+		end_height: Option<u64>,
+	) -> Result<(u64, u64), Error>;
+
+	/// Look up a kernel by its excess, optionally between two block heights.
+	/// Returns the kernel, the height it was included at and its MMR index,
+	/// or `None` if it is not on chain
+	fn get_kernel(
+		&self,
+		excess: &Commitment,
+		min_height: Option<u64>,
+		max_height: Option<u64>,
+	) -> Result<Option<(TxKernel, u64, u64)>, Error>;
 }
 
 #[derive(Clone)]
@@ -123,9 +143,7 @@ impl NodeClient for HTTPNodeClient {
 			Ok(n) => n,
 			Err(e) => {
 				// If node isn't available, allow offline functions
-				// unfortunately have to parse string due to error structure
-				let err_string = format!("{}", e);
-				if err_string.contains("404") {
+				if e.status() == Some(StatusCode::NOT_FOUND) {
 					return Some(NodeVersionInfo {
 						node_version: "1.0.0".into(),
 						block_header_version: 1,
@@ -155,7 +173,11 @@ impl NodeClient for HTTPNodeClient {
 		if let Err(e) = res {
 			let report = format!("Posting transaction to node: {}", e);
 			error!("Post TX Error: {}", e);
-			return Err(ErrorKind::ClientCallback(report).into());
+			// A response means the node is up, but didn't accept the transaction
+			return match e.status() {
+				Some(_) => Err(ErrorKind::ClientCallback(report).into()),
+				None => Err(ErrorKind::NodeUnreachable(report).into()),
+			};
 		}
 		Ok(())
 	}
@@ -268,4 +290,81 @@ impl NodeClient for HTTPNodeClient {
 			}
 		}
 	}
+
+	fn height_range_to_pmmr_indices(
+		&self,
+		start_height: u64,
+		end_height: Option<u64>,
+	) -> Result<(u64, u64), Error> {
+		let addr = self.node_url();
+		let mut query_param = format!("start_height={}", start_height);
+		if let Some(e) = end_height {
+			query_param = format!("{}&end_height={}", query_param, e);
+		}
+
+		let url = format!("{}/v1/txhashset/heightstopmmr?{}", addr, query_param);
+
+		match client::get::<OutputListing>(url.as_str(), self.node_api_secret()) {
+			Ok(o) => Ok((o.last_retrieved_index, o.highest_index)),
+			Err(e) => {
+				error!(
+					"height_range_to_pmmr_indices: error contacting {}. Error: {}",
+					addr, e
+				);
+				let report = format!("heights to pmmr indices: {}", e);
+				Err(ErrorKind::ClientCallback(report))?
+			}
+		}
+	}
+
+	fn get_kernel(
+		&self,
+		excess: &Commitment,
+		min_height: Option<u64>,
+		max_height: Option<u64>,
+	) -> Result<Option<(TxKernel, u64, u64)>, Error> {
+		let mut query = vec![];
+		if let Some(h) = min_height {
+			query.push(format!("min_height={}", h));
+		}
+		if let Some(h) = max_height {
+			query.push(format!("max_height={}", h));
+		}
+		let mut url = format!(
+			"{}/v1/chain/kernels/{}",
+			self.node_url(),
+			to_hex(excess.0.to_vec())
+		);
+		if !query.is_empty() {
+			url = format!("{}?{}", url, query.join("&"));
+		}
+
+		match client::get::<Option<LocatedTxKernel>>(url.as_str(), self.node_api_secret()) {
+			Ok(k) => Ok(k.map(|k| (k.tx_kernel, k.height, k.mmr_index))),
+			Err(e) => {
+				// The node reports unknown kernels as not found. A node without the
+				// endpoint answers with an empty 404, that is an error
+				let not_found = e.status() == Some(StatusCode::NOT_FOUND);
+				let body = e.body().unwrap_or_default();
+				if not_found && body.to_lowercase().contains("not found") {
+					return Ok(None);
+				}
+				if not_found {
+					let report = format!(
+						"kernel lookup: {} doesn't support /v1/chain/kernels",
+						self.node_url()
+					);
+					error!("get_kernel: {}", report);
+					Err(ErrorKind::ClientCallback(report))?
+				}
+				error!(
+					"get_kernel: error contacting {}. Error: {}",
+					self.node_url(),
+					e
+				);
+				let report = format!("kernel lookup: {}", e);
+				Err(ErrorKind::ClientCallback(report))?
+			}
+		}
+	}
 }
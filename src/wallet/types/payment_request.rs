@@ -0,0 +1,114 @@
+// Copyright 2019 The vault713 Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use chrono::prelude::*;
+use grin_core::ser;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use uuid::Uuid;
+
+/// First grinbox address index handed out to payment requests, far above the
+/// indices reached with `address --next`
+pub const PAYMENT_REQUEST_FIRST_INDEX: u32 = 1_000_000;
+
+/// State of a payment request
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum PaymentRequestStatus {
+	/// Waiting for a payment
+	Open,
+	/// Payment received, waiting for confirmation
+	Received,
+	/// Payment confirmed
+	Paid,
+	/// Expired before a payment arrived
+	Expired,
+	/// Cancelled by the owner
+	Cancelled,
+}
+
+impl fmt::Display for PaymentRequestStatus {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			PaymentRequestStatus::Open => write!(f, "Open"),
+			PaymentRequestStatus::Received => write!(f, "Received"),
+			PaymentRequestStatus::Paid => write!(f, "Paid"),
+			PaymentRequestStatus::Expired => write!(f, "Expired"),
+			PaymentRequestStatus::Cancelled => write!(f, "Cancelled"),
+		}
+	}
+}
+
+/// Expected payment to a grinbox address derived for it alone
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PaymentRequest {
+	/// Index of the grinbox address, identifies the request
+	pub address_index: u32,
+	/// The grinbox address to pay to
+	pub address: String,
+	/// Expected amount in nanogrins
+	pub amount: u64,
+	/// Free text, for example an order number
+	pub memo: Option<String>,
+	/// When the request was created
+	pub creation_ts: DateTime<Utc>,
+	/// The request expires at this time if it isn't paid
+	pub expiry_ts: Option<DateTime<Utc>>,
+	/// Current state
+	pub status: PaymentRequestStatus,
+	/// Slate of the payment
+	pub slate_id: Option<Uuid>,
+	/// Amount received in nanogrins
+	pub amount_received: u64,
+}
+
+impl PaymentRequest {
+	pub fn new(
+		address_index: u32,
+		address: String,
+		amount: u64,
+		memo: Option<String>,
+		expiry_ts: Option<DateTime<Utc>>,
+	) -> Self {
+		Self {
+			address_index,
+			address,
+			amount,
+			memo,
+			creation_ts: Utc::now(),
+			expiry_ts,
+			status: PaymentRequestStatus::Open,
+			slate_id: None,
+			amount_received: 0,
+		}
+	}
+
+	/// Whether the request is open but past its expiry time
+	pub fn is_expired(&self) -> bool {
+		self.status == PaymentRequestStatus::Open
+			&& self.expiry_ts.map(|t| t <= Utc::now()).unwrap_or(false)
+	}
+}
+
+impl ser::Writeable for PaymentRequest {
+	fn write<W: ser::Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		writer.write_bytes(&serde_json::to_vec(self).map_err(|_| ser::Error::CorruptedData)?)
+	}
+}
+
+impl ser::Readable for PaymentRequest {
+	fn read(reader: &mut dyn ser::Reader) -> Result<PaymentRequest, ser::Error> {
+		let data = reader.read_bytes_len_prefix()?;
+		serde_json::from_slice(&data[..]).map_err(|_| ser::Error::CorruptedData)
+	}
+}
@@ -0,0 +1,146 @@
+// Copyright 2019 The vault713 Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::{Arc, Mutex};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+/// Progress of a restore or check scan over the output PMMR
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ScanProgress {
+	/// Whether a scan is currently running
+	pub running: bool,
+	/// PMMR index the scan started (or resumed) from
+	pub start_index: u64,
+	/// Last PMMR index that has been scanned
+	pub last_index: u64,
+	/// Highest PMMR index reported by the node
+	pub highest_index: u64,
+	/// Percentage of the PMMR that has been scanned
+	pub percentage: u8,
+	/// Estimated number of seconds until the scan completes
+	pub eta: Option<u64>,
+}
+
+/// Where a restore or check starts scanning the output PMMR
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum ScanStart {
+	/// Continue an interrupted scan. Otherwise a check starts after the last
+	/// fully scanned index and a restore starts from the first output
+	Resume,
+	/// Start at the first output of a block height
+	Height(u64),
+	/// Start at a PMMR index
+	Index(u64),
+}
+
+impl ScanStart {
+	/// Scan the full output PMMR
+	pub fn full() -> Self {
+		ScanStart::Index(1)
+	}
+}
+
+lazy_static! {
+	static ref SCAN_MONITOR: ScanMonitor = ScanMonitor::new();
+}
+
+/// Shared handle to follow and cancel a running scan from other threads
+#[derive(Clone)]
+pub struct ScanMonitor {
+	progress: Arc<Mutex<ScanProgress>>,
+	started_at: Arc<Mutex<Option<Instant>>>,
+	cancelled: Arc<AtomicBool>,
+}
+
+impl ScanMonitor {
+	pub fn new() -> Self {
+		Self {
+			progress: Arc::new(Mutex::new(ScanProgress::default())),
+			started_at: Arc::new(Mutex::new(None)),
+			cancelled: Arc::new(AtomicBool::new(false)),
+		}
+	}
+
+	/// Monitor shared by all APIs in this process. It lives outside of the
+	/// wallet container, so it can be read while a scan holds the container lock
+	pub fn global() -> Self {
+		SCAN_MONITOR.clone()
+	}
+
+	/// Mark the start of a scan at the given PMMR index
+	pub fn start(&self, start_index: u64) {
+		self.cancelled.store(false, Ordering::SeqCst);
+		*self.started_at.lock() = Some(Instant::now());
+		*self.progress.lock() = ScanProgress {
+			running: true,
+			start_index,
+			last_index: start_index.saturating_sub(1),
+			highest_index: 0,
+			percentage: 0,
+			eta: None,
+		};
+	}
+
+	/// Record that the scan has reached `last_index` and return the updated progress
+	pub fn update(&self, last_index: u64, highest_index: u64) -> ScanProgress {
+		let elapsed = self
+			.started_at
+			.lock()
+			.map(|s| s.elapsed().as_secs())
+			.unwrap_or(0);
+		let mut progress = self.progress.lock();
+		progress.last_index = last_index;
+		progress.highest_index = highest_index;
+		progress.percentage = match highest_index {
+			0 => 100,
+			h => (last_index.min(h) * 100 / h) as u8,
+		};
+		let scanned = last_index.saturating_sub(progress.start_index) + 1;
+		let remaining = highest_index.saturating_sub(last_index);
+		progress.eta = match elapsed {
+			0 => None,
+			e => Some(e * remaining / scanned),
+		};
+		progress.clone()
+	}
+
+	/// Mark the scan as finished, whether it completed or not
+	pub fn finish(&self) {
+		*self.started_at.lock() = None;
+		let mut progress = self.progress.lock();
+		progress.running = false;
+		progress.eta = None;
+	}
+
+	/// Request the running scan to stop after the current batch.
+	/// Returns `false` if no scan was running
+	pub fn cancel(&self) -> bool {
+		let running = self.progress.lock().running;
+		if running {
+			self.cancelled.store(true, Ordering::SeqCst);
+		}
+		running
+	}
+
+	pub fn is_cancelled(&self) -> bool {
+		self.cancelled.load(Ordering::SeqCst)
+	}
+
+	pub fn progress(&self) -> ScanProgress {
+		self.progress.lock().clone()
+	}
+}
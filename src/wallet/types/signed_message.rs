@@ -0,0 +1,26 @@
+// Copyright 2019 The vault713 Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use serde::{Deserialize, Serialize};
+
+/// Message signed with the key of a grinbox address, proving control of it
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SignedMessage {
+	/// The grinbox address that signed
+	pub address: String,
+	/// The message as it was signed
+	pub message: String,
+	/// Hex encoded DER signature
+	pub signature: String,
+}
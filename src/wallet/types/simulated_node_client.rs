@@ -0,0 +1,456 @@
+// Copyright 2019 The vault713 Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{CbData, NodeClient, NodeVersionInfo, TxWrapper};
+use crate::common::{Arc, Mutex};
+use crate::wallet::ErrorKind;
+use failure::Error;
+use grin_core::core::transaction::Weighting;
+use grin_core::core::verifier_cache::LruVerifierCache;
+use grin_core::core::{Transaction, TxKernel};
+use grin_core::ser;
+use grin_util::secp::pedersen::{Commitment, RangeProof};
+use grin_util::{from_hex, to_hex, RwLock};
+use std::collections::HashMap;
+
+/// Position in the output MMR of the n-th (1-based) inserted output
+fn insertion_to_pmmr_index(n: u64) -> u64 {
+	let n = n - 1;
+	2 * n - n.count_ones() as u64 + 1
+}
+
+struct ChainOutput {
+	commit: Commitment,
+	proof: RangeProof,
+	is_coinbase: bool,
+	height: u64,
+	mmr_index: u64,
+	spent: bool,
+}
+
+#[derive(Default)]
+struct SimulatedChain {
+	height: u64,
+	outputs: Vec<ChainOutput>,
+	kernels: Vec<(TxKernel, u64)>,
+	/// Output MMR size at every height
+	mmr_sizes: Vec<u64>,
+	pool: Vec<Transaction>,
+}
+
+impl SimulatedChain {
+	fn new() -> Self {
+		Self {
+			mmr_sizes: vec![0],
+			..Default::default()
+		}
+	}
+
+	fn mmr_size(&self) -> u64 {
+		self.outputs.last().map(|o| o.mmr_index).unwrap_or(0)
+	}
+
+	fn unspent(&self, commit: &Commitment) -> Option<&ChainOutput> {
+		self.outputs
+			.iter()
+			.find(|o| !o.spent && o.commit == *commit)
+	}
+
+	fn add_output(&mut self, commit: Commitment, proof: RangeProof, is_coinbase: bool) {
+		let mmr_index = insertion_to_pmmr_index(self.outputs.len() as u64 + 1);
+		self.outputs.push(ChainOutput {
+			commit,
+			proof,
+			is_coinbase,
+			height: self.height,
+			mmr_index,
+			spent: false,
+		});
+	}
+}
+
+/// Node client backed by an in-memory chain, to test wallets without a grin node.
+/// Clones share the same chain, so several wallets in one process can transact
+/// with each other. Transactions are collected in a pool until a block is mined
+#[derive(Clone)]
+pub struct SimulatedNodeClient {
+	node_url: String,
+	chain: Arc<Mutex<SimulatedChain>>,
+}
+
+impl SimulatedNodeClient {
+	pub fn new() -> Self {
+		Self {
+			node_url: String::from("simulated"),
+			chain: Arc::new(Mutex::new(SimulatedChain::new())),
+		}
+	}
+
+	/// Sum of the fees of all transactions waiting to be mined, which the
+	/// coinbase of the next block has to include
+	pub fn pending_fees(&self) -> u64 {
+		self.chain.lock().pool.iter().map(|tx| tx.fee()).sum()
+	}
+
+	/// Mine a block with all pooled transactions and an optional coinbase,
+	/// as built by the foreign API. Returns the new chain height
+	pub fn mine_block(&self, coinbase: Option<&CbData>) -> u64 {
+		let mut chain = self.chain.lock();
+		chain.height += 1;
+		let pool: Vec<Transaction> = chain.pool.drain(..).collect();
+		for tx in pool {
+			for input in tx.inputs() {
+				if let Some(o) = chain
+					.outputs
+					.iter_mut()
+					.find(|o| !o.spent && o.commit == input.commit)
+				{
+					o.spent = true;
+				}
+			}
+			for output in tx.outputs() {
+				chain.add_output(output.commit, output.proof, false);
+			}
+			let height = chain.height;
+			for kernel in tx.kernels() {
+				chain.kernels.push((kernel.clone(), height));
+			}
+		}
+		if let Some(cb) = coinbase {
+			chain.add_output(cb.output.commit, cb.output.proof, true);
+			let height = chain.height;
+			chain.kernels.push((cb.kernel.clone(), height));
+		}
+		let size = chain.mmr_size();
+		chain.mmr_sizes.push(size);
+		chain.height
+	}
+
+	/// Mine a number of empty blocks
+	pub fn mine_blocks(&self, count: u64) -> u64 {
+		for _ in 0..count {
+			self.mine_block(None);
+		}
+		self.chain.lock().height
+	}
+
+	/// Kernels in the chain, with the height they were included at
+	pub fn kernels(&self) -> Vec<(TxKernel, u64)> {
+		self.chain.lock().kernels.clone()
+	}
+}
+
+impl NodeClient for SimulatedNodeClient {
+	fn node_url(&self) -> &str {
+		&self.node_url
+	}
+
+	fn set_node_url(&mut self, node_url: &str) {
+		self.node_url = node_url.to_owned();
+	}
+
+	fn node_api_secret(&self) -> Option<String> {
+		None
+	}
+
+	fn set_node_api_secret(&mut self, _node_api_secret: Option<String>) {}
+
+	fn get_version_info(&mut self) -> Option<NodeVersionInfo> {
+		Some(NodeVersionInfo {
+			node_version: "3.0.0".into(),
+			block_header_version: 2,
+			verified: Some(true),
+		})
+	}
+
+	/// Validate a transaction and add it to the pool
+	fn post_tx(&self, tx: &TxWrapper, _fluff: bool) -> Result<(), Error> {
+		let tx_bin = from_hex(tx.tx_hex.clone())
+			.map_err(|e| ErrorKind::ClientCallback(format!("Invalid transaction hex: {}", e)))?;
+		let tx: Transaction = ser::deserialize(&mut &tx_bin[..], ser::ProtocolVersion(1))
+			.map_err(|e| ErrorKind::ClientCallback(format!("Invalid transaction: {}", e)))?;
+		let verifier_cache = Arc::new(RwLock::new(LruVerifierCache::new()));
+		tx.validate(Weighting::AsTransaction, verifier_cache)
+			.map_err(|e| ErrorKind::ClientCallback(format!("Invalid transaction: {}", e)))?;
+
+		let mut chain = self.chain.lock();
+		for input in tx.inputs() {
+			let pooled = chain
+				.pool
+				.iter()
+				.any(|p| p.inputs().iter().any(|i| i.commit == input.commit));
+			if pooled || chain.unspent(&input.commit).is_none() {
+				return Err(ErrorKind::ClientCallback(format!(
+					"Input {} is not spendable",
+					to_hex(input.commit.0.to_vec())
+				))
+				.into());
+			}
+		}
+		chain.pool.push(tx);
+		Ok(())
+	}
+
+	fn get_chain_height(&self) -> Result<u64, Error> {
+		Ok(self.chain.lock().height)
+	}
+
+	fn get_outputs_from_node(
+		&self,
+		wallet_outputs: Vec<Commitment>,
+	) -> Result<HashMap<Commitment, (String, u64, u64)>, Error> {
+		let chain = self.chain.lock();
+		let mut api_outputs = HashMap::new();
+		for commit in wallet_outputs {
+			if let Some(o) = chain.unspent(&commit) {
+				api_outputs.insert(commit, (to_hex(commit.0.to_vec()), o.height, o.mmr_index));
+			}
+		}
+		Ok(api_outputs)
+	}
+
+	fn get_outputs_by_pmmr_index(
+		&self,
+		start_height: u64,
+		max_outputs: u64,
+	) -> Result<(u64, u64, Vec<(Commitment, RangeProof, bool, u64, u64)>), Error> {
+		let chain = self.chain.lock();
+		let highest_index = chain.mmr_size();
+		let outputs: Vec<(Commitment, RangeProof, bool, u64, u64)> = chain
+			.outputs
+			.iter()
+			.filter(|o| !o.spent && o.mmr_index >= start_height)
+			.take(max_outputs as usize)
+			.map(|o| (o.commit, o.proof, o.is_coinbase, o.height, o.mmr_index))
+			.collect();
+		let last_retrieved_index = outputs.last().map(|o| o.4).unwrap_or(highest_index);
+		Ok((highest_index, last_retrieved_index, outputs))
+	}
+
+	fn height_range_to_pmmr_indices(
+		&self,
+		start_height: u64,
+		end_height: Option<u64>,
+	) -> Result<(u64, u64), Error> {
+		let chain = self.chain.lock();
+		let end_height = end_height.unwrap_or(chain.height).min(chain.height);
+		let start_index = match start_height {
+			0 => 0,
+			h => chain.mmr_sizes[(h - 1).min(chain.height) as usize] + 1,
+		};
+		Ok((start_index, chain.mmr_sizes[end_height as usize]))
+	}
+
+	fn get_kernel(
+		&self,
+		excess: &Commitment,
+		min_height: Option<u64>,
+		max_height: Option<u64>,
+	) -> Result<Option<(TxKernel, u64, u64)>, Error> {
+		let chain = self.chain.lock();
+		let min_height = min_height.unwrap_or(0);
+		let max_height = max_height.unwrap_or(chain.height);
+		let kernel = chain
+			.kernels
+			.iter()
+			.enumerate()
+			.find(|(_, (k, h))| &k.excess == excess && *h >= min_height && *h <= max_height)
+			.map(|(i, (k, h))| (k.clone(), *h, insertion_to_pmmr_index(i as u64 + 1)));
+		Ok(kernel)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::common::config::{Wallet713Config, WalletConfig};
+	use crate::contacts::{self, AddressBook};
+	use crate::wallet::api::{Foreign, Owner};
+	use crate::wallet::types::{BlockFees, InitTxArgs, Slate, TxLogEntryType, WalletInfo};
+	use crate::wallet::{Backend, Container};
+	use grin_core::consensus::{GRIN_BASE, REWARD};
+	use grin_core::global::{self, ChainTypes};
+	use grin_keychain::ExtKeychain;
+	use grin_util::ZeroingString;
+	use std::env;
+	use std::fs;
+	use std::path::PathBuf;
+	use uuid::Uuid;
+
+	type TestBackend = Backend<SimulatedNodeClient, ExtKeychain>;
+
+	/// Wallet in a temporary directory, removed when dropped
+	struct TestWallet {
+		owner: Owner<TestBackend, SimulatedNodeClient, ExtKeychain>,
+		foreign: Foreign<TestBackend, SimulatedNodeClient, ExtKeychain>,
+		dir: PathBuf,
+	}
+
+	impl TestWallet {
+		fn new(client: &SimulatedNodeClient) -> Self {
+			let dir = env::temp_dir().join(format!("wallet713-test-{}", Uuid::new_v4()));
+			let wallet_dir = dir.join("wallet");
+			let contacts_dir = dir.join("contacts");
+			let config = WalletConfig {
+				data_file_dir: wallet_dir.to_string_lossy().into_owned(),
+				..WalletConfig::default()
+			};
+			let backend = Backend::new(&config, client.clone()).unwrap();
+			let contacts = contacts::Backend::new(contacts_dir.to_str().unwrap()).unwrap();
+			let address_book = AddressBook::new(Box::new(contacts)).unwrap();
+			let mut config = Wallet713Config::default(&Some(ChainTypes::AutomatedTesting)).unwrap();
+			config.wallet713_data_path = wallet_dir.to_string_lossy().into_owned();
+			let container = Container::new(config, backend, address_book);
+
+			let owner = Owner::new(container.clone());
+			owner
+				.set_seed(None, ZeroingString::from("password"), false)
+				.unwrap();
+			owner.connect().unwrap();
+			let foreign = Foreign::new(container);
+			Self {
+				owner,
+				foreign,
+				dir,
+			}
+		}
+
+		/// Wallet with a single mature coinbase output
+		fn funded(client: &SimulatedNodeClient) -> Self {
+			let wallet = Self::new(client);
+			wallet.mine(client);
+			client.mine_blocks(global::coinbase_maturity());
+			wallet.refresh();
+			wallet
+		}
+
+		/// Mine a block paying the coinbase and the fees of pooled transactions to us
+		fn mine(&self, client: &SimulatedNodeClient) {
+			let block_fees = BlockFees {
+				fees: client.pending_fees(),
+				height: client.get_chain_height().unwrap() + 1,
+				key_id: None,
+			};
+			let cb = self.foreign.build_coinbase(&block_fees).unwrap();
+			client.mine_block(Some(&cb));
+		}
+
+		fn refresh(&self) -> WalletInfo {
+			let (validated, info) = self.owner.retrieve_summary_info(true, 1).unwrap();
+			assert!(validated);
+			info
+		}
+
+		fn confirmed(&self, slate: &Slate, tx_type: TxLogEntryType) -> bool {
+			let (_, _, txs, _, _) = self
+				.owner
+				.retrieve_txs(false, false, false, None, Some(slate.id))
+				.unwrap();
+			txs.iter().any(|t| t.tx_type == tx_type && t.confirmed)
+		}
+
+		fn post(&self, slate: &Slate) -> Result<(), Error> {
+			self.owner.post_tx(&slate.tx, false)
+		}
+	}
+
+	impl Drop for TestWallet {
+		fn drop(&mut self) {
+			let _ = self.owner.disconnect();
+			let _ = fs::remove_dir_all(&self.dir);
+		}
+	}
+
+	/// Run a send from one wallet to the other up to the finalized slate
+	fn send(sender: &TestWallet, receiver: &TestWallet, amount: u64) -> Slate {
+		let args = InitTxArgs {
+			amount,
+			minimum_confirmations: 1,
+			..Default::default()
+		};
+		let slate = sender.owner.init_send_tx(args).unwrap();
+		sender.owner.tx_lock_outputs(&slate, 0, None).unwrap();
+		let slate = receiver
+			.foreign
+			.receive_tx(&slate, None, None, None, None)
+			.unwrap();
+		sender.owner.finalize_tx(&slate, None).unwrap()
+	}
+
+	#[test]
+	fn send_receive_finalize_confirm() {
+		global::set_mining_mode(ChainTypes::AutomatedTesting);
+		let client = SimulatedNodeClient::new();
+		let sender = TestWallet::funded(&client);
+		let receiver = TestWallet::new(&client);
+
+		let amount = 10 * GRIN_BASE;
+		let slate = send(&sender, &receiver, amount);
+		assert!(slate.receiver_proof.is_some());
+		sender.post(&slate).unwrap();
+		sender.mine(&client);
+
+		let excess = slate.tx.kernels()[0].excess;
+		let height = client.get_chain_height().unwrap();
+		assert!(client
+			.kernels()
+			.iter()
+			.any(|(k, h)| k.excess == excess && *h == height));
+
+		assert_eq!(receiver.refresh().total, amount);
+		assert!(receiver.confirmed(&slate, TxLogEntryType::TxReceived));
+
+		// The fee comes back with the coinbase of the block that included the transaction
+		assert_eq!(sender.refresh().total, 2 * REWARD - amount);
+		assert!(sender.confirmed(&slate, TxLogEntryType::TxSent));
+	}
+
+	#[test]
+	fn unconfirmed_until_mined() {
+		global::set_mining_mode(ChainTypes::AutomatedTesting);
+		let client = SimulatedNodeClient::new();
+		let sender = TestWallet::funded(&client);
+		let receiver = TestWallet::new(&client);
+
+		let amount = 5 * GRIN_BASE;
+		let slate = send(&sender, &receiver, amount);
+		sender.post(&slate).unwrap();
+
+		let info = receiver.refresh();
+		assert_eq!(info.total, 0);
+		assert_eq!(info.amount_awaiting_finalization, amount);
+		assert!(!receiver.confirmed(&slate, TxLogEntryType::TxReceived));
+		assert_eq!(sender.refresh().amount_locked, REWARD);
+		assert!(!sender.confirmed(&slate, TxLogEntryType::TxSent));
+
+		client.mine_block(None);
+		assert_eq!(receiver.refresh().total, amount);
+		assert_eq!(sender.refresh().amount_locked, 0);
+	}
+
+	#[test]
+	fn post_rejects_double_spend() {
+		global::set_mining_mode(ChainTypes::AutomatedTesting);
+		let client = SimulatedNodeClient::new();
+		let sender = TestWallet::funded(&client);
+		let receiver = TestWallet::new(&client);
+
+		let slate = send(&sender, &receiver, GRIN_BASE);
+		sender.post(&slate).unwrap();
+		assert!(sender.post(&slate).is_err());
+
+		client.mine_block(None);
+		assert!(sender.post(&slate).is_err());
+	}
+}
@@ -0,0 +1,329 @@
+// Copyright 2019 The vault713 Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Armored slates: a compact binary slate, base58 encoded with a checksum
+//! and framed, so it can be pasted into any chat. The slate can be encrypted
+//! to the grinbox address of the other party.
+//!
+//! Encrypted payload: sender public key (33) | salt (8) | nonce (12) | sealed slate
+
+use super::slate::{ParticipantData, PaymentInfo, ReceiverProof, Slate, VersionCompatInfo};
+use crate::common::base58::{FromBase58, ToBase58};
+use crate::common::message::{open, seal, shared_key};
+use crate::wallet::ErrorKind;
+use failure::Error;
+use grin_core::core::Transaction;
+use grin_core::ser::{self, Readable, Reader, Writeable, Writer};
+use grin_util::secp::key::{PublicKey, SecretKey};
+use grin_util::secp::Signature;
+use grin_util::static_secp_instance;
+use rand::{thread_rng, Rng};
+use uuid::Uuid;
+
+const BEGIN_SLATE: &'static str = "-----BEGIN GRIN SLATE-----";
+const END_SLATE: &'static str = "-----END GRIN SLATE-----";
+const BEGIN_ENCRYPTED_SLATE: &'static str = "-----BEGIN ENCRYPTED GRIN SLATE-----";
+const END_ENCRYPTED_SLATE: &'static str = "-----END ENCRYPTED GRIN SLATE-----";
+
+/// Version byte of the base58 check encoding
+const ARMOR_VERSION: u8 = 1;
+const LINE_WIDTH: usize = 64;
+const PUBLIC_KEY_SIZE: usize = 33;
+const SALT_SIZE: usize = 8;
+const NONCE_SIZE: usize = 12;
+
+fn invalid(reason: &str) -> Error {
+	ErrorKind::ArmoredSlate(reason.to_owned()).into()
+}
+
+/// Whether the text looks like an armored slate, rather than a JSON slate
+pub fn is_armored(text: &str) -> bool {
+	let text = text.trim_start();
+	text.starts_with(BEGIN_SLATE) || text.starts_with(BEGIN_ENCRYPTED_SLATE)
+}
+
+/// Whether the text is the last line of an armored slate
+pub fn is_armor_end(line: &str) -> bool {
+	let line = line.trim();
+	line == END_SLATE || line == END_ENCRYPTED_SLATE
+}
+
+/// Armor a slate. If a recipient public key is given, the slate is encrypted
+/// with the shared secret of the recipient and the sender secret key
+pub fn armor(slate: &Slate, encrypt: Option<(&PublicKey, &SecretKey)>) -> Result<String, Error> {
+	let data = ser::ser_vec(slate, ser::ProtocolVersion(1))?;
+	let (payload, begin, end) = match encrypt {
+		Some((recipient, secret_key)) => {
+			let secp = static_secp_instance();
+			let secp = secp.lock();
+			let sender = PublicKey::from_secret_key(&secp, secret_key)?;
+
+			let salt: [u8; SALT_SIZE] = thread_rng().gen();
+			let nonce: [u8; NONCE_SIZE] = thread_rng().gen();
+			let key = shared_key(recipient, secret_key, &salt)?;
+
+			let mut payload = sender.serialize_vec(&secp, true).to_vec();
+			payload.extend_from_slice(&salt);
+			payload.extend_from_slice(&nonce);
+			payload.extend(seal(&key, &nonce, data)?);
+			(payload, BEGIN_ENCRYPTED_SLATE, END_ENCRYPTED_SLATE)
+		}
+		None => (data, BEGIN_SLATE, END_SLATE),
+	};
+
+	let encoded = payload.to_base58_check(vec![ARMOR_VERSION]);
+	let mut armored = String::from(begin);
+	armored.push('\n');
+	for line in encoded.as_bytes().chunks(LINE_WIDTH) {
+		// Base58 is pure ascii
+		armored.push_str(std::str::from_utf8(line).unwrap());
+		armored.push('\n');
+	}
+	armored.push_str(end);
+	armored.push('\n');
+	Ok(armored)
+}
+
+/// Read an armored slate. Encrypted slates are decrypted with the secret key,
+/// in which case the public key of the sender is returned as well
+pub fn dearmor(
+	armored: &str,
+	secret_key: Option<&SecretKey>,
+) -> Result<(Slate, Option<PublicKey>), Error> {
+	let mut lines = armored.lines().map(|l| l.trim()).filter(|l| !l.is_empty());
+	let (encrypted, end) = match lines.next() {
+		Some(BEGIN_SLATE) => (false, END_SLATE),
+		Some(BEGIN_ENCRYPTED_SLATE) => (true, END_ENCRYPTED_SLATE),
+		_ => return Err(invalid("missing begin line")),
+	};
+
+	let mut encoded = String::new();
+	let mut complete = false;
+	for line in lines {
+		if line == end {
+			complete = true;
+			break;
+		}
+		encoded.push_str(line);
+	}
+	if !complete {
+		return Err(invalid("missing end line"));
+	}
+
+	let (version, payload) = encoded
+		.from_base58_check(1)
+		.map_err(|e| ErrorKind::ArmoredSlate(e.to_string()))?;
+	if version != vec![ARMOR_VERSION] {
+		return Err(invalid("unknown version"));
+	}
+
+	let (data, sender) = if encrypted {
+		let secret_key = secret_key.ok_or(invalid("slate is encrypted"))?;
+		if payload.len() < PUBLIC_KEY_SIZE + SALT_SIZE + NONCE_SIZE {
+			return Err(invalid("payload too short"));
+		}
+		let (sender, rest) = payload.split_at(PUBLIC_KEY_SIZE);
+		let (salt, rest) = rest.split_at(SALT_SIZE);
+		let (nonce, sealed) = rest.split_at(NONCE_SIZE);
+
+		let sender = {
+			let secp = static_secp_instance();
+			let secp = secp.lock();
+			PublicKey::from_slice(&secp, sender).map_err(|_| invalid("invalid sender key"))?
+		};
+		let key = shared_key(&sender, secret_key, salt)?;
+		let data = open(&key, nonce, sealed.to_vec())
+			.map_err(|_| invalid("unable to decrypt, the slate is meant for another address"))?;
+		(data, Some(sender))
+	} else {
+		(payload, None)
+	};
+
+	let slate = ser::deserialize(&mut &data[..], ser::ProtocolVersion(1))
+		.map_err(|e| ErrorKind::ArmoredSlate(e.to_string()))?;
+	Ok((slate, sender))
+}
+
+fn write_option<W, T, F>(writer: &mut W, value: &Option<T>, f: F) -> Result<(), ser::Error>
+where
+	W: Writer,
+	F: Fn(&mut W, &T) -> Result<(), ser::Error>,
+{
+	match value {
+		Some(v) => {
+			writer.write_u8(1)?;
+			f(writer, v)
+		}
+		None => writer.write_u8(0),
+	}
+}
+
+fn read_option<T, F>(reader: &mut dyn Reader, f: F) -> Result<Option<T>, ser::Error>
+where
+	F: Fn(&mut dyn Reader) -> Result<T, ser::Error>,
+{
+	match reader.read_u8()? {
+		0 => Ok(None),
+		1 => Ok(Some(f(reader)?)),
+		_ => Err(ser::Error::CorruptedData),
+	}
+}
+
+fn write_string<W: Writer>(writer: &mut W, value: &String) -> Result<(), ser::Error> {
+	writer.write_bytes(&value.as_bytes())
+}
+
+fn read_string(reader: &mut dyn Reader) -> Result<String, ser::Error> {
+	String::from_utf8(reader.read_bytes_len_prefix()?).map_err(|_| ser::Error::CorruptedData)
+}
+
+fn write_public_key<W: Writer>(writer: &mut W, key: &PublicKey) -> Result<(), ser::Error> {
+	let secp = static_secp_instance();
+	let secp = secp.lock();
+	writer.write_fixed_bytes(&key.serialize_vec(&secp, true).to_vec())
+}
+
+fn read_public_key(reader: &mut dyn Reader) -> Result<PublicKey, ser::Error> {
+	let bytes = reader.read_fixed_bytes(PUBLIC_KEY_SIZE)?;
+	let secp = static_secp_instance();
+	let secp = secp.lock();
+	PublicKey::from_slice(&secp, &bytes).map_err(|_| ser::Error::CorruptedData)
+}
+
+impl Writeable for ParticipantData {
+	fn write<W: Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		writer.write_u64(self.id)?;
+		write_public_key(writer, &self.public_blind_excess)?;
+		write_public_key(writer, &self.public_nonce)?;
+		write_option(writer, &self.part_sig, |w, s| s.write(w))?;
+		write_option(writer, &self.message, write_string)?;
+		write_option(writer, &self.message_sig, |w, s| s.write(w))
+	}
+}
+
+impl Readable for ParticipantData {
+	fn read(reader: &mut dyn Reader) -> Result<ParticipantData, ser::Error> {
+		Ok(ParticipantData {
+			id: reader.read_u64()?,
+			public_blind_excess: read_public_key(reader)?,
+			public_nonce: read_public_key(reader)?,
+			part_sig: read_option(reader, |r| Signature::read(r))?,
+			message: read_option(reader, read_string)?,
+			message_sig: read_option(reader, |r| Signature::read(r))?,
+		})
+	}
+}
+
+impl Writeable for PaymentInfo {
+	fn write<W: Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		write_string(writer, &self.sender_address)?;
+		write_string(writer, &self.receiver_address)?;
+		write_option(writer, &self.receiver_signature, write_string)
+	}
+}
+
+impl Readable for PaymentInfo {
+	fn read(reader: &mut dyn Reader) -> Result<PaymentInfo, ser::Error> {
+		Ok(PaymentInfo {
+			sender_address: read_string(reader)?,
+			receiver_address: read_string(reader)?,
+			receiver_signature: read_option(reader, read_string)?,
+		})
+	}
+}
+
+impl Writeable for ReceiverProof {
+	fn write<W: Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		write_string(writer, &self.sender_address)?;
+		write_string(writer, &self.receiver_address)?;
+		write_option(writer, &self.receiver_signature, write_string)
+	}
+}
+
+impl Readable for ReceiverProof {
+	fn read(reader: &mut dyn Reader) -> Result<ReceiverProof, ser::Error> {
+		Ok(ReceiverProof {
+			sender_address: read_string(reader)?,
+			receiver_address: read_string(reader)?,
+			receiver_signature: read_option(reader, read_string)?,
+		})
+	}
+}
+
+impl Writeable for Slate {
+	fn write<W: Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		writer.write_u16(self.version_info.version)?;
+		writer.write_u16(self.version_info.orig_version)?;
+		writer.write_u16(self.version_info.block_header_version)?;
+		writer.write_u8(self.num_participants as u8)?;
+		writer.write_fixed_bytes(&self.id.as_bytes().to_vec())?;
+		writer.write_u64(self.amount)?;
+		writer.write_u64(self.fee)?;
+		writer.write_u64(self.height)?;
+		writer.write_u64(self.lock_height)?;
+		write_option(writer, &self.ttl_cutoff_height, |w, h| w.write_u64(*h))?;
+		self.tx.write(writer)?;
+		writer.write_u8(self.participant_data.len() as u8)?;
+		for p in &self.participant_data {
+			p.write(writer)?;
+		}
+		write_option(writer, &self.payment_proof, |w, p| p.write(w))?;
+		write_option(writer, &self.coin_type, write_string)?;
+		write_option(writer, &self.receiver_proof, |w, p| p.write(w))
+	}
+}
+
+impl Readable for Slate {
+	fn read(reader: &mut dyn Reader) -> Result<Slate, ser::Error> {
+		let version_info = VersionCompatInfo {
+			version: reader.read_u16()?,
+			orig_version: reader.read_u16()?,
+			block_header_version: reader.read_u16()?,
+		};
+		let num_participants = reader.read_u8()? as usize;
+		let id = Uuid::from_bytes(&reader.read_fixed_bytes(16)?)
+			.map_err(|_| ser::Error::CorruptedData)?;
+		let amount = reader.read_u64()?;
+		let fee = reader.read_u64()?;
+		let height = reader.read_u64()?;
+		let lock_height = reader.read_u64()?;
+		let ttl_cutoff_height = read_option(reader, |r| r.read_u64())?;
+		let tx = Transaction::read(reader)?;
+		let count = reader.read_u8()?;
+		let mut participant_data = Vec::with_capacity(count as usize);
+		for _ in 0..count {
+			participant_data.push(ParticipantData::read(reader)?);
+		}
+		let payment_proof = read_option(reader, |r| PaymentInfo::read(r))?;
+		let coin_type = read_option(reader, read_string)?;
+		let receiver_proof = read_option(reader, |r| ReceiverProof::read(r))?;
+
+		Ok(Slate {
+			version_info,
+			num_participants,
+			id,
+			tx,
+			amount,
+			fee,
+			height,
+			lock_height,
+			ttl_cutoff_height,
+			participant_data,
+			payment_proof,
+			coin_type,
+			rejection: None,
+			receiver_proof,
+		})
+	}
+}
@@ -12,10 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod armor;
 pub mod slate;
 pub mod versions;
 
-pub use self::slate::Slate;
+pub use self::slate::{ReceiverProof, Slate};
 pub use self::versions::{
 	SlateVersion, VersionedSlate, CURRENT_SLATE_VERSION, GRIN_BLOCK_HEADER_VERSION,
+	MIN_SLATE_VERSION,
 };
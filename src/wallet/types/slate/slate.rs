@@ -16,6 +16,7 @@
 //! around during an interactive wallet exchange
 
 use super::versions::v2::*;
+use super::versions::v3::{opt_string_or_u64, PaymentInfoV3, SlateV3};
 use super::versions::{CompatKernelFeatures, CURRENT_SLATE_VERSION, GRIN_BLOCK_HEADER_VERSION};
 use crate::wallet::ErrorKind;
 use blake2_rfc::blake2b::blake2b;
@@ -135,10 +136,53 @@ pub struct Slate {
 	/// Lock height
 	#[serde(with = "secp_ser::string_or_u64")]
 	pub lock_height: u64,
+	/// TTL, the block height at which wallets
+	/// should refuse to process the transaction and unlock all
+	/// associated outputs
+	#[serde(default)]
+	#[serde(with = "opt_string_or_u64")]
+	pub ttl_cutoff_height: Option<u64>,
 	/// Participant data, each participant in the transaction will
 	/// insert their public data here. For now, 0 is sender and 1
 	/// is receiver, though this will change for multi-party
 	pub participant_data: Vec<ParticipantData>,
+	/// Payment proof
+	#[serde(default)]
+	pub payment_proof: Option<PaymentInfo>,
+	/// Coin the slate is for, only set by wallets that handle several coins
+	#[serde(default)]
+	pub coin_type: Option<String>,
+	/// Reason the receiver gave for rejecting the slate. Only understood
+	/// by wallet713, other wallets ignore it
+	#[serde(default)]
+	pub rejection: Option<String>,
+	/// Payment proof signed with the receiver grinbox address key. Only
+	/// understood by wallet713, other wallets ignore it
+	#[serde(default)]
+	pub receiver_proof: Option<ReceiverProof>,
+}
+
+/// Addresses of both parties and the receiver signature of a payment proof
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PaymentInfo {
+	/// Hex encoded address of the sender
+	pub sender_address: String,
+	/// Hex encoded address of the receiver
+	pub receiver_address: String,
+	/// Hex encoded signature of the receiver
+	pub receiver_signature: Option<String>,
+}
+
+/// Grinbox addresses of both parties and the receiver signature of a
+/// wallet713 payment proof
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReceiverProof {
+	/// Grinbox public key of the sender
+	pub sender_address: String,
+	/// Grinbox public key of the receiver, empty until it signs
+	pub receiver_address: String,
+	/// Hex encoded signature of the receiver
+	pub receiver_signature: Option<String>,
 }
 
 /// Versioning and compatibility info about this slate
@@ -170,7 +214,12 @@ impl Slate {
 			fee: 0,
 			height: 0,
 			lock_height: 0,
+			ttl_cutoff_height: None,
 			participant_data: vec![],
+			payment_proof: None,
+			coin_type: None,
+			rejection: None,
+			receiver_proof: None,
 			version_info: VersionCompatInfo {
 				version: CURRENT_SLATE_VERSION,
 				orig_version: CURRENT_SLATE_VERSION,
@@ -601,9 +650,17 @@ impl Serialize for Slate {
 	{
 		use serde::ser::Error;
 
-		let v2 = SlateV2::from(self);
 		match self.version_info.orig_version {
-			2 => v2.serialize(serializer),
+			3 => {
+				let mut v3 = SlateV3::from(self);
+				v3.version_info.version = 3;
+				v3.serialize(serializer)
+			}
+			2 => {
+				let mut v2 = SlateV2::from(self);
+				v2.version_info.version = 2;
+				v2.serialize(serializer)
+			}
 			v => Err(S::Error::custom(format!("Unknown slate version {}", v))),
 		}
 	}
@@ -624,10 +681,14 @@ impl From<Slate> for SlateV2 {
 			lock_height,
 			participant_data,
 			version_info,
+			rejection,
+			receiver_proof,
+			..
 		} = slate;
 		let participant_data = map_vec!(participant_data, |data| ParticipantDataV2::from(data));
 		let version_info = VersionCompatInfoV2::from(&version_info);
 		let tx = TransactionV2::from(tx);
+		let receiver_proof = receiver_proof.as_ref().map(|p| ReceiverProofV2::from(p));
 		SlateV2 {
 			num_participants,
 			id,
@@ -638,6 +699,8 @@ impl From<Slate> for SlateV2 {
 			lock_height,
 			participant_data,
 			version_info,
+			rejection,
+			receiver_proof,
 		}
 	}
 }
@@ -654,6 +717,9 @@ impl From<&Slate> for SlateV2 {
 			lock_height,
 			participant_data,
 			version_info,
+			rejection,
+			receiver_proof,
+			..
 		} = slate;
 		let num_participants = *num_participants;
 		let id = *id;
@@ -664,6 +730,8 @@ impl From<&Slate> for SlateV2 {
 		let lock_height = *lock_height;
 		let participant_data = map_vec!(participant_data, |data| ParticipantDataV2::from(data));
 		let version_info = VersionCompatInfoV2::from(version_info);
+		let rejection = rejection.clone();
+		let receiver_proof = receiver_proof.as_ref().map(|p| ReceiverProofV2::from(p));
 		SlateV2 {
 			num_participants,
 			id,
@@ -674,6 +742,131 @@ impl From<&Slate> for SlateV2 {
 			lock_height,
 			participant_data,
 			version_info,
+			rejection,
+			receiver_proof,
+		}
+	}
+}
+
+impl From<Slate> for SlateV3 {
+	fn from(slate: Slate) -> SlateV3 {
+		let Slate {
+			num_participants,
+			id,
+			tx,
+			amount,
+			fee,
+			height,
+			lock_height,
+			ttl_cutoff_height,
+			participant_data,
+			version_info,
+			payment_proof,
+			coin_type,
+			rejection,
+			receiver_proof,
+		} = slate;
+		let participant_data = map_vec!(participant_data, |data| ParticipantDataV2::from(data));
+		let version_info = VersionCompatInfoV2::from(&version_info);
+		let tx = TransactionV2::from(tx);
+		let payment_proof = payment_proof.as_ref().map(|p| PaymentInfoV3::from(p));
+		let receiver_proof = receiver_proof.as_ref().map(|p| ReceiverProofV2::from(p));
+		SlateV3 {
+			num_participants,
+			id,
+			tx,
+			amount,
+			fee,
+			height,
+			lock_height,
+			ttl_cutoff_height,
+			participant_data,
+			version_info,
+			payment_proof,
+			coin_type,
+			rejection,
+			receiver_proof,
+		}
+	}
+}
+
+impl From<&Slate> for SlateV3 {
+	fn from(slate: &Slate) -> SlateV3 {
+		let Slate {
+			num_participants,
+			id,
+			tx,
+			amount,
+			fee,
+			height,
+			lock_height,
+			ttl_cutoff_height,
+			participant_data,
+			version_info,
+			payment_proof,
+			coin_type,
+			rejection,
+			receiver_proof,
+		} = slate;
+		let num_participants = *num_participants;
+		let id = *id;
+		let tx = TransactionV2::from(tx);
+		let amount = *amount;
+		let fee = *fee;
+		let height = *height;
+		let lock_height = *lock_height;
+		let ttl_cutoff_height = *ttl_cutoff_height;
+		let participant_data = map_vec!(participant_data, |data| ParticipantDataV2::from(data));
+		let version_info = VersionCompatInfoV2::from(version_info);
+		let payment_proof = payment_proof.as_ref().map(|p| PaymentInfoV3::from(p));
+		let coin_type = coin_type.clone();
+		let rejection = rejection.clone();
+		let receiver_proof = receiver_proof.as_ref().map(|p| ReceiverProofV2::from(p));
+		SlateV3 {
+			num_participants,
+			id,
+			tx,
+			amount,
+			fee,
+			height,
+			lock_height,
+			ttl_cutoff_height,
+			participant_data,
+			version_info,
+			payment_proof,
+			coin_type,
+			rejection,
+			receiver_proof,
+		}
+	}
+}
+
+impl From<&PaymentInfo> for PaymentInfoV3 {
+	fn from(data: &PaymentInfo) -> PaymentInfoV3 {
+		let PaymentInfo {
+			sender_address,
+			receiver_address,
+			receiver_signature,
+		} = data;
+		PaymentInfoV3 {
+			sender_address: sender_address.clone(),
+			receiver_address: receiver_address.clone(),
+			receiver_signature: receiver_signature.clone(),
+		}
+	}
+}
+
+impl From<&ReceiverProof> for ReceiverProofV2 {
+	fn from(data: &ReceiverProof) -> ReceiverProofV2 {
+		let ReceiverProof {
+			sender_address,
+			receiver_address,
+			receiver_signature,
+		} = data;
+		ReceiverProofV2 {
+			sender_address: sender_address.clone(),
+			receiver_address: receiver_address.clone(),
+			receiver_signature: receiver_signature.clone(),
 		}
 	}
 }
@@ -818,10 +1011,13 @@ impl From<SlateV2> for Slate {
 			lock_height,
 			participant_data,
 			version_info,
+			rejection,
+			receiver_proof,
 		} = slate;
 		let participant_data = map_vec!(participant_data, |data| ParticipantData::from(data));
 		let version_info = VersionCompatInfo::from(&version_info);
 		let tx = Transaction::from(tx);
+		let receiver_proof = receiver_proof.as_ref().map(|p| ReceiverProof::from(p));
 		Slate {
 			num_participants,
 			id,
@@ -830,8 +1026,13 @@ impl From<SlateV2> for Slate {
 			fee,
 			height,
 			lock_height,
+			ttl_cutoff_height: None,
 			participant_data,
 			version_info,
+			payment_proof: None,
+			coin_type: None,
+			rejection,
+			receiver_proof,
 		}
 	}
 }
@@ -848,6 +1049,63 @@ impl From<&SlateV2> for Slate {
 			lock_height,
 			participant_data,
 			version_info,
+			rejection,
+			receiver_proof,
+		} = slate;
+		let num_participants = *num_participants;
+		let id = id.clone();
+		let tx = Transaction::from(tx);
+		let amount = *amount;
+		let fee = *fee;
+		let height = *height;
+		let lock_height = *lock_height;
+		let participant_data = map_vec!(participant_data, |data| ParticipantData::from(data));
+		let version_info = VersionCompatInfo::from(version_info);
+		let rejection = rejection.clone();
+		let receiver_proof = receiver_proof.as_ref().map(|p| ReceiverProof::from(p));
+
+		Slate {
+			num_participants,
+			id,
+			tx,
+			amount,
+			fee,
+			height,
+			lock_height,
+			ttl_cutoff_height: None,
+			participant_data,
+			version_info,
+			payment_proof: None,
+			coin_type: None,
+			rejection,
+			receiver_proof,
+		}
+	}
+}
+
+impl From<SlateV3> for Slate {
+	fn from(slate: SlateV3) -> Slate {
+		Slate::from(&slate)
+	}
+}
+
+impl From<&SlateV3> for Slate {
+	fn from(slate: &SlateV3) -> Slate {
+		let SlateV3 {
+			num_participants,
+			id,
+			tx,
+			amount,
+			fee,
+			height,
+			lock_height,
+			ttl_cutoff_height,
+			participant_data,
+			version_info,
+			payment_proof,
+			coin_type,
+			rejection,
+			receiver_proof,
 		} = slate;
 		let num_participants = *num_participants;
 		let id = id.clone();
@@ -856,8 +1114,13 @@ impl From<&SlateV2> for Slate {
 		let fee = *fee;
 		let height = *height;
 		let lock_height = *lock_height;
+		let ttl_cutoff_height = *ttl_cutoff_height;
 		let participant_data = map_vec!(participant_data, |data| ParticipantData::from(data));
 		let version_info = VersionCompatInfo::from(version_info);
+		let payment_proof = payment_proof.as_ref().map(|p| PaymentInfo::from(p));
+		let coin_type = coin_type.clone();
+		let rejection = rejection.clone();
+		let receiver_proof = receiver_proof.as_ref().map(|p| ReceiverProof::from(p));
 
 		Slate {
 			num_participants,
@@ -867,8 +1130,43 @@ impl From<&SlateV2> for Slate {
 			fee,
 			height,
 			lock_height,
+			ttl_cutoff_height,
 			participant_data,
 			version_info,
+			payment_proof,
+			coin_type,
+			rejection,
+			receiver_proof,
+		}
+	}
+}
+
+impl From<&PaymentInfoV3> for PaymentInfo {
+	fn from(data: &PaymentInfoV3) -> PaymentInfo {
+		let PaymentInfoV3 {
+			sender_address,
+			receiver_address,
+			receiver_signature,
+		} = data;
+		PaymentInfo {
+			sender_address: sender_address.clone(),
+			receiver_address: receiver_address.clone(),
+			receiver_signature: receiver_signature.clone(),
+		}
+	}
+}
+
+impl From<&ReceiverProofV2> for ReceiverProof {
+	fn from(data: &ReceiverProofV2) -> ReceiverProof {
+		let ReceiverProofV2 {
+			sender_address,
+			receiver_address,
+			receiver_signature,
+		} = data;
+		ReceiverProof {
+			sender_address: sender_address.clone(),
+			receiver_address: receiver_address.clone(),
+			receiver_signature: receiver_signature.clone(),
 		}
 	}
 }
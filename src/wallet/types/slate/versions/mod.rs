@@ -13,21 +13,42 @@
 // limitations under the License.
 
 pub mod v2;
+pub mod v3;
 
 use super::Slate;
 use crate::wallet::error::ErrorKind;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
 use std::convert::TryFrom;
 use v2::SlateV2;
+use v3::SlateV3;
 
-pub const CURRENT_SLATE_VERSION: u16 = 2;
+pub const CURRENT_SLATE_VERSION: u16 = 3;
+/// Oldest slate version this wallet still accepts
+pub const MIN_SLATE_VERSION: u16 = 2;
 pub const GRIN_BLOCK_HEADER_VERSION: u16 = 2;
 
-/// Existing versions of the slate
+/// Existing versions of the slate, ordered oldest to newest
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, PartialOrd, Eq, Ord)]
 pub enum SlateVersion {
-	/// V2 (most current)
+	/// V2 (Grin 1.1.0 - 2.x)
 	V2,
+	/// V3 (most current)
+	V3,
+}
+
+impl SlateVersion {
+	/// Versions this wallet can read and write, newest first
+	pub fn supported() -> Vec<SlateVersion> {
+		vec![SlateVersion::V3, SlateVersion::V2]
+	}
+
+	/// Highest version supported by both this wallet and the other party
+	pub fn negotiate(other: &[SlateVersion]) -> Option<SlateVersion> {
+		SlateVersion::supported()
+			.into_iter()
+			.find(|v| other.contains(v))
+	}
 }
 
 impl Default for SlateVersion {
@@ -42,24 +63,52 @@ impl TryFrom<u16> for SlateVersion {
 	fn try_from(value: u16) -> Result<Self, Self::Error> {
 		match value {
 			2 => Ok(SlateVersion::V2),
+			3 => Ok(SlateVersion::V3),
 			v => Err(ErrorKind::SlateVersion(v)),
 		}
 	}
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Clone)]
 #[serde(untagged)]
-/// Versions are ordered newest to oldest so serde attempts to
-/// deserialize newer versions first, then falls back to older versions.
+/// Newer versions only add optional fields, so the version
+/// is taken from the version info when deserializing
 pub enum VersionedSlate {
-	/// Current (Grin 1.1.0 - 2.x (current))
+	/// Current (Grin 3.0.0 - current)
+	V3(SlateV3),
+	/// Grin 1.1.0 - 2.x
 	V2(SlateV2),
 }
 
+impl<'de> Deserialize<'de> for VersionedSlate {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		use serde::de::Error;
+
+		let value = Value::deserialize(deserializer)?;
+		let version = value["version_info"]["version"]
+			.as_u64()
+			.ok_or_else(|| D::Error::custom("missing slate version"))?;
+		match version {
+			2 => SlateV2::deserialize(value)
+				.map(VersionedSlate::V2)
+				.map_err(D::Error::custom),
+			// Newer slates are read as V3, as far as they are compatible
+			v if v >= 3 => SlateV3::deserialize(value)
+				.map(VersionedSlate::V3)
+				.map_err(D::Error::custom),
+			v => Err(D::Error::custom(format!("unsupported slate version {}", v))),
+		}
+	}
+}
+
 impl VersionedSlate {
 	/// Return slate version
 	pub fn version(&self) -> SlateVersion {
 		match *self {
+			VersionedSlate::V3(_) => SlateVersion::V3,
 			VersionedSlate::V2(_) => SlateVersion::V2,
 		}
 	}
@@ -67,7 +116,16 @@ impl VersionedSlate {
 	/// convert this slate type to a specified older version
 	pub fn into_version(slate: Slate, version: SlateVersion) -> VersionedSlate {
 		match version {
-			SlateVersion::V2 => VersionedSlate::V2(slate.into()),
+			SlateVersion::V3 => {
+				let mut s = SlateV3::from(slate);
+				s.version_info.version = 3;
+				VersionedSlate::V3(s)
+			}
+			SlateVersion::V2 => {
+				let mut s = SlateV2::from(slate);
+				s.version_info.version = 2;
+				VersionedSlate::V2(s)
+			}
 		}
 	}
 }
@@ -75,10 +133,8 @@ impl VersionedSlate {
 impl From<VersionedSlate> for Slate {
 	fn from(slate: VersionedSlate) -> Slate {
 		match slate {
-			VersionedSlate::V2(s) => {
-				let s = SlateV2::from(s);
-				Slate::from(s)
-			}
+			VersionedSlate::V3(s) => Slate::from(s),
+			VersionedSlate::V2(s) => Slate::from(s),
 		}
 	}
 }
@@ -86,6 +142,7 @@ impl From<VersionedSlate> for Slate {
 impl From<&VersionedSlate> for Slate {
 	fn from(slate: &VersionedSlate) -> Slate {
 		match slate {
+			VersionedSlate::V3(s) => Slate::from(s),
 			VersionedSlate::V2(s) => Slate::from(s),
 		}
 	}
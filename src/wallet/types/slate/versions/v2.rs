@@ -34,6 +34,9 @@
 //!    version: u16
 //!    orig_verion: u16,
 //!    block_header_version: u16
+//!
+//! wallet713 adds optional rejection and receiver_proof fields, omitted when
+//! not set
 
 use super::CompatKernelFeatures;
 use grin_core::core::transaction::OutputFeatures;
@@ -72,6 +75,22 @@ pub struct SlateV2 {
 	/// insert their public data here. For now, 0 is sender and 1
 	/// is receiver, though this will change for multi-party
 	pub participant_data: Vec<ParticipantDataV2>,
+	/// Rejection reason, ignored by other wallets
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub rejection: Option<String>,
+	/// Grinbox payment proof, ignored by other wallets
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub receiver_proof: Option<ReceiverProofV2>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReceiverProofV2 {
+	/// Grinbox public key of the sender
+	pub sender_address: String,
+	/// Grinbox public key of the receiver
+	pub receiver_address: String,
+	/// Hex encoded signature of the receiver
+	pub receiver_signature: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
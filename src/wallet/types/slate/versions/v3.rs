@@ -0,0 +1,122 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Contains V3 of the slate (grin-wallet 3.0.0)
+//! Changes from V2:
+//! * Addition of payment_proof (PaymentInfo struct)
+//! * Addition of ttl_cutoff_height field
+//! * Addition of an optional coin_type field, omitted when not set
+//!
+//! Participant data, the transaction and the wallet713 rejection and
+//! receiver_proof fields are unchanged from V2
+
+use grin_core::libtx::secp_ser;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+pub use super::v2::{
+	InputV2 as InputV3, OutputV2 as OutputV3, ParticipantDataV2 as ParticipantDataV3,
+	ReceiverProofV2 as ReceiverProofV3, TransactionBodyV2 as TransactionBodyV3,
+	TransactionV2 as TransactionV3, TxKernelV2 as TxKernelV3,
+	VersionCompatInfoV2 as VersionCompatInfoV3,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SlateV3 {
+	/// Versioning info
+	pub version_info: VersionCompatInfoV3,
+	/// The number of participants intended to take part in this transaction
+	pub num_participants: usize,
+	/// Unique transaction ID, selected by sender
+	pub id: Uuid,
+	/// The core transaction data:
+	/// inputs, outputs, kernels, kernel offset
+	pub tx: TransactionV3,
+	/// base amount (excluding fee)
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub amount: u64,
+	/// fee amount
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub fee: u64,
+	/// Block height for the transaction
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub height: u64,
+	/// Lock height
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub lock_height: u64,
+	/// TTL, the block height at which wallets
+	/// should refuse to process the transaction and unlock all
+	/// associated outputs
+	#[serde(default)]
+	#[serde(with = "opt_string_or_u64")]
+	pub ttl_cutoff_height: Option<u64>,
+	/// Participant data, each participant in the transaction will
+	/// insert their public data here. For now, 0 is sender and 1
+	/// is receiver, though this will change for multi-party
+	pub participant_data: Vec<ParticipantDataV3>,
+	/// Payment Proof
+	#[serde(default)]
+	pub payment_proof: Option<PaymentInfoV3>,
+	/// Coin the slate is for, only set by wallets that handle several coins
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub coin_type: Option<String>,
+	/// Rejection reason, ignored by other wallets
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub rejection: Option<String>,
+	/// Grinbox payment proof, ignored by other wallets
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub receiver_proof: Option<ReceiverProofV3>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PaymentInfoV3 {
+	/// Hex encoded address of the sender
+	pub sender_address: String,
+	/// Hex encoded address of the receiver
+	pub receiver_address: String,
+	/// Hex encoded signature of the receiver
+	pub receiver_signature: Option<String>,
+}
+
+/// Serializes an Option<u64> to and from a string, accepting numbers as well
+pub mod opt_string_or_u64 {
+	use serde::de::Error;
+	use serde::{Deserialize, Deserializer, Serializer};
+	use serde_json::Value;
+
+	pub fn serialize<S>(num: &Option<u64>, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		match num {
+			Some(n) => serializer.serialize_str(&n.to_string()),
+			None => serializer.serialize_none(),
+		}
+	}
+
+	pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		match Value::deserialize(deserializer)? {
+			Value::Null => Ok(None),
+			Value::Number(n) => n
+				.as_u64()
+				.map(Some)
+				.ok_or_else(|| D::Error::custom("invalid number")),
+			Value::String(s) => s.parse().map(Some).map_err(D::Error::custom),
+			_ => Err(D::Error::custom("expected a number or string")),
+		}
+	}
+}
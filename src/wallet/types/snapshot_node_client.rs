@@ -0,0 +1,173 @@
+// Copyright 2019 The vault713 Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{NodeClient, NodeVersionInfo, TxWrapper};
+use crate::wallet::ErrorKind;
+use chrono::{DateTime, Utc};
+use failure::Error;
+use grin_core::core::TxKernel;
+use grin_util::from_hex;
+use grin_util::secp::pedersen::{Commitment, RangeProof};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+const SNAPSHOT_FILE: &'static str = "snapshot.json";
+
+/// Status of an output on chain, as seen by the node that created the snapshot
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SnapshotOutput {
+	/// Hex encoded commitment
+	pub commit: String,
+	/// Height of the block the output was included in
+	pub height: u64,
+	/// Output PMMR index
+	pub mmr_index: u64,
+}
+
+/// Chain state for a set of outputs, created by an online wallet
+/// and imported by an offline wallet
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OutputSnapshot {
+	/// Chain height when the snapshot was created
+	pub height: u64,
+	/// Version info of the node the snapshot was created with
+	pub node_version: Option<NodeVersionInfo>,
+	/// Creation time
+	pub created: DateTime<Utc>,
+	/// Hex encoded commitments that were requested
+	pub requested: Vec<String>,
+	/// Requested outputs that are unspent
+	pub outputs: Vec<SnapshotOutput>,
+}
+
+impl OutputSnapshot {
+	/// Location of the imported snapshot in the wallet data directory
+	pub fn path(data_path: &Path) -> PathBuf {
+		data_path.join(SNAPSHOT_FILE)
+	}
+
+	pub fn from_file(path: &Path) -> Result<Self, Error> {
+		let mut file = File::open(path)?;
+		let mut content = String::new();
+		file.read_to_string(&mut content)?;
+		let snapshot = serde_json::from_str(&content)
+			.map_err(|e| ErrorKind::GenericError(format!("Invalid snapshot file: {}", e)))?;
+		Ok(snapshot)
+	}
+
+	pub fn to_file(&self, path: &Path) -> Result<(), Error> {
+		let mut file = File::create(path)?;
+		file.write_all(serde_json::to_string_pretty(self)?.as_bytes())?;
+		Ok(())
+	}
+}
+
+/// Node client for a wallet without a node connection. Chain queries are
+/// answered from the last imported output snapshot, anything that needs
+/// a live node fails
+#[derive(Clone)]
+pub struct SnapshotNodeClient {
+	node_url: String,
+	snapshot_path: PathBuf,
+}
+
+impl SnapshotNodeClient {
+	pub fn new(data_path: &Path) -> Self {
+		Self {
+			node_url: String::from("offline"),
+			snapshot_path: OutputSnapshot::path(data_path),
+		}
+	}
+
+	fn snapshot(&self) -> Result<OutputSnapshot, Error> {
+		if !self.snapshot_path.exists() {
+			return Err(ErrorKind::Offline("no output snapshot imported".to_owned()).into());
+		}
+		OutputSnapshot::from_file(&self.snapshot_path)
+	}
+}
+
+impl NodeClient for SnapshotNodeClient {
+	fn node_url(&self) -> &str {
+		&self.node_url
+	}
+
+	fn set_node_url(&mut self, _node_url: &str) {}
+
+	fn node_api_secret(&self) -> Option<String> {
+		None
+	}
+
+	fn set_node_api_secret(&mut self, _node_api_secret: Option<String>) {}
+
+	fn get_version_info(&mut self) -> Option<NodeVersionInfo> {
+		self.snapshot().ok().and_then(|s| s.node_version)
+	}
+
+	fn post_tx(&self, _tx: &TxWrapper, _fluff: bool) -> Result<(), Error> {
+		Err(ErrorKind::Offline("post the transaction from an online wallet".to_owned()).into())
+	}
+
+	fn get_chain_height(&self) -> Result<u64, Error> {
+		Ok(self.snapshot()?.height)
+	}
+
+	/// Outputs that are not part of the snapshot are reported as missing, so
+	/// only outputs that were in the snapshot request can be trusted
+	fn get_outputs_from_node(
+		&self,
+		wallet_outputs: Vec<Commitment>,
+	) -> Result<HashMap<Commitment, (String, u64, u64)>, Error> {
+		let snapshot = self.snapshot()?;
+		let mut api_outputs = HashMap::new();
+		for out in snapshot.outputs {
+			let commit = Commitment::from_vec(
+				from_hex(out.commit.clone())
+					.map_err(|_| ErrorKind::GenericError("Invalid snapshot commit".to_owned()))?,
+			);
+			if wallet_outputs.contains(&commit) {
+				api_outputs.insert(commit, (out.commit, out.height, out.mmr_index));
+			}
+		}
+		Ok(api_outputs)
+	}
+
+	fn get_outputs_by_pmmr_index(
+		&self,
+		_start_height: u64,
+		_max_outputs: u64,
+	) -> Result<(u64, u64, Vec<(Commitment, RangeProof, bool, u64, u64)>), Error> {
+		Err(ErrorKind::Offline("scanning the chain needs a node".to_owned()).into())
+	}
+
+	fn height_range_to_pmmr_indices(
+		&self,
+		_start_height: u64,
+		_end_height: Option<u64>,
+	) -> Result<(u64, u64), Error> {
+		Err(ErrorKind::Offline("scanning the chain needs a node".to_owned()).into())
+	}
+
+	fn get_kernel(
+		&self,
+		_excess: &Commitment,
+		_min_height: Option<u64>,
+		_max_height: Option<u64>,
+	) -> Result<Option<(TxKernel, u64, u64)>, Error> {
+		Err(ErrorKind::Offline("looking up kernels needs a node".to_owned()).into())
+	}
+}
@@ -35,6 +35,9 @@ pub struct TxLogEntry {
 	/// Address of the other party
 	#[serde(default)]
 	pub address: Option<String>,
+	/// Method the slate was delivered with, if we sent it
+	#[serde(default)]
+	pub transport: Option<String>,
 	/// Time this tx entry was created
 	/// #[serde(with = "tx_date_format")]
 	pub creation_ts: DateTime<Utc>,
@@ -57,6 +60,18 @@ pub struct TxLogEntry {
 	pub fee: Option<u64>,
 	/// Public kernel excess
 	pub excess: Option<Commitment>,
+	/// Height of the block the kernel was included in
+	#[serde(default)]
+	pub kernel_height: Option<u64>,
+	/// Chain height when the transaction was finalized
+	#[serde(default)]
+	pub finalized_height: Option<u64>,
+	/// Chain height after which an unanswered send is cancelled
+	#[serde(default)]
+	pub ttl_cutoff_height: Option<u64>,
+	/// Index of our grinbox address a received slate was sent to
+	#[serde(default)]
+	pub address_index: Option<u32>,
 	/// Location of the store transaction, (reference or resending)
 	pub stored_tx: Option<String>,
 }
@@ -69,6 +84,7 @@ impl TxLogEntry {
 			tx_type: t,
 			id: id,
 			address: None,
+			transport: None,
 			tx_slate_id: None,
 			creation_ts: Utc::now(),
 			confirmation_ts: None,
@@ -79,6 +95,10 @@ impl TxLogEntry {
 			num_outputs: 0,
 			fee: None,
 			excess: None,
+			kernel_height: None,
+			finalized_height: None,
+			ttl_cutoff_height: None,
+			address_index: None,
 			stored_tx: None,
 		}
 	}
@@ -45,20 +45,62 @@ pub enum ErrorKind {
 	ParseSlate,
 }
 
+/// Proof of a payment to `address`. Either the grinbox message with the
+/// slate returned by the receiver, or the receiver signature over the amount,
+/// kernel excess and sender address (a payment proof)
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TxProof {
 	pub address: GrinboxAddress,
+	#[serde(default)]
 	pub message: String,
+	#[serde(default)]
 	pub challenge: String,
 	pub signature: Signature,
+	#[serde(default)]
 	pub key: [u8; 32],
 	pub amount: u64,
 	pub fee: u64,
 	pub inputs: Vec<Commitment>,
 	pub outputs: Vec<Commitment>,
+	/// Sender address, only set on payment proofs
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub sender: Option<GrinboxAddress>,
+	/// Kernel excess, only set on payment proofs
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub excess: Option<Commitment>,
 }
 
 impl TxProof {
+	/// The message the receiver signs for a payment proof
+	pub fn payment_message(amount: u64, excess: &Commitment, sender: &GrinboxAddress) -> String {
+		format!("{}|{}|{}", amount, excess.to_hex(), sender.public_key)
+	}
+
+	/// Whether this is a payment proof rather than a grinbox proof
+	pub fn is_payment(&self) -> bool {
+		self.excess.is_some()
+	}
+
+	/// Check the receiver signature of a payment proof and
+	/// return the sender address and kernel excess
+	pub fn verify_payment(&self) -> Result<(GrinboxAddress, Commitment), ErrorKind> {
+		let (sender, excess) = match (&self.sender, &self.excess) {
+			(Some(s), Some(e)) => (s, e),
+			_ => return Err(ErrorKind::VerifySignature),
+		};
+
+		let public_key = self
+			.address
+			.public_key()
+			.map_err(|_| ErrorKind::ParsePublicKey)?;
+
+		let message = Self::payment_message(self.amount, excess, sender);
+		verify_signature(&message, &self.signature, &public_key)
+			.map_err(|_| ErrorKind::VerifySignature)?;
+
+		Ok((sender.clone(), excess.clone()))
+	}
+
 	pub fn verify_extract(
 		&self,
 		expected_destination: Option<&GrinboxAddress>,
@@ -126,10 +168,43 @@ impl TxProof {
 			fee: 0,
 			inputs: vec![],
 			outputs: vec![],
+			sender: None,
+			excess: None,
 		};
 
 		let (_, slate) = proof.verify_extract(expected_destination)?;
 
 		Ok((slate, proof))
 	}
+
+	pub fn from_payment(
+		receiver: String,
+		signature: String,
+		sender: GrinboxAddress,
+		amount: u64,
+		excess: Commitment,
+	) -> Result<TxProof, ErrorKind> {
+		let address =
+			GrinboxAddress::from_str(receiver.as_str()).map_err(|_| ErrorKind::ParseAddress)?;
+		let signature =
+			Signature::from_hex(signature.as_str()).map_err(|_| ErrorKind::ParseSignature)?;
+
+		let proof = TxProof {
+			address,
+			message: String::new(),
+			challenge: String::new(),
+			signature,
+			key: [0; 32],
+			amount,
+			fee: 0,
+			inputs: vec![],
+			outputs: vec![],
+			sender: Some(sender),
+			excess: Some(excess),
+		};
+
+		proof.verify_payment()?;
+
+		Ok(proof)
+	}
 }
@@ -13,8 +13,9 @@
 // limitations under the License.
 
 use super::{
-	AcctPathMapping, Context, Identifier, Keychain, NodeClient, OutputData, Result, Transaction,
-	TxLogEntry, TxProof, WalletBackendBatch,
+	AcctPathMapping, Context, Identifier, IncomingSlate, Keychain, NodeClient, OutboxEntry,
+	OutputData, PaymentRequest, Result, ScanMonitor, ScanStart, Transaction, TxLogEntry, TxProof,
+	WalletBackendBatch, WalletEvent, WebhookEntry,
 };
 use grin_util::ZeroingString;
 
@@ -49,8 +50,13 @@ where
 
 	fn open_with_credentials(&mut self) -> Result<()>;
 	fn close(&mut self) -> Result<()>;
-	fn restore(&mut self) -> Result<()>;
-	fn check_repair(&mut self, delete_unconfirmed: bool) -> Result<()>;
+	fn restore(&mut self, start: &ScanStart, monitor: &ScanMonitor) -> Result<()>;
+	fn check_repair(
+		&mut self,
+		delete_unconfirmed: bool,
+		start: &ScanStart,
+		monitor: &ScanMonitor,
+	) -> Result<()>;
 	fn get_parent_key_id(&self) -> Identifier;
 	fn set_parent_key_id(&mut self, id: &Identifier);
 	fn set_parent_key_id_by_name(&mut self, label: &str) -> Result<()>;
@@ -62,6 +68,10 @@ where
 	fn get_private_context(&mut self, slate_id: &[u8], participant_id: usize) -> Result<Context>;
 	fn get_acct_path(&self, label: &str) -> Result<Option<AcctPathMapping>>;
 	fn get_last_confirmed_height(&self) -> Result<u64>;
+	/// Last PMMR index processed by an interrupted scan, if any
+	fn get_scan_checkpoint(&self, scan: &str) -> Result<Option<u64>>;
+	/// Highest PMMR index up to which the whole chain has been scanned
+	fn get_last_scanned_index(&self) -> Result<Option<u64>>;
 	fn get_stored_tx(&self, uuid: &str) -> Result<Option<Transaction>>;
 	fn has_stored_tx_proof(&self, uuid: &str) -> Result<bool>;
 	fn get_stored_tx_proof(&self, uuid: &str) -> Result<Option<TxProof>>;
@@ -69,5 +79,15 @@ where
 	fn outputs<'a>(&'a self) -> Result<Box<dyn Iterator<Item = OutputData> + 'a>>;
 	fn tx_logs<'a>(&'a self) -> Result<Box<dyn Iterator<Item = TxLogEntry> + 'a>>;
 	fn accounts<'a>(&'a self) -> Result<Box<dyn Iterator<Item = AcctPathMapping> + 'a>>;
+	/// Slates waiting to be delivered over grinbox or keybase
+	fn outbox<'a>(&'a self) -> Result<Box<dyn Iterator<Item = OutboxEntry> + 'a>>;
+	/// Received slates waiting for approval
+	fn incoming<'a>(&'a self) -> Result<Box<dyn Iterator<Item = IncomingSlate> + 'a>>;
+	/// Payment requests, ordered by address index
+	fn payment_requests<'a>(&'a self) -> Result<Box<dyn Iterator<Item = PaymentRequest> + 'a>>;
+	/// Wallet events waiting to be posted to webhooks
+	fn webhook_queue<'a>(&'a self) -> Result<Box<dyn Iterator<Item = WebhookEntry> + 'a>>;
+	/// Events queued by batches committed since the last call
+	fn take_events(&mut self) -> Vec<WalletEvent>;
 	fn batch<'a>(&'a self) -> Result<Box<dyn WalletBackendBatch<K> + 'a>>;
 }
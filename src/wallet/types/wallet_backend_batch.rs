@@ -13,9 +13,11 @@
 // limitations under the License.
 
 use super::{
-	AcctPathMapping, Context, Identifier, Keychain, OutputData, Result, Transaction, TxLogEntry,
+	AcctPathMapping, Context, Identifier, IncomingSlate, Keychain, OutboxEntry, OutputData,
+	PaymentRequest, Result, Transaction, TxLogEntry, WalletEvent, WebhookEntry,
 };
 use crate::wallet::types::TxProof;
+use uuid::Uuid;
 
 pub trait WalletBackendBatch<K>
 where
@@ -27,6 +29,9 @@ where
 	fn lock_output(&mut self, out: &mut OutputData) -> Result<()>;
 	fn save_child_index(&mut self, parent_key_id: &Identifier, index: u32) -> Result<()>;
 	fn save_last_confirmed_height(&mut self, height: u64) -> Result<()>;
+	fn save_scan_checkpoint(&mut self, scan: &str, index: u64) -> Result<()>;
+	fn delete_scan_checkpoint(&mut self, scan: &str) -> Result<()>;
+	fn save_last_scanned_index(&mut self, index: u64) -> Result<()>;
 	fn next_tx_log_id(&mut self, parent_key_id: &Identifier) -> Result<u32>;
 	fn save_tx_log_entry(&mut self, t: &TxLogEntry) -> Result<()>;
 	fn save_acct_path(&mut self, mapping: &AcctPathMapping) -> Result<()>;
@@ -37,6 +42,16 @@ where
 		ctx: &Context,
 	) -> Result<()>;
 	fn delete_private_context(&mut self, slate_id: &[u8], participant_id: usize) -> Result<()>;
+	fn save_outbox_entry(&mut self, entry: &OutboxEntry) -> Result<()>;
+	fn delete_outbox_entry(&mut self, slate_id: &Uuid) -> Result<()>;
+	fn save_incoming_slate(&mut self, entry: &IncomingSlate) -> Result<()>;
+	fn delete_incoming_slate(&mut self, slate_id: &Uuid) -> Result<()>;
+	fn save_payment_request(&mut self, request: &PaymentRequest) -> Result<()>;
+	fn save_webhook_entry(&mut self, entry: &WebhookEntry) -> Result<()>;
+	fn delete_webhook_entry(&mut self, event_id: &Uuid) -> Result<()>;
+
+	/// Queue an event, handed to the wallet API once the batch is committed
+	fn queue_event(&mut self, event: WalletEvent) -> Result<()>;
 	fn store_tx(&self, uuid: &str, tx: &Transaction) -> Result<()>;
 	fn store_tx_proof(&self, uuid: &str, tx_proof: &TxProof) -> Result<()>;
 	fn commit(&mut self) -> Result<()>;
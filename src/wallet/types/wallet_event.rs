@@ -0,0 +1,115 @@
+// Copyright 2019 The vault713 Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{TxLogEntry, TxLogEntryType, WalletInfo};
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use uuid::Uuid;
+
+/// Kind of a wallet event, named as in the `events` list of a webhook
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WalletEventType {
+	/// A new slate arrived from a peer or was read from a file
+	SlateReceived,
+	/// A received slate was signed and sent back
+	ResponseSent,
+	/// The answer to a sent slate was finalized
+	SlateFinalized,
+	/// A transaction was posted to the node
+	TxPosted,
+	/// The kernel or an output of a transaction is on chain
+	TxConfirmed,
+	/// A transaction was cancelled
+	TxCancelled,
+	/// A grinbox or keybase listener lost its connection
+	ListenerDisconnected,
+	/// A grinbox or keybase listener connected again
+	ListenerReestablished,
+	/// The amounts of the wallet summary changed
+	BalanceChanged,
+	/// Sent by `webhooks test`
+	Test,
+}
+
+impl fmt::Display for WalletEventType {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			WalletEventType::SlateReceived => write!(f, "slate_received"),
+			WalletEventType::ResponseSent => write!(f, "response_sent"),
+			WalletEventType::SlateFinalized => write!(f, "slate_finalized"),
+			WalletEventType::TxPosted => write!(f, "tx_posted"),
+			WalletEventType::TxConfirmed => write!(f, "tx_confirmed"),
+			WalletEventType::TxCancelled => write!(f, "tx_cancelled"),
+			WalletEventType::ListenerDisconnected => write!(f, "listener_disconnected"),
+			WalletEventType::ListenerReestablished => write!(f, "listener_reestablished"),
+			WalletEventType::BalanceChanged => write!(f, "balance_changed"),
+			WalletEventType::Test => write!(f, "test"),
+		}
+	}
+}
+
+/// Body of the request posted to webhooks
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WalletEvent {
+	/// Unique id, the same for every retry
+	pub id: Uuid,
+	pub event: WalletEventType,
+	pub creation_ts: DateTime<Utc>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub tx_id: Option<u32>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub slate_id: Option<Uuid>,
+	/// Amount in nanogrins, excluding the fee
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub amount: Option<u64>,
+	/// Address of the other party, or of the listener
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub address: Option<String>,
+	/// Summary of the wallet after the change
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub balance: Option<WalletInfo>,
+}
+
+impl WalletEvent {
+	pub fn new(event: WalletEventType) -> Self {
+		Self {
+			id: Uuid::new_v4(),
+			event,
+			creation_ts: Utc::now(),
+			tx_id: None,
+			slate_id: None,
+			amount: None,
+			address: None,
+			balance: None,
+		}
+	}
+
+	/// Event about a transaction of the log
+	pub fn from_tx(event: WalletEventType, tx: &TxLogEntry) -> Self {
+		let amount = match tx.tx_type {
+			TxLogEntryType::TxSent | TxLogEntryType::TxSentCancelled => tx
+				.amount_debited
+				.saturating_sub(tx.amount_credited + tx.fee.unwrap_or(0)),
+			_ => tx.amount_credited,
+		};
+		let mut e = Self::new(event);
+		e.tx_id = Some(tx.id);
+		e.slate_id = tx.tx_slate_id;
+		e.amount = Some(amount);
+		e.address = tx.address.clone();
+		e
+	}
+}
@@ -0,0 +1,84 @@
+// Copyright 2019 The vault713 Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::WalletEvent;
+use chrono::prelude::*;
+use chrono::Duration;
+use grin_core::ser;
+use serde::{Deserialize, Serialize};
+
+/// Delay before the first retry, doubled after every failed attempt
+const RETRY_DELAY_SECS: i64 = 30;
+/// Upper bound of the delay between two retries
+const MAX_RETRY_DELAY_SECS: i64 = 3600;
+/// Events are dropped after this many failed attempts
+const MAX_ATTEMPTS: u32 = 20;
+
+/// Wallet event waiting to be posted to the configured webhooks
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WebhookEntry {
+	pub event: WalletEvent,
+	/// Webhook urls that already accepted the event
+	pub delivered: Vec<String>,
+	/// Number of failed delivery attempts
+	pub attempts: u32,
+	/// Error of the last failed attempt
+	pub last_error: Option<String>,
+	/// Earliest time of the next attempt
+	pub next_attempt_ts: DateTime<Utc>,
+}
+
+impl WebhookEntry {
+	pub fn new(event: WalletEvent) -> Self {
+		Self {
+			next_attempt_ts: event.creation_ts,
+			event,
+			delivered: Vec::new(),
+			attempts: 0,
+			last_error: None,
+		}
+	}
+
+	/// Whether the backoff of the last failed attempt has passed
+	pub fn is_due(&self) -> bool {
+		self.next_attempt_ts <= Utc::now()
+	}
+
+	/// Whether retrying is pointless
+	pub fn is_exhausted(&self) -> bool {
+		self.attempts >= MAX_ATTEMPTS
+	}
+
+	/// Record a failed attempt and schedule the next one
+	pub fn failed(&mut self, error: String) {
+		let shift = self.attempts.min(7);
+		let delay = (RETRY_DELAY_SECS << shift).min(MAX_RETRY_DELAY_SECS);
+		self.attempts += 1;
+		self.last_error = Some(error);
+		self.next_attempt_ts = Utc::now() + Duration::seconds(delay);
+	}
+}
+
+impl ser::Writeable for WebhookEntry {
+	fn write<W: ser::Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		writer.write_bytes(&serde_json::to_vec(self).map_err(|_| ser::Error::CorruptedData)?)
+	}
+}
+
+impl ser::Readable for WebhookEntry {
+	fn read(reader: &mut dyn ser::Reader) -> Result<WebhookEntry, ser::Error> {
+		let data = reader.read_bytes_len_prefix()?;
+		serde_json::from_slice(&data[..]).map_err(|_| ser::Error::CorruptedData)
+	}
+}